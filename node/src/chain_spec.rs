@@ -1,6 +1,6 @@
 use node_template_runtime::{
-	AccountId, AuraConfig, BalancesConfig, GenesisConfig, GrandpaConfig, Signature, SudoConfig,
-	SystemConfig, WASM_BINARY,
+	AccountId, AuraConfig, BalancesConfig, CouncilConfig, CouncilMembershipConfig, DemocracyConfig,
+	GenesisConfig, GrandpaConfig, Signature, SystemConfig, WASM_BINARY,
 };
 use sc_service::ChainType;
 use sp_consensus_aura::sr25519::AuthorityId as AuraId;
@@ -45,23 +45,7 @@ pub fn development_config() -> Result<ChainSpec, String> {
 		// ID
 		"dev",
 		ChainType::Development,
-		move || {
-			testnet_genesis(
-				wasm_binary,
-				// Initial PoA authorities
-				vec![authority_keys_from_seed("Alice")],
-				// Sudo account
-				get_account_id_from_seed::<sr25519::Public>("Alice"),
-				// Pre-funded accounts
-				vec![
-					get_account_id_from_seed::<sr25519::Public>("Alice"),
-					get_account_id_from_seed::<sr25519::Public>("Bob"),
-					get_account_id_from_seed::<sr25519::Public>("Alice//stash"),
-					get_account_id_from_seed::<sr25519::Public>("Bob//stash"),
-				],
-				true,
-			)
-		},
+		move || development_genesis(wasm_binary),
 		// Bootnodes
 		vec![],
 		// Telemetry
@@ -84,31 +68,7 @@ pub fn local_testnet_config() -> Result<ChainSpec, String> {
 		// ID
 		"local_testnet",
 		ChainType::Local,
-		move || {
-			testnet_genesis(
-				wasm_binary,
-				// Initial PoA authorities
-				vec![authority_keys_from_seed("Alice"), authority_keys_from_seed("Bob")],
-				// Sudo account
-				get_account_id_from_seed::<sr25519::Public>("Alice"),
-				// Pre-funded accounts
-				vec![
-					get_account_id_from_seed::<sr25519::Public>("Alice"),
-					get_account_id_from_seed::<sr25519::Public>("Bob"),
-					get_account_id_from_seed::<sr25519::Public>("Charlie"),
-					get_account_id_from_seed::<sr25519::Public>("Dave"),
-					get_account_id_from_seed::<sr25519::Public>("Eve"),
-					get_account_id_from_seed::<sr25519::Public>("Ferdie"),
-					get_account_id_from_seed::<sr25519::Public>("Alice//stash"),
-					get_account_id_from_seed::<sr25519::Public>("Bob//stash"),
-					get_account_id_from_seed::<sr25519::Public>("Charlie//stash"),
-					get_account_id_from_seed::<sr25519::Public>("Dave//stash"),
-					get_account_id_from_seed::<sr25519::Public>("Eve//stash"),
-					get_account_id_from_seed::<sr25519::Public>("Ferdie//stash"),
-				],
-				true,
-			)
-		},
+		move || local_testnet_genesis(wasm_binary),
 		// Bootnodes
 		vec![],
 		// Telemetry
@@ -122,11 +82,59 @@ pub fn local_testnet_config() -> Result<ChainSpec, String> {
 	))
 }
 
+fn development_genesis(wasm_binary: &[u8]) -> GenesisConfig {
+	testnet_genesis(
+		wasm_binary,
+		// Initial PoA authorities
+		vec![authority_keys_from_seed("Alice")],
+		// Initial council
+		vec![get_account_id_from_seed::<sr25519::Public>("Alice")],
+		// Pre-funded accounts
+		vec![
+			get_account_id_from_seed::<sr25519::Public>("Alice"),
+			get_account_id_from_seed::<sr25519::Public>("Bob"),
+			get_account_id_from_seed::<sr25519::Public>("Alice//stash"),
+			get_account_id_from_seed::<sr25519::Public>("Bob//stash"),
+		],
+		true,
+	)
+}
+
+fn local_testnet_genesis(wasm_binary: &[u8]) -> GenesisConfig {
+	testnet_genesis(
+		wasm_binary,
+		// Initial PoA authorities
+		vec![authority_keys_from_seed("Alice"), authority_keys_from_seed("Bob")],
+		// Initial council
+		vec![
+			get_account_id_from_seed::<sr25519::Public>("Alice"),
+			get_account_id_from_seed::<sr25519::Public>("Bob"),
+			get_account_id_from_seed::<sr25519::Public>("Charlie"),
+		],
+		// Pre-funded accounts
+		vec![
+			get_account_id_from_seed::<sr25519::Public>("Alice"),
+			get_account_id_from_seed::<sr25519::Public>("Bob"),
+			get_account_id_from_seed::<sr25519::Public>("Charlie"),
+			get_account_id_from_seed::<sr25519::Public>("Dave"),
+			get_account_id_from_seed::<sr25519::Public>("Eve"),
+			get_account_id_from_seed::<sr25519::Public>("Ferdie"),
+			get_account_id_from_seed::<sr25519::Public>("Alice//stash"),
+			get_account_id_from_seed::<sr25519::Public>("Bob//stash"),
+			get_account_id_from_seed::<sr25519::Public>("Charlie//stash"),
+			get_account_id_from_seed::<sr25519::Public>("Dave//stash"),
+			get_account_id_from_seed::<sr25519::Public>("Eve//stash"),
+			get_account_id_from_seed::<sr25519::Public>("Ferdie//stash"),
+		],
+		true,
+	)
+}
+
 /// Configure initial storage state for FRAME modules.
 fn testnet_genesis(
 	wasm_binary: &[u8],
 	initial_authorities: Vec<(AuraId, GrandpaId)>,
-	root_key: AccountId,
+	council: Vec<AccountId>,
 	endowed_accounts: Vec<AccountId>,
 	_enable_println: bool,
 ) -> GenesisConfig {
@@ -146,11 +154,48 @@ fn testnet_genesis(
 		grandpa: GrandpaConfig {
 			authorities: initial_authorities.iter().map(|x| (x.1.clone(), 1)).collect(),
 		},
-		sudo: SudoConfig {
-			// Assign network admin rights.
-			key: root_key,
+		council: CouncilConfig::default(),
+		council_membership: CouncilMembershipConfig {
+			// the membership pallet initializes the members of the council
+			members: council,
+			phantom: Default::default(),
 		},
+		// Root is only reached through the referenda of the democracy pallet
+		democracy: DemocracyConfig::default(),
         kitties: Default::default(),
         nft: Default::default(),
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use node_template_runtime::{Council, CouncilMembership, Democracy};
+	use sp_runtime::BuildStorage;
+
+	#[test]
+	fn local_testnet_has_a_council_and_no_sudo_key() {
+		let storage = local_testnet_genesis(&[]).build_storage().unwrap();
+
+		sp_io::TestExternalities::new(storage).execute_with(|| {
+			// the membership pallet initializes the members of the council
+			assert_eq!(CouncilMembership::members().len(), 3);
+			assert_eq!(Council::members(), CouncilMembership::members());
+			assert!(Council::members().contains(&get_account_id_from_seed::<sr25519::Public>("Charlie")));
+
+			// Root can only be reached with a referendum, and none is pending
+			assert_eq!(Democracy::referendum_count(), 0);
+			assert_eq!(Democracy::public_prop_count(), 0);
+		});
+	}
+
+	#[test]
+	fn development_council_is_alice() {
+		let storage = development_genesis(&[]).build_storage().unwrap();
+
+		sp_io::TestExternalities::new(storage).execute_with(|| {
+			let alice = get_account_id_from_seed::<sr25519::Public>("Alice");
+			assert_eq!(Council::members(), vec![alice.clone()]);
+		});
+	}
+}
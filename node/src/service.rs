@@ -176,12 +176,14 @@ pub fn new_full(mut config: Configuration) -> Result<TaskManager, ServiceError>
 	let rpc_extensions_builder = {
 		let client = client.clone();
 		let pool = transaction_pool.clone();
+		let offchain_storage = sc_client_api::Backend::offchain_storage(&*backend);
 
 		Box::new(move |deny_unsafe, _| {
 			let deps = crate::rpc::FullDeps {
 				client: client.clone(),
 				pool: pool.clone(),
 				deny_unsafe,
+				offchain_storage: offchain_storage.clone(),
 			};
 
 			crate::rpc::create_full(deps)
@@ -0,0 +1,229 @@
+//! RPC interface for the kitties pallet.
+
+use std::{marker::PhantomData, sync::Arc};
+
+use codec::{Codec, Encode};
+use jsonrpc_core::{Error as RpcError, ErrorCode, Result};
+use jsonrpc_derive::rpc;
+use pallet_kitties::{KittiesPage, Kitty, KittyClassData, OwnershipRecord, MINER_ENABLED_KEY, MINER_INTERVAL_KEY, MINER_MAX_ITERATIONS_KEY};
+use parking_lot::RwLock;
+use sc_rpc_api::DenyUnsafe;
+use serde::{de::DeserializeOwned, Serialize};
+use sp_api::ProvideRuntimeApi;
+use sp_blockchain::HeaderBackend;
+use sp_core::offchain::OffchainStorage;
+use sp_runtime::{generic::BlockId, traits::Block as BlockT};
+
+pub use pallet_kitties_runtime_api::KittiesApi as KittiesRuntimeApi;
+
+/// Error code for failed runtime API calls
+const RUNTIME_ERROR: i64 = 1;
+
+/// Read access to the collections, the kitties, their owners and the marketplace
+#[rpc]
+pub trait KittiesApi<BlockHash, AccountId, ClassId, KittyIndex, Balance, BlockNumber> {
+	/// Get the settings of a collection
+	#[rpc(name = "kitties_collection")]
+	fn collection(&self, class_id: ClassId, at: Option<BlockHash>) -> Result<Option<KittyClassData>>;
+
+	/// Get a kitty by its collection and id
+	#[rpc(name = "kitties_kitty")]
+	fn kitty(&self, class_id: ClassId, kitty_id: KittyIndex, at: Option<BlockHash>) -> Result<Option<Kitty>>;
+
+	/// Get the owner of a kitty
+	#[rpc(name = "kitties_ownerOf")]
+	fn owner_of(&self, class_id: ClassId, kitty_id: KittyIndex, at: Option<BlockHash>) -> Result<Option<AccountId>>;
+
+	/// Get the price of a kitty. None means not for sale.
+	#[rpc(name = "kitties_priceOf")]
+	fn price_of(&self, class_id: ClassId, kitty_id: KittyIndex, at: Option<BlockHash>) -> Result<Option<Balance>>;
+
+	/// Get at most `limit` kitties of `owner` in a collection with an id higher than `after`.
+	/// Use the `next` cursor of a page to get the page after it.
+	#[rpc(name = "kitties_kittiesOf")]
+	fn kitties_of(
+		&self,
+		owner: AccountId,
+		class_id: ClassId,
+		after: Option<KittyIndex>,
+		limit: u32,
+		at: Option<BlockHash>,
+	) -> Result<KittiesPage<KittyIndex>>;
+
+	/// Get at most `limit` kitties of a collection that are for sale, cheapest first,
+	/// skipping the first `start` listings
+	#[rpc(name = "kitties_listings")]
+	fn listings(
+		&self,
+		class_id: ClassId,
+		start: u32,
+		limit: u32,
+		at: Option<BlockHash>,
+	) -> Result<Vec<(KittyIndex, Balance)>>;
+
+	/// Get the lowest price of all kitties of a collection that are for sale
+	#[rpc(name = "kitties_floorPrice")]
+	fn floor_price(&self, class_id: ClassId, at: Option<BlockHash>) -> Result<Option<Balance>>;
+
+	/// Get the last owners of a kitty, oldest first
+	#[rpc(name = "kitties_ownershipHistory")]
+	fn ownership_history(
+		&self,
+		class_id: ClassId,
+		kitty_id: KittyIndex,
+		at: Option<BlockHash>,
+	) -> Result<Vec<OwnershipRecord<AccountId, Balance, BlockNumber>>>;
+}
+
+/// Implements the `KittiesApi` RPC by calling into the runtime
+pub struct Kitties<C, B> {
+	client: Arc<C>,
+	_marker: PhantomData<B>,
+}
+
+impl<C, B> Kitties<C, B> {
+	/// Create a new instance of the kitties RPC
+	pub fn new(client: Arc<C>) -> Self {
+		Kitties { client, _marker: Default::default() }
+	}
+}
+
+fn runtime_error(error: impl std::fmt::Debug) -> RpcError {
+	RpcError {
+		code: ErrorCode::ServerError(RUNTIME_ERROR),
+		message: "Unable to query the kitties runtime API.".into(),
+		data: Some(format!("{:?}", error).into()),
+	}
+}
+
+impl<C, Block, AccountId, ClassId, KittyIndex, Balance, BlockNumber>
+	KittiesApi<<Block as BlockT>::Hash, AccountId, ClassId, KittyIndex, Balance, BlockNumber> for Kitties<C, Block>
+where
+	Block: BlockT,
+	C: ProvideRuntimeApi<Block> + HeaderBackend<Block> + Send + Sync + 'static,
+	C::Api: KittiesRuntimeApi<Block, AccountId, ClassId, KittyIndex, Balance, BlockNumber>,
+	AccountId: Codec + Serialize + DeserializeOwned + Send + Sync + 'static,
+	ClassId: Codec + Serialize + DeserializeOwned + Send + Sync + 'static,
+	KittyIndex: Codec + Serialize + DeserializeOwned + Send + Sync + 'static,
+	Balance: Codec + Serialize + DeserializeOwned + Send + Sync + 'static,
+	BlockNumber: Codec + Serialize + DeserializeOwned + Send + Sync + 'static,
+{
+	fn collection(&self, class_id: ClassId, at: Option<Block::Hash>) -> Result<Option<KittyClassData>> {
+		let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+		self.client.runtime_api().collection(&at, class_id).map_err(runtime_error)
+	}
+
+	fn kitty(&self, class_id: ClassId, kitty_id: KittyIndex, at: Option<Block::Hash>) -> Result<Option<Kitty>> {
+		let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+		self.client.runtime_api().kitty(&at, class_id, kitty_id).map_err(runtime_error)
+	}
+
+	fn owner_of(&self, class_id: ClassId, kitty_id: KittyIndex, at: Option<Block::Hash>) -> Result<Option<AccountId>> {
+		let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+		self.client.runtime_api().owner_of(&at, class_id, kitty_id).map_err(runtime_error)
+	}
+
+	fn price_of(&self, class_id: ClassId, kitty_id: KittyIndex, at: Option<Block::Hash>) -> Result<Option<Balance>> {
+		let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+		self.client.runtime_api().price_of(&at, class_id, kitty_id).map_err(runtime_error)
+	}
+
+	fn kitties_of(
+		&self,
+		owner: AccountId,
+		class_id: ClassId,
+		after: Option<KittyIndex>,
+		limit: u32,
+		at: Option<Block::Hash>,
+	) -> Result<KittiesPage<KittyIndex>> {
+		let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+		self.client.runtime_api().kitties_of(&at, owner, class_id, after, limit).map_err(runtime_error)
+	}
+
+	fn listings(
+		&self,
+		class_id: ClassId,
+		start: u32,
+		limit: u32,
+		at: Option<Block::Hash>,
+	) -> Result<Vec<(KittyIndex, Balance)>> {
+		let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+		self.client.runtime_api().listings(&at, class_id, start, limit).map_err(runtime_error)
+	}
+
+	fn floor_price(&self, class_id: ClassId, at: Option<Block::Hash>) -> Result<Option<Balance>> {
+		let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+		self.client.runtime_api().floor_price(&at, class_id).map_err(runtime_error)
+	}
+
+	fn ownership_history(
+		&self,
+		class_id: ClassId,
+		kitty_id: KittyIndex,
+		at: Option<Block::Hash>,
+	) -> Result<Vec<OwnershipRecord<AccountId, Balance, BlockNumber>>> {
+		let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+		self.client.runtime_api().ownership_history(&at, class_id, kitty_id).map_err(runtime_error)
+	}
+}
+
+/// Node specific settings of the kitties offchain worker
+#[rpc]
+pub trait MinerApi {
+	/// Change the auto breed miner settings of this node.
+	/// Values that are `None` are left unchanged.
+	///
+	/// This method is unsafe, because it changes the behaviour of the node.
+	#[rpc(name = "kitties_setMinerConfig")]
+	fn set_miner_config(
+		&self,
+		enabled: Option<bool>,
+		max_iterations: Option<u32>,
+		interval: Option<u32>,
+	) -> Result<()>;
+}
+
+/// Writes the miner settings to the offchain local storage of the node,
+/// where the offchain worker reads them from
+pub struct Miner<T: OffchainStorage> {
+	storage: Arc<RwLock<T>>,
+	deny_unsafe: DenyUnsafe,
+}
+
+impl<T: OffchainStorage> Miner<T> {
+	/// Create a new instance of the miner RPC
+	pub fn new(storage: T, deny_unsafe: DenyUnsafe) -> Self {
+		Miner { storage: Arc::new(RwLock::new(storage)), deny_unsafe }
+	}
+}
+
+impl<T: OffchainStorage + 'static> MinerApi for Miner<T> {
+	fn set_miner_config(
+		&self,
+		enabled: Option<bool>,
+		max_iterations: Option<u32>,
+		interval: Option<u32>,
+	) -> Result<()> {
+		self.deny_unsafe.check_if_safe()?;
+
+		if interval == Some(0) {
+			return Err(RpcError::invalid_params("interval must be at least 1 block"))
+		}
+
+		// the offchain worker reads from the persistent offchain storage
+		let prefix = sp_offchain::STORAGE_PREFIX;
+		let mut storage = self.storage.write();
+
+		if let Some(enabled) = enabled {
+			storage.set(prefix, MINER_ENABLED_KEY, &enabled.encode());
+		}
+		if let Some(max_iterations) = max_iterations {
+			storage.set(prefix, MINER_MAX_ITERATIONS_KEY, &max_iterations.encode());
+		}
+		if let Some(interval) = interval {
+			storage.set(prefix, MINER_INTERVAL_KEY, &interval.encode());
+		}
+
+		Ok(())
+	}
+}
@@ -0,0 +1,38 @@
+//! Runtime API definition for the kitties pallet.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use codec::Codec;
+use sp_std::prelude::*;
+
+pub use pallet_kitties::{KittiesPage, Kitty, KittyClassData, OwnershipRecord};
+
+sp_api::decl_runtime_apis! {
+	/// Read access to the collections, the kitties, their owners and the marketplace
+	pub trait KittiesApi<AccountId, ClassId, KittyIndex, Balance, BlockNumber> where
+		AccountId: Codec,
+		ClassId: Codec,
+		KittyIndex: Codec,
+		Balance: Codec,
+		BlockNumber: Codec,
+	{
+		/// Get the settings of a collection
+		fn collection(class_id: ClassId) -> Option<KittyClassData>;
+		/// Get a kitty by its collection and id
+		fn kitty(class_id: ClassId, kitty_id: KittyIndex) -> Option<Kitty>;
+		/// Get the owner of a kitty
+		fn owner_of(class_id: ClassId, kitty_id: KittyIndex) -> Option<AccountId>;
+		/// Get the price of a kitty. None means not for sale.
+		fn price_of(class_id: ClassId, kitty_id: KittyIndex) -> Option<Balance>;
+		/// Get at most `limit` kitties of `owner` in a collection with an id higher than `after`.
+		/// Use the `next` cursor of a page to get the page after it.
+		fn kitties_of(owner: AccountId, class_id: ClassId, after: Option<KittyIndex>, limit: u32) -> KittiesPage<KittyIndex>;
+		/// Get at most `limit` kitties of a collection that are for sale, cheapest first,
+		/// skipping the first `start` listings
+		fn listings(class_id: ClassId, start: u32, limit: u32) -> Vec<(KittyIndex, Balance)>;
+		/// Get the lowest price of all kitties of a collection that are for sale
+		fn floor_price(class_id: ClassId) -> Option<Balance>;
+		/// Get the last owners of a kitty, oldest first
+		fn ownership_history(class_id: ClassId, kitty_id: KittyIndex) -> Vec<OwnershipRecord<AccountId, Balance, BlockNumber>>;
+	}
+}
@@ -2,56 +2,496 @@
 use super::*;
 
 use frame_system::RawOrigin;
-use frame_benchmarking::{ benchmarks, impl_benchmark_test_suite, whitelisted_caller, account };
+use frame_support::unsigned::ValidateUnsigned;
+use sp_runtime::traits::{Bounded, Saturating};
+use frame_benchmarking::{ benchmarks_instance_pallet, impl_benchmark_test_suite, whitelisted_caller, account };
+
+// create a collection without a supply cap that allows breeding, and raise the lengths of the
+// order books and the ownership histories to their caps, which the weights are benchmarked at
+fn setup_collection<T: Config<I>, I: 'static>() -> Result<ClassIdOf<T, I>, &'static str> {
+    Parameters::<T, I>::mutate(|parameters| {
+        parameters.max_history_length = MAX_HISTORY_LENGTH;
+        parameters.max_buy_orders = MAX_BUY_ORDERS;
+        parameters.max_floor_bids = MAX_FLOOR_BIDS;
+    });
+
+    let class_id = T::Nft::create_class(&Default::default(), KittyClassData::default())?;
+    Collections::<T, I>::insert(class_id, ());
+    Ok(class_id)
+}
+
+// fill the ownership history of a kitty, so the next change of its owner drops the oldest record
+fn setup_history<T: Config<I>, I: 'static>(class_id: ClassIdOf<T, I>, kitty_id: KittyIndexOf<T, I>) {
+    let history = (0..MAX_HISTORY_LENGTH).map(|i| OwnershipRecord {
+        from: Some(account("owner", i, 2)),
+        to: account("owner", i + 1, 2),
+        block: Zero::zero(),
+        kind: OwnershipChange::Sale(Pallet::<T, I>::parameters().min_order_price),
+    }).collect::<Vec<_>>();
+
+    OwnershipHistory::<T, I>::insert(class_id, kitty_id, history);
+}
+
+// fill the order book of a collection with orders for the minimum price that don't match any
+// kitty
+fn setup_buy_orders<T: Config<I>, I: 'static>(class_id: ClassIdOf<T, I>, count: u32) {
+    let orders = (0..count).map(|i| {
+        let order = BuyOrder {
+            buyer: account("buyer", i, 1),
+            mask: [0xff; 16],
+            value: [0xff; 16],
+            max_price: Pallet::<T, I>::parameters().min_order_price,
+        };
+        (i, order)
+    }).collect::<Vec<_>>();
+
+    BuyOrders::<T, I>::insert(class_id, orders);
+    NextOrderId::<T, I>::put(count);
+}
+
+// fill the order book of a collection with floor bids for the minimum amount
+fn setup_floor_bids<T: Config<I>, I: 'static>(class_id: ClassIdOf<T, I>, count: u32) {
+    let bids = (0..count).map(|i| {
+        let bid = FloorBid {
+            bidder: account("bidder", i, 1),
+            amount: Pallet::<T, I>::parameters().min_order_price,
+            quantity: 10,
+        };
+        (i, bid)
+    }).collect::<Vec<_>>();
+
+    FloorBids::<T, I>::insert(class_id, bids);
+    NextBidId::<T, I>::put(count);
+}
+
+// mint a kitty and list it for 500
+fn setup_listed_kitty<T: Config<I>, I: 'static>() -> Result<(ClassIdOf<T, I>, T::AccountId, KittyIndexOf<T, I>), &'static str> {
+    let seller: T::AccountId = account("seller", 0, 0);
+    let class_id = setup_collection::<T, I>()?;
+
+    let kitty_id = T::Nft::mint(&seller, class_id, Kitty::default())?;
+    setup_history::<T, I>(class_id, kitty_id);
+    Pallet::<T, I>::set_price(RawOrigin::Signed(seller.clone()).into(), class_id, kitty_id, Some(500u32.into()))?;
+
+    Ok((class_id, seller, kitty_id))
+}
+
+// mint a kitty and escrow it for a loan of 100 with an interest of 10 for 10 blocks
+fn setup_loan<T: Config<I>, I: 'static>(borrower: &T::AccountId) -> Result<(ClassIdOf<T, I>, KittyIndexOf<T, I>), &'static str> {
+    let class_id = setup_collection::<T, I>()?;
+
+    let kitty_id = T::Nft::mint(borrower, class_id, Kitty::default())?;
+    setup_history::<T, I>(class_id, kitty_id);
+    Pallet::<T, I>::request_loan(RawOrigin::Signed(borrower.clone()).into(), class_id, kitty_id, 100u32.into(), 10u32.into(), 10u32.into())?;
+
+    Ok((class_id, kitty_id))
+}
+
+// mint a kitty and split it into 100 shares with a reserve price of 500
+fn setup_vault<T: Config<I>, I: 'static>(owner: &T::AccountId) -> Result<AssetIdOf<T, I>, &'static str> {
+    let class_id = setup_collection::<T, I>()?;
+
+    let kitty_id = T::Nft::mint(owner, class_id, Kitty::default())?;
+    setup_history::<T, I>(class_id, kitty_id);
+    Pallet::<T, I>::fractionalise(RawOrigin::Signed(owner.clone()).into(), class_id, kitty_id, 100u32.into(), 500u32.into())?;
+
+    Ok(KittyShares::<T, I>::get(class_id, kitty_id).ok_or("kitty is not fractionalised")?)
+}
+
+// mint two kitties of different genders that opted in to auto breeding, and find a
+// solution for them
+fn setup_auto_breed<T: Config<I>, I: 'static>() -> Result<(ClassIdOf<T, I>, KittyIndexOf<T, I>, KittyIndexOf<T, I>, u32, u128), &'static str> {
+    let owner: T::AccountId = account("owner", 0, 0);
+    let class_id = setup_collection::<T, I>()?;
+
+    let mut kitty = Kitty::default();
+    let kitty_id = T::Nft::mint(&owner, class_id, kitty.clone())?;
+
+    kitty.dna[0] = 1;
+    let kitty_id2 = T::Nft::mint(&owner, class_id, kitty)?;
+
+    Pallet::<T, I>::set_auto_breed_consent(RawOrigin::Signed(owner.clone()).into(), class_id, kitty_id, true)?;
+    Pallet::<T, I>::set_auto_breed_consent(RawOrigin::Signed(owner).into(), class_id, kitty_id2, true)?;
+
+    // brute force a solution like the offchain worker does
+    let nonce = Pallet::<T, I>::auto_breed_nonce();
+    let solution = (0u128..).find(|solution| Pallet::<T, I>::validate_solution(class_id, kitty_id, kitty_id2, nonce, *solution))
+        .ok_or("no solution found")?;
+
+    Ok((class_id, kitty_id, kitty_id2, nonce, solution))
+}
 
 // usually testing the "happy" pass, which is usually the longer pass
 //  as error passes return earlier
-benchmarks! {
+benchmarks_instance_pallet! {
     create {
         // use the whitelisted caller, as caller also introduces some storage access overhead, like
         // nonce, account balance, etc.
         // for the whitelisted caller, this overhead is not counted
         // we need this because this overhead is standard overhead and is already counted in the extrinsic based weights
         let caller = whitelisted_caller();  // ignore the storage access of this caller
-    }: _(RawOrigin::Signed(caller))     // pass the benchmarking a create() method
+        let class_id = setup_collection::<T, I>()?;
+    }: _(RawOrigin::Signed(caller), class_id)     // pass the benchmarking a create() method
 
+    // the worst case burns a breed fee
     breed {
-        let caller = whitelisted_caller();
+        let caller: T::AccountId = whitelisted_caller();
+        let class_id = setup_collection::<T, I>()?;
+        Parameters::<T, I>::mutate(|parameters| parameters.breed_fee = 1u32.into());
+        let _ = T::Currency::make_free_balance_be(&caller, 1000u32.into());
 
         // mint the parent kitties
-        let mut kitty = Kitty(Default::default());
-        let kitty_id = orml_nft::Pallet::<T>::mint(&caller, Pallet::<T>::class_id(), Vec::new(), kitty.clone())?;
+        let mut kitty = Kitty::default();
+        let kitty_id = T::Nft::mint(&caller, class_id, kitty.clone())?;
 
-        kitty.0[0] = 1;  // modify the kitty DNA, so one is a male and the other is a female
-        let kitty_id2 = orml_nft::Pallet::<T>::mint(&caller, Pallet::<T>::class_id(), Vec::new(), kitty)?;
-    }: _(RawOrigin::Signed(caller), kitty_id, kitty_id2)    // pass the benchmarking a breed() method
+        kitty.dna[0] = 1;  // modify the kitty DNA, so one is a male and the other is a female
+        let kitty_id2 = T::Nft::mint(&caller, class_id, kitty)?;
+    }: _(RawOrigin::Signed(caller), class_id, kitty_id, kitty_id2)    // pass the benchmarking a breed() method
 
+    // the worst case moves the deposit for the longest name and metadata
     transfer {
-        let caller = whitelisted_caller();
+        let caller: T::AccountId = whitelisted_caller();
+        let class_id = setup_collection::<T, I>()?;
         // generate a test account with account()
-        let to = account("to", 0, 0);
+        let to: T::AccountId = account("to", 0, 0);
+        let _ = T::Currency::make_free_balance_be(&caller, BalanceOf::<T, I>::max_value() / 4u32.into());
+        let _ = T::Currency::make_free_balance_be(&to, BalanceOf::<T, I>::max_value() / 4u32.into());
 
         // transfer the kitty to the test account
-        let kitty_id = orml_nft::Pallet::<T>::mint(&caller, Pallet::<T>::class_id(), Vec::new(), Kitty(Default::default()))?;
-    }: _(RawOrigin::Signed(caller), to, kitty_id)
+        let kitty_id = T::Nft::mint(&caller, class_id, Kitty::default())?;
+        setup_history::<T, I>(class_id, kitty_id);
+        Pallet::<T, I>::set_name(RawOrigin::Signed(caller.clone()).into(), class_id, kitty_id, vec![b'a'; T::MaxNameLength::get() as usize])?;
+        Pallet::<T, I>::set_metadata(RawOrigin::Signed(caller.clone()).into(), class_id, kitty_id, vec![0u8; T::MaxMetadataLength::get() as usize])?;
+    }: _(RawOrigin::Signed(caller), to, class_id, kitty_id)
 
     // the difference between set_price() and clear_price() is really small, so just ignore
     // clear_price()
     set_price {
         let caller = whitelisted_caller();
+        let class_id = setup_collection::<T, I>()?;
 
-        let kitty_id = orml_nft::Pallet::<T>::mint(&caller, Pallet::<T>::class_id(), Vec::new(), Kitty(Default::default()))?;
-    }: _(RawOrigin::Signed(caller), kitty_id, Some(100u32.into()))
+        // the worst case is a kitty that is listed already, so its old listing is removed
+        let kitty_id = T::Nft::mint(&caller, class_id, Kitty::default())?;
+        Pallet::<T, I>::set_price(RawOrigin::Signed(caller.clone()).into(), class_id, kitty_id, Some(50u32.into()))?;
+    }: _(RawOrigin::Signed(caller), class_id, kitty_id, Some(100u32.into()))
 
     buy {
         let caller = whitelisted_caller();
+        let class_id = setup_collection::<T, I>()?;
         let seller = account("seller", 0, 0);
 
         let _ = T::Currency::make_free_balance_be(&caller, 1000u32.into());
 
-        let kitty_id = orml_nft::Pallet::<T>::mint(&seller, Pallet::<T>::class_id(), Vec::new(), Kitty(Default::default()))?;
-        Pallet::<T>::set_price(RawOrigin::Signed(seller.clone()).into(), kitty_id, Some(500u32.into()))?;
-    }: _(RawOrigin::Signed(caller), seller, kitty_id, 500u32.into())
+        let kitty_id = T::Nft::mint(&seller, class_id, Kitty::default())?;
+        setup_history::<T, I>(class_id, kitty_id);
+        Pallet::<T, I>::set_price(RawOrigin::Signed(seller.clone()).into(), class_id, kitty_id, Some(500u32.into()))?;
+    }: _(RawOrigin::Signed(caller), seller, class_id, kitty_id, 500u32.into())
+
+    // the worst case is the longest name
+    set_name {
+        let caller = whitelisted_caller();
+        let class_id = setup_collection::<T, I>()?;
+        let _ = T::Currency::make_free_balance_be(&caller, BalanceOf::<T, I>::max_value() / 2u32.into());
+
+        let kitty_id = T::Nft::mint(&caller, class_id, Kitty::default())?;
+        let name = vec![b'a'; T::MaxNameLength::get() as usize];
+    }: _(RawOrigin::Signed(caller), class_id, kitty_id, name)
+
+    set_metadata {
+        let caller = whitelisted_caller();
+        let class_id = setup_collection::<T, I>()?;
+        let _ = T::Currency::make_free_balance_be(&caller, BalanceOf::<T, I>::max_value() / 2u32.into());
+
+        let kitty_id = T::Nft::mint(&caller, class_id, Kitty::default())?;
+        let metadata = vec![0u8; T::MaxMetadataLength::get() as usize];
+    }: _(RawOrigin::Signed(caller), class_id, kitty_id, metadata)
+
+    // the worst case is a listed kitty with a name and metadata
+    burn {
+        let caller: T::AccountId = whitelisted_caller();
+        let class_id = setup_collection::<T, I>()?;
+        let _ = T::Currency::make_free_balance_be(&caller, BalanceOf::<T, I>::max_value() / 2u32.into());
+
+        let kitty_id = T::Nft::mint(&caller, class_id, Kitty::default())?;
+        setup_history::<T, I>(class_id, kitty_id);
+        Pallet::<T, I>::set_price(RawOrigin::Signed(caller.clone()).into(), class_id, kitty_id, Some(100u32.into()))?;
+        Pallet::<T, I>::set_name(RawOrigin::Signed(caller.clone()).into(), class_id, kitty_id, vec![b'a'; T::MaxNameLength::get() as usize])?;
+        Pallet::<T, I>::set_metadata(RawOrigin::Signed(caller.clone()).into(), class_id, kitty_id, vec![0u8; T::MaxMetadataLength::get() as usize])?;
+    }: _(RawOrigin::Signed(caller), class_id, kitty_id)
+
+    set_auto_breed_consent {
+        let caller = whitelisted_caller();
+        let class_id = setup_collection::<T, I>()?;
+
+        let kitty_id = T::Nft::mint(&caller, class_id, Kitty::default())?;
+    }: _(RawOrigin::Signed(caller), class_id, kitty_id, true)
+
+    // auto_breed() is an unsigned transaction, so there is no caller
+    auto_breed {
+        let (class_id, kitty_id, kitty_id2, nonce, solution) = setup_auto_breed::<T, I>()?;
+    }: _(RawOrigin::None, class_id, kitty_id, kitty_id2, nonce, solution)
+
+    // the transaction pool (and pre_dispatch()) run validate_unsigned() for every auto_breed()
+    // transaction, so its cost has to be known as well
+    validate_unsigned {
+        let (class_id, kitty_id, kitty_id2, nonce, solution) = setup_auto_breed::<T, I>()?;
+        let call = Call::<T, I>::auto_breed(class_id, kitty_id, kitty_id2, nonce, solution);
+    }: {
+        Pallet::<T, I>::validate_unsigned(TransactionSource::External, &call).map_err(<&str>::from)?;
+    }
+
+    // the worst case is the longest name
+    create_collection {
+        let origin = T::CollectionOrigin::successful_origin();
+        let name = vec![b'a'; T::MaxNameLength::get() as usize];
+    }: _<T::Origin>(origin, name, Some(1_000), true)
+
+    approve {
+        let caller = whitelisted_caller();
+        let class_id = setup_collection::<T, I>()?;
+        let operator = account("operator", 0, 0);
+
+        let kitty_id = T::Nft::mint(&caller, class_id, Kitty::default())?;
+    }: _(RawOrigin::Signed(caller), class_id, kitty_id, Some(operator))
+
+    set_approval_for_all {
+        let caller = whitelisted_caller();
+        let operator = account("operator", 0, 0);
+    }: _(RawOrigin::Signed(caller), operator, true)
+
+    // the worst case is an operator that is approved for all kitties of the owner, and a kitty
+    // that is listed and approved
+    transfer_from {
+        let caller: T::AccountId = whitelisted_caller();
+        let class_id = setup_collection::<T, I>()?;
+        let owner: T::AccountId = account("owner", 0, 0);
+        let to = account("to", 0, 0);
+
+        let kitty_id = T::Nft::mint(&owner, class_id, Kitty::default())?;
+        setup_history::<T, I>(class_id, kitty_id);
+        Pallet::<T, I>::set_price(RawOrigin::Signed(owner.clone()).into(), class_id, kitty_id, Some(100u32.into()))?;
+        Pallet::<T, I>::approve(RawOrigin::Signed(owner.clone()).into(), class_id, kitty_id, Some(account("approved", 0, 0)))?;
+        Pallet::<T, I>::set_approval_for_all(RawOrigin::Signed(owner.clone()).into(), caller.clone(), true)?;
+    }: _(RawOrigin::Signed(caller), owner, to, class_id, kitty_id)
+
+    // the worst case changes every parameter
+    set_parameters {
+        let origin = T::AdminOrigin::successful_origin();
+        let parameters = KittyParameters {
+            difficulty: 2,
+            breed_cooldown: 1u32.into(),
+            max_name_length: 1,
+            max_metadata_length: 1,
+            deposit_per_byte: 1u32.into(),
+            breeding_enabled: false,
+            auto_breeding_enabled: false,
+            breed_fee: 1u32.into(),
+            max_reason_length: 1,
+            max_history_length: MAX_HISTORY_LENGTH,
+            max_buy_orders: MAX_BUY_ORDERS,
+            max_floor_bids: MAX_FLOOR_BIDS,
+            min_order_price: 1u32.into(),
+            layaway_deposit: Perbill::from_percent(1),
+            layaway_period: 1u32.into(),
+            layaway_forfeit: Perbill::from_percent(1),
+        };
+    }: _<T::Origin>(origin, parameters)
+
+    pause {
+        let origin = T::AdminOrigin::successful_origin();
+    }: _<T::Origin>(origin, CallKind::Buy)
+
+    unpause {
+        let origin = T::AdminOrigin::successful_origin();
+        PausedCalls::<T, I>::insert(CallKind::Buy, ());
+    }: _<T::Origin>(origin, CallKind::Buy)
+
+    // the worst case replaces the block of an account, so its old expiry is removed
+    block_account {
+        let origin = T::AdminOrigin::successful_origin();
+        let who: T::AccountId = account("who", 0, 0);
+        let reason = vec![b'a'; Pallet::<T, I>::parameters().max_reason_length as usize];
+
+        Pallet::<T, I>::block_account(origin.clone(), who.clone(), Vec::new(), Some(10u32.into()))?;
+    }: _<T::Origin>(origin, who, reason, Some(20u32.into()))
+
+    unblock_account {
+        let origin = T::AdminOrigin::successful_origin();
+        let who: T::AccountId = account("who", 0, 0);
+
+        Pallet::<T, I>::block_account(origin.clone(), who.clone(), Vec::new(), Some(10u32.into()))?;
+    }: _<T::Origin>(origin, who)
+
+    // the worst case adds the highest order to a full order book, which evicts the lowest order
+    place_buy_order {
+        let caller = whitelisted_caller();
+        let class_id = setup_collection::<T, I>()?;
+        let price = Pallet::<T, I>::parameters().min_order_price.saturating_mul(2u32.into());
+        let _ = T::Currency::make_free_balance_be(&caller, price.saturating_mul(2u32.into()));
+
+        setup_buy_orders::<T, I>(class_id, MAX_BUY_ORDERS);
+    }: _(RawOrigin::Signed(caller), class_id, [0u8; 16], [0u8; 16], price)
+
+    cancel_buy_order {
+        let caller: T::AccountId = whitelisted_caller();
+        let class_id = setup_collection::<T, I>()?;
+        let price = Pallet::<T, I>::parameters().min_order_price;
+        let _ = T::Currency::make_free_balance_be(&caller, price.saturating_mul(2u32.into()));
+
+        setup_buy_orders::<T, I>(class_id, MAX_BUY_ORDERS - 1);
+        Pallet::<T, I>::place_buy_order(RawOrigin::Signed(caller.clone()).into(), class_id, [0u8; 16], [0u8; 16], price)?;
+        let order_id = NextOrderId::<T, I>::get() - 1;
+    }: _(RawOrigin::Signed(caller), class_id, order_id)
+
+    // the worst case fills the last order of a full order book
+    fill_order {
+        let caller = whitelisted_caller();
+        let class_id = setup_collection::<T, I>()?;
+        let buyer: T::AccountId = account("buyer", 0, 0);
+        let price = Pallet::<T, I>::parameters().min_order_price;
+        let _ = T::Currency::make_free_balance_be(&buyer, price.saturating_mul(2u32.into()));
+
+        setup_buy_orders::<T, I>(class_id, MAX_BUY_ORDERS - 1);
+        Pallet::<T, I>::place_buy_order(RawOrigin::Signed(buyer).into(), class_id, [0u8; 16], [0u8; 16], price)?;
+        let order_id = NextOrderId::<T, I>::get() - 1;
+
+        let kitty_id = T::Nft::mint(&caller, class_id, Kitty::default())?;
+        setup_history::<T, I>(class_id, kitty_id);
+    }: _(RawOrigin::Signed(caller), class_id, order_id, kitty_id)
+
+    // the worst case adds the highest bid to a full order book, which evicts the lowest bid
+    place_floor_bid {
+        let caller = whitelisted_caller();
+        let class_id = setup_collection::<T, I>()?;
+        let amount = Pallet::<T, I>::parameters().min_order_price.saturating_mul(2u32.into());
+        let _ = T::Currency::make_free_balance_be(&caller, amount.saturating_mul(20u32.into()));
+
+        setup_floor_bids::<T, I>(class_id, MAX_FLOOR_BIDS);
+    }: _(RawOrigin::Signed(caller), class_id, amount, 10)
+
+    cancel_floor_bid {
+        let caller: T::AccountId = whitelisted_caller();
+        let class_id = setup_collection::<T, I>()?;
+        let amount = Pallet::<T, I>::parameters().min_order_price;
+        let _ = T::Currency::make_free_balance_be(&caller, amount.saturating_mul(20u32.into()));
+
+        setup_floor_bids::<T, I>(class_id, MAX_FLOOR_BIDS - 1);
+        Pallet::<T, I>::place_floor_bid(RawOrigin::Signed(caller.clone()).into(), class_id, amount, 10)?;
+        let bid_id = NextBidId::<T, I>::get() - 1;
+    }: _(RawOrigin::Signed(caller), class_id, bid_id)
+
+    // the worst case partially fills the last bid of a full order book
+    sell_into_bid {
+        let caller = whitelisted_caller();
+        let class_id = setup_collection::<T, I>()?;
+        let bidder: T::AccountId = account("bidder", 0, 0);
+        let amount = Pallet::<T, I>::parameters().min_order_price;
+        let _ = T::Currency::make_free_balance_be(&bidder, amount.saturating_mul(20u32.into()));
+
+        setup_floor_bids::<T, I>(class_id, MAX_FLOOR_BIDS - 1);
+        Pallet::<T, I>::place_floor_bid(RawOrigin::Signed(bidder).into(), class_id, amount, 10)?;
+        let bid_id = NextBidId::<T, I>::get() - 1;
+
+        let kitty_id = T::Nft::mint(&caller, class_id, Kitty::default())?;
+        setup_history::<T, I>(class_id, kitty_id);
+    }: _(RawOrigin::Signed(caller), class_id, bid_id, kitty_id)
+
+    start_layaway {
+        let caller = whitelisted_caller();
+        let (class_id, seller, kitty_id) = setup_listed_kitty::<T, I>()?;
+        let _ = T::Currency::make_free_balance_be(&caller, 1000u32.into());
+    }: _(RawOrigin::Signed(caller), seller, class_id, kitty_id, 500u32.into())
+
+    // the worst case is the final payment, which transfers the kitty
+    pay_instalment {
+        let caller: T::AccountId = whitelisted_caller();
+        let (class_id, seller, kitty_id) = setup_listed_kitty::<T, I>()?;
+        let _ = T::Currency::make_free_balance_be(&caller, 1000u32.into());
+
+        Pallet::<T, I>::start_layaway(RawOrigin::Signed(caller.clone()).into(), seller, class_id, kitty_id, 500u32.into())?;
+    }: _(RawOrigin::Signed(caller), class_id, kitty_id, 500u32.into())
+
+    claim_layaway_default {
+        let caller: T::AccountId = whitelisted_caller();
+        let buyer: T::AccountId = account("buyer", 0, 0);
+        let (class_id, seller, kitty_id) = setup_listed_kitty::<T, I>()?;
+        let _ = T::Currency::make_free_balance_be(&buyer, 1000u32.into());
+
+        Pallet::<T, I>::start_layaway(RawOrigin::Signed(buyer).into(), seller, class_id, kitty_id, 500u32.into())?;
+        frame_system::Pallet::<T>::set_block_number(frame_system::Pallet::<T>::block_number() + Pallet::<T, I>::parameters().layaway_period + 1u32.into());
+    }: _(RawOrigin::Signed(caller), class_id, kitty_id)
+
+    // the worst case delists the kitty
+    request_loan {
+        let caller: T::AccountId = whitelisted_caller();
+        let class_id = setup_collection::<T, I>()?;
+
+        let kitty_id = T::Nft::mint(&caller, class_id, Kitty::default())?;
+        setup_history::<T, I>(class_id, kitty_id);
+        Pallet::<T, I>::set_price(RawOrigin::Signed(caller.clone()).into(), class_id, kitty_id, Some(100u32.into()))?;
+    }: _(RawOrigin::Signed(caller), class_id, kitty_id, 100u32.into(), 10u32.into(), 10u32.into())
+
+    cancel_loan {
+        let caller = whitelisted_caller();
+        let (class_id, kitty_id) = setup_loan::<T, I>(&caller)?;
+    }: _(RawOrigin::Signed(caller), class_id, kitty_id)
+
+    fund_loan {
+        let caller = whitelisted_caller();
+        let borrower: T::AccountId = account("borrower", 0, 0);
+        let (class_id, kitty_id) = setup_loan::<T, I>(&borrower)?;
+        let _ = T::Currency::make_free_balance_be(&caller, 1000u32.into());
+    }: _(RawOrigin::Signed(caller), class_id, kitty_id)
+
+    repay_loan {
+        let caller: T::AccountId = whitelisted_caller();
+        let lender: T::AccountId = account("lender", 0, 0);
+        let (class_id, kitty_id) = setup_loan::<T, I>(&caller)?;
+        let _ = T::Currency::make_free_balance_be(&lender, 1000u32.into());
+
+        Pallet::<T, I>::fund_loan(RawOrigin::Signed(lender).into(), class_id, kitty_id)?;
+        let _ = T::Currency::make_free_balance_be(&caller, 1000u32.into());
+    }: _(RawOrigin::Signed(caller), class_id, kitty_id)
+
+    claim_collateral {
+        let caller: T::AccountId = whitelisted_caller();
+        let borrower: T::AccountId = account("borrower", 0, 0);
+        let (class_id, kitty_id) = setup_loan::<T, I>(&borrower)?;
+        let _ = T::Currency::make_free_balance_be(&caller, 1000u32.into());
+
+        Pallet::<T, I>::fund_loan(RawOrigin::Signed(caller.clone()).into(), class_id, kitty_id)?;
+        frame_system::Pallet::<T>::set_block_number(frame_system::Pallet::<T>::block_number() + 11u32.into());
+    }: _(RawOrigin::Signed(caller), class_id, kitty_id)
+
+    fractionalise {
+        let caller = whitelisted_caller();
+        let class_id = setup_collection::<T, I>()?;
+
+        let kitty_id = T::Nft::mint(&caller, class_id, Kitty::default())?;
+        setup_history::<T, I>(class_id, kitty_id);
+    }: _(RawOrigin::Signed(caller), class_id, kitty_id, 100u32.into(), 500u32.into())
+
+    redeem {
+        let caller = whitelisted_caller();
+        let asset_id = setup_vault::<T, I>(&caller)?;
+    }: _(RawOrigin::Signed(caller), asset_id)
+
+    buyout {
+        let caller = whitelisted_caller();
+        let owner: T::AccountId = account("owner", 0, 0);
+        let asset_id = setup_vault::<T, I>(&owner)?;
+        let _ = T::Currency::make_free_balance_be(&caller, 1000u32.into());
+    }: _(RawOrigin::Signed(caller), asset_id)
+
+    // the worst case is the last claim, which removes the vault and destroys its asset class
+    claim_proceeds {
+        let caller: T::AccountId = whitelisted_caller();
+        let buyer: T::AccountId = account("buyer", 0, 0);
+        let asset_id = setup_vault::<T, I>(&caller)?;
+        let _ = T::Currency::make_free_balance_be(&buyer, 1000u32.into());
+
+        Pallet::<T, I>::buyout(RawOrigin::Signed(buyer).into(), asset_id)?;
+    }: _(RawOrigin::Signed(caller), asset_id)
 }
 
 // convert the benchmarks above to unit tests, so they can be used as additional unit tests
@@ -3,19 +3,25 @@
 
 use frame_support::{
 	pallet_prelude::*,
-	traits::{Randomness, Currency, ExistenceRequirement},
-    transactional,
+	traits::{Randomness, Currency, ReservableCurrency, ExistenceRequirement, WithdrawReasons, Imbalance, IsSubType, PalletInfoAccess},
+    transactional, PalletId,
 };
 use frame_system::{
     pallet_prelude::*,
     offchain::{SendTransactionTypes, SubmitTransaction},
 };
-use sp_std::{
-    prelude::*,
-    convert::TryInto,
-};
+use sp_std::{prelude::*, convert::TryFrom};
+use codec::{Input, Output, EncodeLike};
 use sp_io::hashing::blake2_128;
-use sp_runtime::offchain::storage_lock::{StorageLock, BlockAndTime};
+use sp_runtime::{
+    offchain::{
+        storage_lock::{StorageLock, BlockAndTime},
+        StorageKind,
+    },
+    traits::{Zero, One, CheckedAdd, CheckedMul, Saturating, AccountIdConversion, UniqueSaturatedInto, SignedExtension, DispatchInfoOf},
+    transaction_validity::{TransactionPriority, TransactionValidityError},
+    ArithmeticError, Perbill,
+};
 use rand_chacha::{
     rand_core::{RngCore, SeedableRng},
     ChaChaRng,
@@ -35,7 +41,13 @@ mod benchmarking;
 
 mod weights;
 
+pub mod migrations;
+pub mod nft;
+pub mod shares;
+
 pub use weights::WeightInfo;
+pub use nft::{NftBackend, UniquesBackend};
+pub use shares::SharesBackend;
 
 // define an enum for the kitty gender
 #[derive(Encode, Decode, Clone, Copy, RuntimeDebug, PartialEq, Eq)]
@@ -44,282 +56,2157 @@ pub enum KittyGender {
     Female
 }
 
+/// Reasons for rejecting an `auto_breed` transaction, used as `InvalidTransaction::Custom` codes
+#[derive(Clone, Copy, RuntimeDebug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum AutoBreedValidityError {
+    /// One of the kitties does not exist (anymore)
+    InvalidKittyId = 0,
+    /// Both kitties have the same gender
+    SameGender = 1,
+    /// One of the kitties was bred recently
+    OnCooldown = 2,
+    /// The owner of one of the kitties did not opt in to auto breeding
+    NoConsent = 3,
+    /// The collection of the kitties does not exist or does not allow breeding, or auto breeding
+    /// is disabled
+    BreedingDisabled = 4,
+    /// `auto_breed` is paused
+    Paused = 5,
+}
+
+impl From<AutoBreedValidityError> for InvalidTransaction {
+    fn from(error: AutoBreedValidityError) -> Self {
+        InvalidTransaction::Custom(error as u8)
+    }
+}
+
+/// The storage layouts of the pallet, used to decide which migrations have to run
+#[derive(Encode, Decode, Clone, Copy, RuntimeDebug, PartialEq, Eq)]
+pub enum Releases {
+    /// A single collection, and kitties that only have a dna
+    V1_0_0,
+    /// Multiple collections, and kitties with a generation
+    V2_0_0,
+    /// Collections that belong to an instance of the pallet
+    V3_0_0,
+}
+
+impl Default for Releases {
+    fn default() -> Self {
+        Releases::V1_0_0
+    }
+}
+
+/// Why a kitty is locked. A locked kitty can not change its owner until it is unlocked.
+#[derive(Encode, Decode, Clone, Copy, RuntimeDebug, PartialEq, Eq)]
+pub enum LockReason {
+    /// The kitty is listed on a marketplace
+    Listed,
+    /// The kitty is auctioned
+    Auctioned,
+    /// The kitty is lent to another account
+    Lent,
+    /// The kitty is staked
+    Staked,
+    /// The kitty is bought in instalments
+    Layaway,
+}
+
+/// The id of a buy order, unique within an instance of the pallet
+pub type OrderId = u32;
+
+/// A standing order to buy any kitty of a collection whose dna matches a pattern
 #[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
 #[derive(Encode, Decode, Clone, RuntimeDebug, PartialEq, Eq)]
-pub struct Kitty(pub [u8; 16]);    // each kitty must have a 128-bit value representing its dna
+pub struct BuyOrder<AccountId, Balance> {
+    /// The buyer, whose funds are reserved until the order is filled or cancelled
+    pub buyer: AccountId,
+    /// The bits of the dna that have to match
+    pub mask: [u8; 16],
+    /// The values of the bits of the dna that are selected by the mask
+    pub value: [u8; 16],
+    /// The highest price the buyer pays
+    pub max_price: Balance,
+}
 
-impl Kitty {
-    pub fn gender(&self) -> KittyGender {
-        // if the dna of the kitty has an even first bit, then the kitty is male
-        if self.0[0] % 2 == 0 {
-            KittyGender::Male
-        }
-        else {
-            KittyGender::Female
-        }
+impl<AccountId, Balance> BuyOrder<AccountId, Balance> {
+    /// Whether a kitty matches the pattern of the order
+    pub fn matches(&self, kitty: &Kitty) -> bool {
+        kitty.dna.iter()
+            .zip(self.mask.iter().zip(self.value.iter()))
+            .all(|(dna, (mask, value))| dna & mask == value & mask)
     }
 }
 
-#[frame_support::pallet]
-pub mod pallet {
-    use super::*;
+/// The id of a floor bid, unique within an instance of the pallet
+pub type BidId = u32;
 
-	#[pallet::config]
-	pub trait Config: frame_system::Config + orml_nft::Config<TokenData = Kitty, ClassData = ()> + SendTransactionTypes<Call<Self>> {
-		type Event: From<Event<Self>> + IsType<<Self as frame_system::Config>::Event>;
-        type Randomness: Randomness<Self::Hash, Self::BlockNumber>;
-        // use "fungibles" pallet if working with multiple currencies
-        type Currency: Currency<Self::AccountId>;
-        type WeightInfo: WeightInfo;
-        #[pallet::constant]     // => make this variable available in the metadata as well
-        type DefaultDifficulty: Get<u32>;
+/// A standing bid for a number of kitties of a collection, whatever kitties they are
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Decode, Clone, RuntimeDebug, PartialEq, Eq)]
+pub struct FloorBid<AccountId, Balance> {
+    /// The bidder, whose funds are reserved for the kitties that are not bought yet
+    pub bidder: AccountId,
+    /// The price that is paid for each kitty
+    pub amount: Balance,
+    /// The number of kitties that are still wanted
+    pub quantity: u32,
+}
 
-	}
+/// The purchase of a kitty in instalments. The kitty is locked until it is paid in full, or
+/// until the buyer defaults after the deadline.
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Decode, Clone, RuntimeDebug, PartialEq, Eq)]
+pub struct Layaway<AccountId, Balance, BlockNumber> {
+    /// The buyer, whose payments are reserved until the kitty is paid in full
+    pub buyer: AccountId,
+    /// The owner of the kitty when the layaway started
+    pub seller: AccountId,
+    /// The listed price of the kitty
+    pub price: Balance,
+    /// The first payment, of which the seller keeps a share if the buyer defaults
+    pub deposit: Balance,
+    /// The sum of all payments so far, including the deposit
+    pub paid: Balance,
+    /// The last block in which the buyer can pay
+    pub deadline: BlockNumber,
+}
 
-    pub type KittyIndexOf<T> = <T as orml_nft::Config>::TokenId;
-    pub type BalanceOf<T> = <<T as Config>::Currency as Currency<<T as frame_system::Config>::AccountId>>::Balance;
+/// The id of a loan, unique within an instance of the pallet
+pub type LoanId = u32;
 
-    /// Get kitty price. None means not for sale.
-    #[pallet::storage]
-    #[pallet::getter(fn kitty_prices)]
-    pub type KittyPrices<T: Config> = StorageMap<
-        _,
-        Blake2_128Concat, KittyIndexOf<T>,
-        BalanceOf<T>, OptionQuery
-    >;
+/// A loan that is backed by a kitty. The kitty is escrowed in the account of the loan until
+/// the loan is repaid, or the lender claims it after the deadline.
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Decode, Clone, RuntimeDebug, PartialEq, Eq)]
+pub struct Loan<AccountId, Balance, BlockNumber> {
+    /// The id of the loan, from which the account that escrows the kitty is derived
+    pub id: LoanId,
+    /// The owner of the kitty, who gets the principal
+    pub borrower: AccountId,
+    /// The amount that is lent
+    pub principal: Balance,
+    /// The amount that is paid on top of the principal
+    pub interest: Balance,
+    /// The number of blocks the borrower has to repay the loan in, once it is funded
+    pub duration: BlockNumber,
+    /// The lender and the deadline of the repayment, once the loan is funded
+    pub funded: Option<(AccountId, BlockNumber)>,
+}
 
-    // All kitties should belong to the same class
-    /// The class id for orml_nft
-    #[pallet::storage]
-    #[pallet::getter(fn class_id)]
-    pub type ClassId<T: Config> = StorageValue<_, T::ClassId, ValueQuery>;
+/// A kitty that is escrowed in the account of its vault and split into fungible shares
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Decode, Clone, RuntimeDebug, PartialEq, Eq)]
+pub struct Vault<ClassId, KittyIndex, Balance> {
+    pub class_id: ClassId,
+    pub kitty_id: KittyIndex,
+    /// The price for which anyone can buy the kitty out of the vault
+    pub reserve_price: Balance,
+    /// The part of the reserve price that the holders of the shares did not claim yet, once the
+    /// kitty is bought out. None while the kitty is in the vault.
+    pub proceeds: Option<Balance>,
+}
 
-    /// Nonce for auto breed to prevent replay attack
-    #[pallet::storage]
-    #[pallet::getter(fn auto_breed_nonce)]
-    pub type AutoBreedNonce<T: Config> = StorageValue<_, u32, ValueQuery>;
+/// How a kitty got a new owner
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Decode, Clone, RuntimeDebug, PartialEq, Eq)]
+pub enum OwnershipChange<Balance> {
+    /// The kitty is created
+    Mint,
+    /// The kitty is transferred
+    Transfer,
+    /// The kitty is sold for a price
+    Sale(Balance),
+    /// The kitty is bred
+    Breed,
+}
 
-    // define a hook for the offchain worker
-    #[pallet::hooks]
-    impl<T: Config> Hooks<T::BlockNumber> for Pallet<T> {
-        fn offchain_worker(_now: T::BlockNumber) {
-            let _ = Self::run_offchain_worker();
-        }
+/// A change of the owner of a kitty
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Decode, Clone, RuntimeDebug, PartialEq, Eq)]
+pub struct OwnershipRecord<AccountId, Balance, BlockNumber> {
+    /// The previous owner. None for new kitties.
+    pub from: Option<AccountId>,
+    /// The new owner
+    pub to: AccountId,
+    /// The block in which the owner changed
+    pub block: BlockNumber,
+    /// How the owner changed
+    pub kind: OwnershipChange<Balance>,
+}
+
+/// Why and until when an account is blocked by the moderators
+#[derive(Encode, Decode, Clone, RuntimeDebug, PartialEq, Eq)]
+pub struct BlockEntry<BlockNumber> {
+    /// Why the account is blocked, for example a link to a report
+    pub reason: Vec<u8>,
+    /// The block at which the account is unblocked. None means the account stays blocked
+    /// until it is unblocked by the moderators.
+    pub expiry: Option<BlockNumber>,
+}
+
+/// The calls of the pallet that can be paused
+#[derive(Encode, Decode, Clone, Copy, RuntimeDebug, PartialEq, Eq)]
+pub enum CallKind {
+    Create,
+    Breed,
+    Buy,
+    SetPrice,
+    AutoBreed,
+}
+
+/// The lock registry of the kitties, used by the pallets that commit a kitty somewhere else.
+/// A kitty can only have one lock at a time, and while it is locked it can not be transferred,
+/// sold or burned.
+pub trait KittyLockManager<ClassId, KittyIndex> {
+    /// Lock a kitty. Fails if the kitty does not exist or is already locked.
+    fn lock(class_id: ClassId, kitty_id: KittyIndex, reason: LockReason) -> DispatchResult;
+    /// Unlock a kitty. Fails if the kitty is not locked for `reason`.
+    fn unlock(class_id: ClassId, kitty_id: KittyIndex, reason: LockReason) -> DispatchResult;
+    /// Get the reason a kitty is locked for. None means the kitty is not locked.
+    fn lock_reason(class_id: ClassId, kitty_id: KittyIndex) -> Option<LockReason>;
+
+    fn is_locked(class_id: ClassId, kitty_id: KittyIndex) -> bool {
+        Self::lock_reason(class_id, kitty_id).is_some()
     }
+}
 
-    // initialize this class at the genesis time
-    #[pallet::genesis_config]
-    #[derive(Default)]
-    pub struct GenesisConfig;
+/// Maximum number of items returned by a single page of the runtime API
+pub const MAX_PAGE_SIZE: u32 = 100;
+
+// The order books and the ownership histories are stored as vectors, so the weights of the calls
+// that read them grow with the governance-tunable limits. The limits can not exceed these caps,
+// and the weights are benchmarked with vectors of these lengths.
+
+/// Upper bound of the `max_history_length` parameter
+pub const MAX_HISTORY_LENGTH: u32 = 50;
+/// Upper bound of the `max_buy_orders` parameter
+pub const MAX_BUY_ORDERS: u32 = 100;
+/// Upper bound of the `max_floor_bids` parameter
+pub const MAX_FLOOR_BIDS: u32 = 100;
+
+/// Base priority of the `auto_breed` transactions in the pool. The difficulty is added to it, so
+/// solutions that took more work to find get a higher priority.
+pub const UNSIGNED_TXS_PRIORITY: TransactionPriority = 100;
+
+/// Offchain local storage key that enables or disables the auto breed miner of a node
+pub const MINER_ENABLED_KEY: &[u8] = b"kitties::miner::enabled";
+/// Offchain local storage key with the number of iterations the miner tries per run
+pub const MINER_MAX_ITERATIONS_KEY: &[u8] = b"kitties::miner::max_iterations";
+/// Offchain local storage key with the number of blocks between two runs of the miner
+pub const MINER_INTERVAL_KEY: &[u8] = b"kitties::miner::interval";
+
+/// Settings of the auto breed miner. Each node operator can change them in the offchain local
+/// storage of their node, so they don't need a different binary to turn mining off.
+#[derive(Encode, Decode, Clone, Copy, RuntimeDebug, PartialEq, Eq)]
+pub struct MinerConfig {
+    /// Whether the offchain worker should mine at all
+    pub enabled: bool,
+    /// How many iterations the miner should try before giving up
+    pub max_iterations: u32,
+    /// The miner runs once every `interval` blocks
+    pub interval: u32,
+}
 
-    #[pallet::genesis_build]
-    impl<T: Config> GenesisBuild<T> for GenesisConfig {
-        fn build(&self) {
-            // create an NFT class
-            let class_id = orml_nft::Pallet::<T>::create_class(&Default::default(), Vec::new(), ())
-                .expect("Cannot fail or invalid chain spec");
-            ClassId::<T>::put(class_id);
+impl Default for MinerConfig {
+    fn default() -> Self {
+        MinerConfig {
+            enabled: true,
+            max_iterations: 500,
+            interval: 1,
         }
     }
+}
 
-	#[pallet::event]
-	#[pallet::generate_deposit(pub(super) fn deposit_event)]
-	#[pallet::metadata(T::AccountId = "AccountId", KittyIndexOf<T> = "KittyIndex", Option<BalanceOf<T>> = "Option<Balance>", BalanceOf<T> = "Balance")]
-	pub enum Event<T: Config> {
-		/// A kitty is created. \[owner, kitty_id, kitty\]
-		KittyCreated(T::AccountId, KittyIndexOf<T>, Kitty),
-        /// A new kitten is bred. \[owner, kitty_id, kitty\]
-        KittyBred(T::AccountId, KittyIndexOf<T>, Kitty),
-        /// A kitty is transferred. \[from, to, kitty_id\]
-        KittyTransferred(T::AccountId, T::AccountId, KittyIndexOf<T>),
-        /// The price for a kitty is updated. \[owner, kitty_id, price\]
-        KittyPriceUpdated(T::AccountId, KittyIndexOf<T>, Option<BalanceOf<T>>),
-        /// A kitty is sold. \[old_owner, new_owner, kitty_id, price\]
-        KittySold(T::AccountId, T::AccountId, KittyIndexOf<T>, BalanceOf<T>),
-	}
+impl MinerConfig {
+    /// Read the settings from the offchain local storage, falling back to the defaults for the
+    /// values that were never set
+    pub fn load() -> Self {
+        let default = Self::default();
+
+        MinerConfig {
+            enabled: Self::get(MINER_ENABLED_KEY).unwrap_or(default.enabled),
+            max_iterations: Self::get(MINER_MAX_ITERATIONS_KEY).unwrap_or(default.max_iterations),
+            // running the miner every 0 blocks makes no sense
+            interval: Self::get(MINER_INTERVAL_KEY).filter(|interval| *interval > 0).unwrap_or(default.interval),
+        }
+    }
 
-    #[pallet::error]
-    pub enum Error<T> {
-        InvalidKittyId,
-        SameGender,
-        NotOwner,
-        NotForSale,
-        PriceTooLow,
-        BuyFromSelf,
+    fn get<V: Decode>(key: &[u8]) -> Option<V> {
+        sp_io::offchain::local_storage_get(StorageKind::PERSISTENT, key)
+            .and_then(|value| V::decode(&mut &value[..]).ok())
     }
+}
 
-	#[pallet::pallet]
-    #[pallet::generate_store(pub(super) trait Store)]
-	pub struct Pallet<T>(_);
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Decode, Clone, Default, RuntimeDebug, PartialEq, Eq)]
+pub struct Kitty {
+    /// Each kitty must have a 128-bit value representing its dna
+    pub dna: [u8; 16],
+    /// 0 for created kitties, one more than the generation of the older parent for bred kittens
+    pub generation: u32,
+}
 
-	#[pallet::call]
-	impl<T:Config> Pallet<T> {
+/// The settings of a collection of kitties, stored as the class data of the NFT backend
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Decode, Clone, RuntimeDebug, PartialEq, Eq)]
+pub struct KittyClassData {
+    /// The name of the collection
+    pub name: Vec<u8>,
+    /// The maximum number of kitties that can exist in the collection. None means unlimited.
+    pub max_supply: Option<u32>,
+    /// Whether the kitties of the collection can be bred
+    pub breeding_enabled: bool,
+}
 
-		/// Create a new kitty
-		#[pallet::weight(T::WeightInfo::create())]
-		pub fn create(origin: OriginFor<T>) -> DispatchResult {
-			let sender = ensure_signed(origin)?;
+impl Default for KittyClassData {
+    fn default() -> Self {
+        KittyClassData {
+            name: b"Kitties".to_vec(),
+            max_supply: None,
+            breeding_enabled: true,
+        }
+    }
+}
 
-            let dna = Self::random_value(&sender);
+/// A page of the kitties of an owner
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Decode, Clone, RuntimeDebug, PartialEq, Eq)]
+pub struct KittiesPage<KittyIndex> {
+    /// The kitties in this page, ordered by their ids
+    pub kitties: Vec<(KittyIndex, Kitty)>,
+    /// The cursor of the next page. None means there are no more kitties.
+    pub next: Option<KittyIndex>,
+}
 
-			// Create and store kitty
-			let kitty = Kitty(dna);
-            let kitty_id = orml_nft::Pallet::<T>::mint(&sender, Self::class_id(), /* metadata: */ Vec::new(), /* data: */ kitty.clone())?;
+// storage keys that are not hashed are iterated in the order of their encodings, so the numbers
+// in them are encoded in big endian
+fn encode_ordered<W: Output + ?Sized>(value: impl UniqueSaturatedInto<u128>, dest: &mut W) {
+    dest.write(&value.unique_saturated_into().to_be_bytes());
+}
 
-			// Emit event
-			Self::deposit_event(Event::KittyCreated(sender, kitty_id, kitty));
+fn decode_ordered<N: TryFrom<u128>, In: Input>(input: &mut In) -> Result<N, codec::Error> {
+    let value = u128::from_be_bytes(<[u8; 16]>::decode(input)?);
+    N::try_from(value).map_err(|_| "number out of range".into())
+}
 
-			Ok(())
-		}
+/// The key of a listing in `ListingsByPrice`. It is encoded in big endian, so iterating the
+/// listings of a collection visits them in the order of their prices (and ids).
+#[derive(Clone, Copy, RuntimeDebug, PartialEq, Eq)]
+pub struct ListingKey<Balance, KittyIndex> {
+    pub price: Balance,
+    pub kitty_id: KittyIndex,
+}
 
-        /// Breed kitties
-        #[pallet::weight(T::WeightInfo::breed())]
-        pub fn breed(origin: OriginFor<T>, kitty_id_1: KittyIndexOf<T>, kitty_id_2: KittyIndexOf<T>) -> DispatchResult {
-            // get the sender
-            let sender = ensure_signed(origin)?;
+impl<Balance, KittyIndex> Encode for ListingKey<Balance, KittyIndex>
+where
+    Balance: UniqueSaturatedInto<u128> + Copy,
+    KittyIndex: UniqueSaturatedInto<u128> + Copy,
+{
+    fn size_hint(&self) -> usize {
+        32
+    }
 
-            // use the kitties getter (Self::kitties) to get the kitties from their ids
-            // since the getter returns an optional kitty, check if it is Ok or None
-            //  if the getter returns None, the kitty does not exist,
-            //  so early return InvalidKittyId to the calling function
-            //  (because of the ?)
-            let kitty1 = Self::kitties(&sender, kitty_id_1).ok_or(Error::<T>::InvalidKittyId)?;
-            let kitty2 = Self::kitties(&sender, kitty_id_2).ok_or(Error::<T>::InvalidKittyId)?;
+    fn encode_to<W: Output + ?Sized>(&self, dest: &mut W) {
+        encode_ordered(self.price, dest);
+        encode_ordered(self.kitty_id, dest);
+    }
+}
 
-            Self::do_breed(sender, kitty1, kitty2)
-        }
+impl<Balance, KittyIndex> EncodeLike for ListingKey<Balance, KittyIndex>
+where
+    Balance: UniqueSaturatedInto<u128> + Copy,
+    KittyIndex: UniqueSaturatedInto<u128> + Copy,
+{}
+
+impl<Balance: TryFrom<u128>, KittyIndex: TryFrom<u128>> Decode for ListingKey<Balance, KittyIndex> {
+    fn decode<In: Input>(input: &mut In) -> Result<Self, codec::Error> {
+        Ok(ListingKey {
+            price: decode_ordered(input)?,
+            kitty_id: decode_ordered(input)?,
+        })
+    }
+}
 
-        /// Transfer a kitty to a new owner
-        #[pallet::weight(T::WeightInfo::transfer())]
-        pub fn transfer(origin: OriginFor<T>, to: T::AccountId, kitty_id: KittyIndexOf<T>) -> DispatchResult {
-            let sender = ensure_signed(origin)?;
+/// A kitty id in the key of `KittiesByOwner`. It is encoded in big endian, so iterating the
+/// kitties of an owner visits them in the order of their ids.
+#[derive(Clone, Copy, RuntimeDebug, PartialEq, Eq)]
+pub struct OrderedKittyIndex<KittyIndex>(pub KittyIndex);
 
-            orml_nft::Pallet::<T>::transfer(&sender, &to, /* token: */ (Self::class_id(), kitty_id))?;
+impl<KittyIndex: UniqueSaturatedInto<u128> + Copy> Encode for OrderedKittyIndex<KittyIndex> {
+    fn size_hint(&self) -> usize {
+        16
+    }
 
-            // if the sender does not transfer to themselves, remove the kitty price and deposit
-            // the KittyTransferred event
-            if sender != to {
-                KittyPrices::<T>::remove(kitty_id);
+    fn encode_to<W: Output + ?Sized>(&self, dest: &mut W) {
+        encode_ordered(self.0, dest);
+    }
+}
 
-                Self::deposit_event(Event::KittyTransferred(sender, to, kitty_id));
-            }
+impl<KittyIndex: UniqueSaturatedInto<u128> + Copy> EncodeLike for OrderedKittyIndex<KittyIndex> {}
 
-            Ok(())
-        }
+impl<KittyIndex: TryFrom<u128>> Decode for OrderedKittyIndex<KittyIndex> {
+    fn decode<In: Input>(input: &mut In) -> Result<Self, codec::Error> {
+        decode_ordered(input).map(OrderedKittyIndex)
+    }
+}
 
-        /// Set a price for a kitty for sale
-        /// None to delist the kitty
-        #[pallet::weight(T::WeightInfo::set_price())]
-        pub fn set_price(origin: OriginFor<T>, kitty_id: KittyIndexOf<T>, new_price: Option<BalanceOf<T>>) -> DispatchResult {
-            let sender = ensure_signed(origin)?;
+/// The parameters of the game that can be changed without a runtime upgrade
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Decode, Clone, RuntimeDebug, PartialEq, Eq)]
+pub struct KittyParameters<Balance, BlockNumber> {
+    /// How hard it is to find an auto breed solution. Must not be 0.
+    pub difficulty: u32,
+    /// Number of blocks a kitty has to wait before it can breed again
+    pub breed_cooldown: BlockNumber,
+    /// Maximum length of the name of a kitty or a collection, in bytes
+    pub max_name_length: u32,
+    /// Maximum length of the metadata of a kitty, in bytes
+    pub max_metadata_length: u32,
+    /// The deposit reserved for every byte of the name and metadata of a kitty
+    pub deposit_per_byte: Balance,
+    /// Whether kitties can be bred at all, in addition to the setting of their collection
+    pub breeding_enabled: bool,
+    /// Whether the offchain workers can breed kitties with `auto_breed`
+    pub auto_breeding_enabled: bool,
+    /// The fee that is burned when a kitty is bred with `breed`
+    pub breed_fee: Balance,
+    /// Maximum length of the reason an account is blocked for, in bytes
+    pub max_reason_length: u32,
+    /// Maximum number of ownership changes that are kept for every kitty. The oldest change
+    /// is dropped when a kitty changes its owner more often. At most `MAX_HISTORY_LENGTH`.
+    pub max_history_length: u32,
+    /// Maximum number of buy orders of a collection. A full order book evicts its lowest order
+    /// to make room for a higher one. At most `MAX_BUY_ORDERS`.
+    pub max_buy_orders: u32,
+    /// Maximum number of floor bids of a collection. A full order book evicts its lowest bid to
+    /// make room for a higher one. At most `MAX_FLOOR_BIDS`.
+    pub max_floor_bids: u32,
+    /// Minimum price of a buy order and minimum amount of a floor bid
+    pub min_order_price: Balance,
+    /// Share of the price that has to be paid as deposit to buy a kitty in instalments
+    pub layaway_deposit: Perbill,
+    /// Number of blocks the buyer has to pay the rest of the price in
+    pub layaway_period: BlockNumber,
+    /// Share of the deposit the seller keeps if the buyer does not pay in time
+    pub layaway_forfeit: Perbill,
+}
 
-            // ensure the sender is the owner of the kitty id
-            ensure!(orml_nft::TokensByOwner::<T>::contains_key(&sender, (Self::class_id(), kitty_id)), Error::<T>::NotOwner);
+/// The new value of a parameter, used to describe what `set_parameters` changed
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Decode, Clone, RuntimeDebug, PartialEq, Eq)]
+pub enum KittyParameter<Balance, BlockNumber> {
+    Difficulty(u32),
+    BreedCooldown(BlockNumber),
+    MaxNameLength(u32),
+    MaxMetadataLength(u32),
+    DepositPerByte(Balance),
+    BreedingEnabled(bool),
+    AutoBreedingEnabled(bool),
+    BreedFee(Balance),
+    MaxReasonLength(u32),
+    MaxHistoryLength(u32),
+    MaxBuyOrders(u32),
+    MaxFloorBids(u32),
+    MinOrderPrice(Balance),
+    LayawayDeposit(Perbill),
+    LayawayPeriod(BlockNumber),
+    LayawayForfeit(Perbill),
+}
 
-            // set the price
-            KittyPrices::<T>::mutate_exists(kitty_id, |price| *price = new_price);
-            // mutate_exists() will check if the new_price is None and add new_price to KittyPrices
-            // if it is not None.
-            // Otherwise, it will remove the kitty_id from KittyPrices
+impl<Balance: Clone + PartialEq, BlockNumber: Clone + PartialEq> KittyParameters<Balance, BlockNumber> {
+    /// Whether the parameters can be used. A difficulty of 0 would make every auto breed
+    /// solution invalid, and longer vectors than the caps would exceed the benchmarked weights.
+    pub fn is_valid(&self) -> bool {
+        self.difficulty > 0
+            && self.max_history_length <= MAX_HISTORY_LENGTH
+            && self.max_buy_orders <= MAX_BUY_ORDERS
+            && self.max_floor_bids <= MAX_FLOOR_BIDS
+    }
 
-            Self::deposit_event(Event::KittyPriceUpdated(sender, kitty_id, new_price));
+    /// The parameters of `new` that differ from these parameters
+    pub fn diff(&self, new: &Self) -> Vec<KittyParameter<Balance, BlockNumber>> {
+        let mut changes = Vec::new();
 
-            Ok(())
+        if self.difficulty != new.difficulty {
+            changes.push(KittyParameter::Difficulty(new.difficulty));
+        }
+        if self.breed_cooldown != new.breed_cooldown {
+            changes.push(KittyParameter::BreedCooldown(new.breed_cooldown.clone()));
+        }
+        if self.max_name_length != new.max_name_length {
+            changes.push(KittyParameter::MaxNameLength(new.max_name_length));
+        }
+        if self.max_metadata_length != new.max_metadata_length {
+            changes.push(KittyParameter::MaxMetadataLength(new.max_metadata_length));
+        }
+        if self.deposit_per_byte != new.deposit_per_byte {
+            changes.push(KittyParameter::DepositPerByte(new.deposit_per_byte.clone()));
+        }
+        if self.breeding_enabled != new.breeding_enabled {
+            changes.push(KittyParameter::BreedingEnabled(new.breeding_enabled));
+        }
+        if self.auto_breeding_enabled != new.auto_breeding_enabled {
+            changes.push(KittyParameter::AutoBreedingEnabled(new.auto_breeding_enabled));
+        }
+        if self.breed_fee != new.breed_fee {
+            changes.push(KittyParameter::BreedFee(new.breed_fee.clone()));
+        }
+        if self.max_reason_length != new.max_reason_length {
+            changes.push(KittyParameter::MaxReasonLength(new.max_reason_length));
+        }
+        if self.max_history_length != new.max_history_length {
+            changes.push(KittyParameter::MaxHistoryLength(new.max_history_length));
+        }
+        if self.max_buy_orders != new.max_buy_orders {
+            changes.push(KittyParameter::MaxBuyOrders(new.max_buy_orders));
+        }
+        if self.max_floor_bids != new.max_floor_bids {
+            changes.push(KittyParameter::MaxFloorBids(new.max_floor_bids));
+        }
+        if self.min_order_price != new.min_order_price {
+            changes.push(KittyParameter::MinOrderPrice(new.min_order_price.clone()));
+        }
+        if self.layaway_deposit != new.layaway_deposit {
+            changes.push(KittyParameter::LayawayDeposit(new.layaway_deposit));
+        }
+        if self.layaway_period != new.layaway_period {
+            changes.push(KittyParameter::LayawayPeriod(new.layaway_period.clone()));
+        }
+        if self.layaway_forfeit != new.layaway_forfeit {
+            changes.push(KittyParameter::LayawayForfeit(new.layaway_forfeit));
         }
 
-        /// Buy a kitty
-        #[pallet::weight(T::WeightInfo::buy())]
-        #[transactional]
-        pub fn buy(origin: OriginFor<T>, owner: T::AccountId, kitty_id: KittyIndexOf<T>, max_price: BalanceOf<T>) -> DispatchResult {
-            let sender = ensure_signed(origin)?;
+        changes
+    }
+}
 
-            // you should not be able to buy a kitty from yourself
-            // (this could also be an early return without an error message,
-            // but it does not really make sense to buy a kitty from yourself)
-            ensure!(sender != owner, Error::<T>::BuyFromSelf);
+impl Kitty {
+    /// A kitty of the first generation
+    pub fn new(dna: [u8; 16]) -> Self {
+        Kitty { dna, generation: 0 }
+    }
 
-            // read and delete the kitty price
-            KittyPrices::<T>::try_mutate_exists(kitty_id, |price| -> DispatchResult {
-                // remove the price of the kitty (and ensure it is actually for sale) as it
-                // will be bought
-                let price = price.take().ok_or(Error::<T>::NotForSale)?;
+    pub fn gender(&self) -> KittyGender {
+        // if the dna of the kitty has an even first bit, then the kitty is male
+        if self.dna[0] % 2 == 0 {
+            KittyGender::Male
+        }
+        else {
+            KittyGender::Female
+        }
+    }
+}
 
-                // ensure the buyer is not overpaying
-                ensure!(max_price >= price, Error::<T>::PriceTooLow);
+#[frame_support::pallet]
+pub mod pallet {
+    use super::*;
 
-                // do the actual transfer
+	#[pallet::config]
+	pub trait Config<I: 'static = ()>: frame_system::Config + SendTransactionTypes<Call<Self, I>> {
+		type Event: From<Event<Self, I>> + IsType<<Self as frame_system::Config>::Event>;
+        /// The NFT pallet that stores the kitties and their collections
+        type Nft: NftBackend<Self::AccountId>;
+        type Randomness: Randomness<Self::Hash, Self::BlockNumber>;
+        // use "fungibles" pallet if working with multiple currencies
+        type Currency: ReservableCurrency<Self::AccountId>;
+        /// The origin that can create new collections
+        type CollectionOrigin: EnsureOrigin<Self::Origin>;
+        /// The origin that can change the parameters of the game
+        type AdminOrigin: EnsureOrigin<Self::Origin>;
+        type WeightInfo: WeightInfo;
+        /// The id of the accounts that escrow the kitties that back loans, and of the accounts of
+        /// the vaults of fractionalised kitties
+        #[pallet::constant]
+        type PalletId: Get<PalletId>;
+        /// The fungible assets pallet that stores the shares of fractionalised kitties
+        type Shares: SharesBackend<Self::AccountId, BalanceOf<Self, I>>;
+        // the constants below are the parameters of the game until `set_parameters` is called
+        // for the first time (see `KittyParameters`)
+        #[pallet::constant]     // => make this variable available in the metadata as well
+        type DefaultDifficulty: Get<u32>;
+        /// Number of blocks a kitty has to wait before it can breed again
+        #[pallet::constant]
+        type BreedCooldown: Get<Self::BlockNumber>;
+        /// Maximum length of the name of a kitty or a collection, in bytes
+        #[pallet::constant]
+        type MaxNameLength: Get<u32>;
+        /// Maximum length of the metadata of a kitty, in bytes
+        #[pallet::constant]
+        type MaxMetadataLength: Get<u32>;
+        /// The deposit reserved for every byte of the name and metadata of a kitty
+        #[pallet::constant]
+        type DepositPerByte: Get<BalanceOf<Self, I>>;
+        /// Maximum length of the reason an account is blocked for, in bytes
+        #[pallet::constant]
+        type MaxReasonLength: Get<u32>;
+        /// Maximum number of ownership changes that are kept for every kitty
+        #[pallet::constant]
+        type MaxHistoryLength: Get<u32>;
+        /// Maximum number of buy orders of a collection
+        #[pallet::constant]
+        type MaxBuyOrders: Get<u32>;
+        /// Maximum number of floor bids of a collection
+        #[pallet::constant]
+        type MaxFloorBids: Get<u32>;
+        /// Minimum price of a buy order and minimum amount of a floor bid, so filling the order
+        /// book of a collection is costly
+        #[pallet::constant]
+        type MinOrderPrice: Get<BalanceOf<Self, I>>;
+        /// Share of the price that has to be paid as deposit to buy a kitty in instalments
+        #[pallet::constant]
+        type LayawayDeposit: Get<Perbill>;
+        /// Number of blocks the buyer has to pay the rest of the price in
+        #[pallet::constant]
+        type LayawayPeriod: Get<Self::BlockNumber>;
+        /// Share of the deposit the seller keeps if the buyer does not pay in time
+        #[pallet::constant]
+        type LayawayForfeit: Get<Perbill>;
 
-                // since now both transfers can fail, they should be atomic
-                //  (which is done by #[transactional], which will revert all storages
-                //  that were changed in buy()'s body, if something fails)
+	}
 
-                // tranfer the ownership of the kitty
-                orml_nft::Pallet::<T>::transfer(&owner, &sender, (Self::class_id(), kitty_id))?;
+    pub type ClassIdOf<T, I = ()> = <<T as Config<I>>::Nft as NftBackend<<T as frame_system::Config>::AccountId>>::ClassId;
+    pub type KittyIndexOf<T, I = ()> = <<T as Config<I>>::Nft as NftBackend<<T as frame_system::Config>::AccountId>>::TokenId;
+    pub type BalanceOf<T, I = ()> = <<T as Config<I>>::Currency as Currency<<T as frame_system::Config>::AccountId>>::Balance;
+    pub type KittyParametersOf<T, I = ()> = KittyParameters<BalanceOf<T, I>, <T as frame_system::Config>::BlockNumber>;
+    pub type KittyParameterOf<T, I = ()> = KittyParameter<BalanceOf<T, I>, <T as frame_system::Config>::BlockNumber>;
+    pub type BuyOrderOf<T, I = ()> = BuyOrder<<T as frame_system::Config>::AccountId, BalanceOf<T, I>>;
+    pub type FloorBidOf<T, I = ()> = FloorBid<<T as frame_system::Config>::AccountId, BalanceOf<T, I>>;
+    pub type AssetIdOf<T, I = ()> = <<T as Config<I>>::Shares as SharesBackend<<T as frame_system::Config>::AccountId, BalanceOf<T, I>>>::AssetId;
+    pub type VaultOf<T, I = ()> = Vault<ClassIdOf<T, I>, KittyIndexOf<T, I>, BalanceOf<T, I>>;
+    pub type LoanOf<T, I = ()> = Loan<<T as frame_system::Config>::AccountId, BalanceOf<T, I>, <T as frame_system::Config>::BlockNumber>;
+    pub type LayawayOf<T, I = ()> = Layaway<<T as frame_system::Config>::AccountId, BalanceOf<T, I>, <T as frame_system::Config>::BlockNumber>;
+    pub type ListingKeyOf<T, I = ()> = ListingKey<BalanceOf<T, I>, KittyIndexOf<T, I>>;
+    pub type OrderedKittyIndexOf<T, I = ()> = OrderedKittyIndex<KittyIndexOf<T, I>>;
+    pub type OwnershipRecordOf<T, I = ()> = OwnershipRecord<<T as frame_system::Config>::AccountId, BalanceOf<T, I>, <T as frame_system::Config>::BlockNumber>;
+
+    /// The parameters of the game that are used until `set_parameters` is called, read from the
+    /// constants of the runtime
+    pub struct DefaultParameters<T, I = ()>(PhantomData<(T, I)>);
+
+    impl<T: Config<I>, I: 'static> Get<KittyParametersOf<T, I>> for DefaultParameters<T, I> {
+        fn get() -> KittyParametersOf<T, I> {
+            KittyParameters {
+                difficulty: T::DefaultDifficulty::get(),
+                breed_cooldown: T::BreedCooldown::get(),
+                max_name_length: T::MaxNameLength::get(),
+                max_metadata_length: T::MaxMetadataLength::get(),
+                deposit_per_byte: T::DepositPerByte::get(),
+                breeding_enabled: true,
+                auto_breeding_enabled: true,
+                breed_fee: Zero::zero(),
+                max_reason_length: T::MaxReasonLength::get(),
+                max_history_length: T::MaxHistoryLength::get(),
+                max_buy_orders: T::MaxBuyOrders::get(),
+                max_floor_bids: T::MaxFloorBids::get(),
+                min_order_price: T::MinOrderPrice::get(),
+                layaway_deposit: T::LayawayDeposit::get(),
+                layaway_period: T::LayawayPeriod::get(),
+                layaway_forfeit: T::LayawayForfeit::get(),
+            }
+        }
+    }
 
-                // send `price` from the sender to the owner of the kitty
-                //  ExistenceRequirement::KeepAlive will ensure that the transfer will not kill
-                //  the account of the sender if there is no more money left
-                T::Currency::transfer(&sender, &owner, price, ExistenceRequirement::KeepAlive)?;
+    // Kitty ids are only unique within their collection (the NFT class), so everything that
+    // is stored about a kitty is keyed by the collection and the kitty id
 
-                Self::deposit_event(Event::KittySold(owner, sender, kitty_id, price));
+    /// The collections of this instance of the pallet. Instances can share the classes of their
+    /// NFT backend, so the kitties of the other instances can not be used here.
+    #[pallet::storage]
+    pub type Collections<T: Config<I>, I: 'static = ()> = StorageMap<
+        _,
+        Twox64Concat, ClassIdOf<T, I>,
+        (), OptionQuery
+    >;
 
-                Ok(())
-            })
-        }
+    /// The settings of the collections, when the kitties are stored with `UniquesBackend`
+    #[pallet::storage]
+    pub type UniquesClassData<T: Config<I>, I: 'static = ()> = StorageMap<
+        _,
+        Twox64Concat, ClassIdOf<T, I>,
+        KittyClassData, OptionQuery
+    >;
 
-        // auto breed feature that is used by the offchain worker
-        #[pallet::weight(1000)]
-        pub fn auto_breed(origin: OriginFor<T>, kitty_id_1: KittyIndexOf<T>, kitty_id_2: KittyIndexOf<T>, _nonce: u32, _solution: u128) -> DispatchResult {
-            // ensure this is an unsigned transaction because the offchain worker is designed for a
-            // PoW approach, so anyone can become a miner
-            // anyone with a valid solution nonce will be able to participate; they don't need an
-            // account (or tokens to pay for a transaction)
-            ensure_none(origin)?;
+    /// The kitties, when they are stored with `UniquesBackend`
+    #[pallet::storage]
+    pub type UniquesTokenData<T: Config<I>, I: 'static = ()> = StorageDoubleMap<
+        _,
+        Twox64Concat, ClassIdOf<T, I>,
+        Twox64Concat, KittyIndexOf<T, I>,
+        Kitty, OptionQuery
+    >;
 
-            // ensure the kitty ids are valid and get the kitties
-            let kitty1 = orml_nft::Pallet::<T>::tokens(Self::class_id(), kitty_id_1).ok_or(Error::<T>::InvalidKittyId)?;
-            let kitty2 = orml_nft::Pallet::<T>::tokens(Self::class_id(), kitty_id_2).ok_or(Error::<T>::InvalidKittyId)?;
+    /// The number of kitties of the collections, when they are stored with `UniquesBackend`
+    #[pallet::storage]
+    pub type UniquesTotalIssuance<T: Config<I>, I: 'static = ()> = StorageMap<
+        _,
+        Twox64Concat, ClassIdOf<T, I>,
+        KittyIndexOf<T, I>, ValueQuery
+    >;
 
-            Self::do_breed(kitty1.owner, kitty1.data, kitty2.data)
-        }
-	}
+    /// The first id to try for the next collection, when the kitties are stored with
+    /// `UniquesBackend`
+    #[pallet::storage]
+    pub type UniquesNextClassId<T: Config<I>, I: 'static = ()> = StorageValue<_, ClassIdOf<T, I>, ValueQuery>;
 
-    // need to implement this to be able to use unsigned transactions
-    // otherwise people could fill the blocks with unsigned transactions
-    //  (even if the _nonce and _solution are validated inside the auto_breed() function,
-    //  the transaction will be included in the block, as it would be already too late to reject
-    //  the transaction)
-    #[pallet::validate_unsigned]
-    impl<T: Config> frame_support::unsigned::ValidateUnsigned for Pallet<T> {
-        type Call = Call<T>;
+    /// The id of the next kitty of the collections, when they are stored with `UniquesBackend`
+    #[pallet::storage]
+    pub type UniquesNextTokenId<T: Config<I>, I: 'static = ()> = StorageMap<
+        _,
+        Twox64Concat, ClassIdOf<T, I>,
+        KittyIndexOf<T, I>, ValueQuery
+    >;
 
-        // validate_unsigned() wil be executed before a transaction is accepted in a transaction pool
+    /// The parameters of the game
+    #[pallet::storage]
+    #[pallet::getter(fn parameters)]
+    pub type Parameters<T: Config<I>, I: 'static = ()> = StorageValue<_, KittyParametersOf<T, I>, ValueQuery, DefaultParameters<T, I>>;
+
+    /// Get kitty price. None means not for sale.
+    #[pallet::storage]
+    #[pallet::getter(fn kitty_prices)]
+    pub type KittyPrices<T: Config<I>, I: 'static = ()> = StorageDoubleMap<
+        _,
+        Twox64Concat, ClassIdOf<T, I>,
+        Blake2_128Concat, KittyIndexOf<T, I>,
+        BalanceOf<T, I>, OptionQuery
+    >;
+
+    /// The kitties of an owner in a collection, sorted by their id.
+    /// The kitty ids are not hashed, so a page of the kitties of an owner starts right after the
+    /// last kitty of the previous page without reading the kitties before it.
+    #[pallet::storage]
+    pub type KittiesByOwner<T: Config<I>, I: 'static = ()> = StorageDoubleMap<
+        _,
+        Blake2_128Concat, (T::AccountId, ClassIdOf<T, I>),
+        Identity, OrderedKittyIndexOf<T, I>,
+        (), OptionQuery
+    >;
+
+    /// The kitties of a collection that are for sale, sorted by their price (and id).
+    /// The keys are not hashed, so the floor price is the first entry of a collection, and
+    /// listing a kitty only touches its own entry however many kitties are for sale.
+    #[pallet::storage]
+    pub type ListingsByPrice<T: Config<I>, I: 'static = ()> = StorageDoubleMap<
+        _,
+        Twox64Concat, ClassIdOf<T, I>,
+        Identity, ListingKeyOf<T, I>,
+        (), OptionQuery
+    >;
+
+    /// The name of a kitty. None means the kitty has no name.
+    #[pallet::storage]
+    #[pallet::getter(fn kitty_name)]
+    pub type KittyNames<T: Config<I>, I: 'static = ()> = StorageDoubleMap<
+        _,
+        Twox64Concat, ClassIdOf<T, I>,
+        Blake2_128Concat, KittyIndexOf<T, I>,
+        Vec<u8>, OptionQuery
+    >;
+
+    /// Arbitrary metadata of a kitty
+    #[pallet::storage]
+    #[pallet::getter(fn kitty_metadata)]
+    pub type KittyMetadata<T: Config<I>, I: 'static = ()> = StorageDoubleMap<
+        _,
+        Twox64Concat, ClassIdOf<T, I>,
+        Blake2_128Concat, KittyIndexOf<T, I>,
+        Vec<u8>, OptionQuery
+    >;
+
+    /// The account that paid the deposit for the name and metadata of a kitty, and the deposit.
+    /// The name and metadata stay with the kitty when it is transferred, and the deposit is
+    /// reserved from the new owner instead. If the new owner can not pay it, the name and
+    /// metadata are cleared.
+    #[pallet::storage]
+    #[pallet::getter(fn kitty_deposit)]
+    pub type KittyDeposits<T: Config<I>, I: 'static = ()> = StorageDoubleMap<
+        _,
+        Twox64Concat, ClassIdOf<T, I>,
+        Blake2_128Concat, KittyIndexOf<T, I>,
+        (T::AccountId, BalanceOf<T, I>), OptionQuery
+    >;
+
+    /// Nonce for auto breed to prevent replay attack
+    #[pallet::storage]
+    #[pallet::getter(fn auto_breed_nonce)]
+    pub type AutoBreedNonce<T: Config<I>, I: 'static = ()> = StorageValue<_, u32, ValueQuery>;
+
+    /// The last block in which a kitty was a parent. None means it never bred.
+    /// It can breed again once the `breed_cooldown` parameter has passed since then.
+    #[pallet::storage]
+    #[pallet::getter(fn last_breed_block)]
+    pub type LastBreedBlock<T: Config<I>, I: 'static = ()> = StorageDoubleMap<
+        _,
+        Twox64Concat, ClassIdOf<T, I>,
+        Blake2_128Concat, KittyIndexOf<T, I>,
+        T::BlockNumber, OptionQuery
+    >;
+
+    /// Kitties whose owners opted in to automatic breeding.
+    /// The value is the position of the kitty in the `AutoBreedCandidates` of its collection.
+    #[pallet::storage]
+    #[pallet::getter(fn auto_breed_consent)]
+    pub type AutoBreedConsent<T: Config<I>, I: 'static = ()> = StorageDoubleMap<
+        _,
+        Twox64Concat, ClassIdOf<T, I>,
+        Blake2_128Concat, KittyIndexOf<T, I>,
+        u32, OptionQuery
+    >;
+
+    /// Index of the kitties of a collection that opted in to automatic breeding, so the
+    /// offchain worker can sample a random candidate without scanning every kitty
+    #[pallet::storage]
+    pub type AutoBreedCandidates<T: Config<I>, I: 'static = ()> = StorageDoubleMap<
+        _,
+        Twox64Concat, ClassIdOf<T, I>,
+        Twox64Concat, u32,
+        KittyIndexOf<T, I>, OptionQuery
+    >;
+
+    /// Number of entries in the `AutoBreedCandidates` of a collection.
+    /// Collections without candidates have no entry.
+    #[pallet::storage]
+    #[pallet::getter(fn auto_breed_candidate_count)]
+    pub type AutoBreedCandidateCount<T: Config<I>, I: 'static = ()> = StorageMap<
+        _,
+        Twox64Concat, ClassIdOf<T, I>,
+        u32, ValueQuery
+    >;
+
+    /// The account that may transfer a kitty on behalf of its owner.
+    /// Cleared whenever the kitty changes its owner.
+    #[pallet::storage]
+    #[pallet::getter(fn approved)]
+    pub type KittyApprovals<T: Config<I>, I: 'static = ()> = StorageDoubleMap<
+        _,
+        Twox64Concat, ClassIdOf<T, I>,
+        Blake2_128Concat, KittyIndexOf<T, I>,
+        T::AccountId, OptionQuery
+    >;
+
+    /// Operators that may transfer all kitties of an owner, keyed by the owner and the operator
+    #[pallet::storage]
+    pub type OperatorApprovals<T: Config<I>, I: 'static = ()> = StorageDoubleMap<
+        _,
+        Blake2_128Concat, T::AccountId,
+        Blake2_128Concat, T::AccountId,
+        (), OptionQuery
+    >;
+
+    /// The buy orders of a collection, highest price first (and oldest first for equal prices).
+    /// Bounded by the `max_buy_orders` parameter, so all orders can be matched against a kitty in a single read.
+    /// When the order book is full, a higher order evicts the last order.
+    #[pallet::storage]
+    #[pallet::getter(fn buy_orders)]
+    pub type BuyOrders<T: Config<I>, I: 'static = ()> = StorageMap<
+        _,
+        Twox64Concat, ClassIdOf<T, I>,
+        Vec<(OrderId, BuyOrderOf<T, I>)>, ValueQuery
+    >;
+
+    /// The id of the next buy order
+    #[pallet::storage]
+    pub type NextOrderId<T: Config<I>, I: 'static = ()> = StorageValue<_, OrderId, ValueQuery>;
+
+    /// The floor bids of a collection, highest amount first (and oldest first for equal amounts).
+    /// Together with the listings, this is the order book of a collection. Bounded by
+    /// the `max_floor_bids` parameter, a higher bid evicts the last bid when the order book is full.
+    #[pallet::storage]
+    #[pallet::getter(fn floor_bids)]
+    pub type FloorBids<T: Config<I>, I: 'static = ()> = StorageMap<
+        _,
+        Twox64Concat, ClassIdOf<T, I>,
+        Vec<(BidId, FloorBidOf<T, I>)>, ValueQuery
+    >;
+
+    /// The id of the next floor bid
+    #[pallet::storage]
+    pub type NextBidId<T: Config<I>, I: 'static = ()> = StorageValue<_, BidId, ValueQuery>;
+
+    /// The kitties that are bought in instalments
+    #[pallet::storage]
+    #[pallet::getter(fn layaway)]
+    pub type Layaways<T: Config<I>, I: 'static = ()> = StorageDoubleMap<
+        _,
+        Twox64Concat, ClassIdOf<T, I>,
+        Blake2_128Concat, KittyIndexOf<T, I>,
+        LayawayOf<T, I>, OptionQuery
+    >;
+
+    /// The loans that are backed by a kitty, by the kitty
+    #[pallet::storage]
+    #[pallet::getter(fn loan)]
+    pub type Loans<T: Config<I>, I: 'static = ()> = StorageDoubleMap<
+        _,
+        Twox64Concat, ClassIdOf<T, I>,
+        Blake2_128Concat, KittyIndexOf<T, I>,
+        LoanOf<T, I>, OptionQuery
+    >;
+
+    /// The id of the next loan
+    #[pallet::storage]
+    pub type NextLoanId<T: Config<I>, I: 'static = ()> = StorageValue<_, LoanId, ValueQuery>;
+
+    /// The vaults of fractionalised kitties, by the asset class of their shares. A vault stays
+    /// after its kitty is bought out, until all proceeds are claimed.
+    #[pallet::storage]
+    #[pallet::getter(fn vault)]
+    pub type Vaults<T: Config<I>, I: 'static = ()> = StorageMap<
+        _,
+        Blake2_128Concat, AssetIdOf<T, I>,
+        VaultOf<T, I>, OptionQuery
+    >;
+
+    /// The asset class of the shares of a kitty that is in a vault
+    #[pallet::storage]
+    #[pallet::getter(fn kitty_shares)]
+    pub type KittyShares<T: Config<I>, I: 'static = ()> = StorageDoubleMap<
+        _,
+        Twox64Concat, ClassIdOf<T, I>,
+        Blake2_128Concat, KittyIndexOf<T, I>,
+        AssetIdOf<T, I>, OptionQuery
+    >;
+
+    /// The first id to try for the asset class of the next vault
+    #[pallet::storage]
+    pub type NextAssetId<T: Config<I>, I: 'static = ()> = StorageValue<_, AssetIdOf<T, I>, ValueQuery>;
+
+    /// The last owners of a kitty, oldest first. Bounded by the `max_history_length` parameter.
+    /// Kept in the state, because the events are pruned by the nodes that are not archive nodes.
+    #[pallet::storage]
+    #[pallet::getter(fn ownership_history)]
+    pub type OwnershipHistory<T: Config<I>, I: 'static = ()> = StorageDoubleMap<
+        _,
+        Twox64Concat, ClassIdOf<T, I>,
+        Blake2_128Concat, KittyIndexOf<T, I>,
+        Vec<OwnershipRecordOf<T, I>>, ValueQuery
+    >;
+
+    /// The reason a kitty is locked for (see `KittyLockManager`). None means it is not locked.
+    #[pallet::storage]
+    #[pallet::getter(fn kitty_lock)]
+    pub type KittyLocks<T: Config<I>, I: 'static = ()> = StorageDoubleMap<
+        _,
+        Twox64Concat, ClassIdOf<T, I>,
+        Blake2_128Concat, KittyIndexOf<T, I>,
+        LockReason, OptionQuery
+    >;
+
+    /// The calls that are paused. The runtime rejects them in its `BaseCallFilter`.
+    #[pallet::storage]
+    pub type PausedCalls<T: Config<I>, I: 'static = ()> = StorageMap<
+        _,
+        Twox64Concat, CallKind,
+        (), OptionQuery
+    >;
+
+    /// The accounts that are blocked by the moderators. A blocked account can not create, breed,
+    /// buy, sell or receive kitties.
+    #[pallet::storage]
+    #[pallet::getter(fn blocked_account)]
+    pub type BlockedAccounts<T: Config<I>, I: 'static = ()> = StorageMap<
+        _,
+        Blake2_128Concat, T::AccountId,
+        BlockEntry<T::BlockNumber>, OptionQuery
+    >;
+
+    /// The accounts that are unblocked at the start of a block
+    #[pallet::storage]
+    pub type BlockExpiries<T: Config<I>, I: 'static = ()> = StorageMap<
+        _,
+        Twox64Concat, T::BlockNumber,
+        Vec<T::AccountId>, ValueQuery
+    >;
+
+    /// The layout of the storage. Chains that were started before the storage was versioned
+    /// have no value, which is read as `V1_0_0`.
+    #[pallet::storage]
+    #[pallet::getter(fn storage_version)]
+    pub type StorageVersion<T: Config<I>, I: 'static = ()> = StorageValue<_, Releases, ValueQuery>;
+
+    // define a hook for the offchain worker and the storage migrations
+    #[pallet::hooks]
+    impl<T: Config<I>, I: 'static> Hooks<T::BlockNumber> for Pallet<T, I> {
+        fn on_initialize(now: T::BlockNumber) -> Weight {
+            Self::expire_blocks(now)
+        }
+
+        fn offchain_worker(now: T::BlockNumber) {
+            let _ = Self::run_offchain_worker(now);
+        }
+
+        // the kitties of older versions of the pallet were stored in orml_nft, so they are
+        // migrated by `migrations::MigrateFromOrml`, which the runtime has to run
+        fn on_runtime_upgrade() -> Weight {
+            migrations::initialize::<T, I>()
+        }
+
+        // the default parameters are used before `set_parameters` is called for the first time,
+        // so they have to be valid as well
+        fn integrity_test() {
+            assert!(DefaultParameters::<T, I>::get().is_valid(), "the default parameters are invalid");
+        }
+    }
+
+    // initialize the collections at the genesis time
+    #[pallet::genesis_config]
+    pub struct GenesisConfig<T: Config<I>, I: 'static = ()> {
+        /// The collections that exist from the start. Their ids are assigned in order.
+        pub collections: Vec<KittyClassData>,
+        /// The parameters of the game
+        pub parameters: KittyParametersOf<T, I>,
+    }
+
+    #[cfg(feature = "std")]
+    impl<T: Config<I>, I: 'static> Default for GenesisConfig<T, I> {
+        fn default() -> Self {
+            GenesisConfig {
+                collections: vec![KittyClassData::default()],
+                parameters: DefaultParameters::<T, I>::get(),
+            }
+        }
+    }
+
+    #[pallet::genesis_build]
+    impl<T: Config<I>, I: 'static> GenesisBuild<T, I> for GenesisConfig<T, I> {
+        fn build(&self) {
+            assert!(self.parameters.is_valid(), "the difficulty must not be 0 and the limits must not exceed their caps");
+            Parameters::<T, I>::put(&self.parameters);
+
+            // create an NFT class for every collection
+            for data in &self.collections {
+                let class_id = T::Nft::create_class(&Default::default(), data.clone())
+                    .expect("Cannot fail or invalid chain spec");
+                Collections::<T, I>::insert(class_id, ());
+            }
+
+            // a new chain starts with the latest storage layout
+            StorageVersion::<T, I>::put(Releases::V3_0_0);
+        }
+    }
+
+	#[pallet::event]
+	#[pallet::generate_deposit(pub(super) fn deposit_event)]
+	#[pallet::metadata(T::AccountId = "AccountId", Option<T::AccountId> = "Option<AccountId>", ClassIdOf<T, I> = "ClassId", KittyIndexOf<T, I> = "KittyIndex", Option<BalanceOf<T, I>> = "Option<Balance>", BalanceOf<T, I> = "Balance", Option<T::BlockNumber> = "Option<BlockNumber>", Vec<KittyParameterOf<T, I>> = "Vec<KittyParameter>", AssetIdOf<T, I> = "AssetId")]
+	pub enum Event<T: Config<I>, I: 'static = ()> {
+		/// A kitty is created. \[owner, class_id, kitty_id, kitty\]
+		KittyCreated(T::AccountId, ClassIdOf<T, I>, KittyIndexOf<T, I>, Kitty),
+        /// A new kitten is bred. \[owner, class_id, kitty_id, kitty\]
+        KittyBred(T::AccountId, ClassIdOf<T, I>, KittyIndexOf<T, I>, Kitty),
+        /// A kitty is transferred. \[from, to, class_id, kitty_id\]
+        KittyTransferred(T::AccountId, T::AccountId, ClassIdOf<T, I>, KittyIndexOf<T, I>),
+        /// The price for a kitty is updated. \[owner, class_id, kitty_id, price\]
+        KittyPriceUpdated(T::AccountId, ClassIdOf<T, I>, KittyIndexOf<T, I>, Option<BalanceOf<T, I>>),
+        /// A kitty is sold. \[old_owner, new_owner, class_id, kitty_id, price\]
+        KittySold(T::AccountId, T::AccountId, ClassIdOf<T, I>, KittyIndexOf<T, I>, BalanceOf<T, I>),
+        /// The owner of a kitty changed its auto breed consent. \[owner, class_id, kitty_id, consent\]
+        AutoBreedConsentUpdated(T::AccountId, ClassIdOf<T, I>, KittyIndexOf<T, I>, bool),
+        /// The name of a kitty is updated. An empty name clears it. \[owner, class_id, kitty_id, name\]
+        KittyNameUpdated(T::AccountId, ClassIdOf<T, I>, KittyIndexOf<T, I>, Vec<u8>),
+        /// The metadata of a kitty is updated. \[owner, class_id, kitty_id\]
+        KittyMetadataUpdated(T::AccountId, ClassIdOf<T, I>, KittyIndexOf<T, I>),
+        /// A kitty is burned. \[owner, class_id, kitty_id\]
+        KittyBurned(T::AccountId, ClassIdOf<T, I>, KittyIndexOf<T, I>),
+        /// A new collection is created. \[class_id, data\]
+        CollectionCreated(ClassIdOf<T, I>, KittyClassData),
+        /// The approved account of a kitty changed. None clears the approval. \[owner, class_id, kitty_id, approved\]
+        Approval(T::AccountId, ClassIdOf<T, I>, KittyIndexOf<T, I>, Option<T::AccountId>),
+        /// An operator is allowed or no longer allowed to transfer all kitties of an owner. \[owner, operator, approved\]
+        ApprovalForAll(T::AccountId, T::AccountId, bool),
+        /// A kitty is locked. \[class_id, kitty_id, reason\]
+        KittyLocked(ClassIdOf<T, I>, KittyIndexOf<T, I>, LockReason),
+        /// A kitty is unlocked. \[class_id, kitty_id, reason\]
+        KittyUnlocked(ClassIdOf<T, I>, KittyIndexOf<T, I>, LockReason),
+        /// The parameters of the game are updated. \[changes\]
+        ParametersUpdated(Vec<KittyParameterOf<T, I>>),
+        /// A kind of calls is paused. \[call_kind\]
+        CallPaused(CallKind),
+        /// A kind of calls is no longer paused. \[call_kind\]
+        CallUnpaused(CallKind),
+        /// An account is blocked. \[who, reason, expiry\]
+        AccountBlocked(T::AccountId, Vec<u8>, Option<T::BlockNumber>),
+        /// An account is unblocked, by the moderators or because its block expired. \[who\]
+        AccountUnblocked(T::AccountId),
+        /// A buy order is placed. \[buyer, class_id, order_id, max_price\]
+        BuyOrderPlaced(T::AccountId, ClassIdOf<T, I>, OrderId, BalanceOf<T, I>),
+        /// A buy order is cancelled or evicted by a higher order, and its funds are unreserved. \[class_id, order_id\]
+        BuyOrderCancelled(ClassIdOf<T, I>, OrderId),
+        /// A kitty is sold into a buy order. \[class_id, order_id, kitty_id\]
+        BuyOrderFilled(ClassIdOf<T, I>, OrderId, KittyIndexOf<T, I>),
+        /// A floor bid is placed. \[bidder, class_id, bid_id, amount, quantity\]
+        FloorBidPlaced(T::AccountId, ClassIdOf<T, I>, BidId, BalanceOf<T, I>, u32),
+        /// A floor bid is cancelled or evicted by a higher bid, and the funds for its remaining quantity are unreserved. \[class_id, bid_id, refund\]
+        FloorBidCancelled(ClassIdOf<T, I>, BidId, BalanceOf<T, I>),
+        /// A kitty is sold into a floor bid. \[class_id, bid_id, kitty_id, remaining_quantity\]
+        FloorBidFilled(ClassIdOf<T, I>, BidId, KittyIndexOf<T, I>, u32),
+        /// A kitty is bought in instalments. \[buyer, class_id, kitty_id, price, deposit, deadline\]
+        LayawayStarted(T::AccountId, ClassIdOf<T, I>, KittyIndexOf<T, I>, BalanceOf<T, I>, BalanceOf<T, I>, T::BlockNumber),
+        /// An instalment is paid. \[class_id, kitty_id, amount, remaining\]
+        InstalmentPaid(ClassIdOf<T, I>, KittyIndexOf<T, I>, BalanceOf<T, I>, BalanceOf<T, I>),
+        /// The buyer did not pay in time, the seller keeps a share of the deposit. \[class_id, kitty_id, forfeit\]
+        LayawayDefaulted(ClassIdOf<T, I>, KittyIndexOf<T, I>, BalanceOf<T, I>),
+        /// A kitty is escrowed to back a loan. \[borrower, class_id, kitty_id, principal, interest, duration\]
+        LoanRequested(T::AccountId, ClassIdOf<T, I>, KittyIndexOf<T, I>, BalanceOf<T, I>, BalanceOf<T, I>, T::BlockNumber),
+        /// A loan request is cancelled and the kitty is returned. \[class_id, kitty_id\]
+        LoanCancelled(ClassIdOf<T, I>, KittyIndexOf<T, I>),
+        /// A loan is funded. \[lender, class_id, kitty_id, deadline\]
+        LoanFunded(T::AccountId, ClassIdOf<T, I>, KittyIndexOf<T, I>, T::BlockNumber),
+        /// A loan is repaid and the kitty is returned. \[class_id, kitty_id, amount\]
+        LoanRepaid(ClassIdOf<T, I>, KittyIndexOf<T, I>, BalanceOf<T, I>),
+        /// A loan was not repaid in time and the lender claimed the kitty. \[lender, class_id, kitty_id\]
+        CollateralClaimed(T::AccountId, ClassIdOf<T, I>, KittyIndexOf<T, I>),
+        /// A kitty is put into a vault and split into shares. \[owner, class_id, kitty_id, asset_id, shares, reserve_price\]
+        KittyFractionalised(T::AccountId, ClassIdOf<T, I>, KittyIndexOf<T, I>, AssetIdOf<T, I>, BalanceOf<T, I>, BalanceOf<T, I>),
+        /// All shares of a kitty are burned and the kitty left the vault. \[who, class_id, kitty_id\]
+        KittyRedeemed(T::AccountId, ClassIdOf<T, I>, KittyIndexOf<T, I>),
+        /// A kitty is bought out of its vault for the reserve price. \[buyer, class_id, kitty_id, price\]
+        KittyBoughtOut(T::AccountId, ClassIdOf<T, I>, KittyIndexOf<T, I>, BalanceOf<T, I>),
+        /// Shares of a kitty that was bought out are burned for their part of the proceeds. \[who, asset_id, shares, amount\]
+        ProceedsClaimed(T::AccountId, AssetIdOf<T, I>, BalanceOf<T, I>, BalanceOf<T, I>),
+	}
+
+    #[pallet::error]
+    pub enum Error<T, I = ()> {
+        InvalidKittyId,
+        SameGender,
+        NotOwner,
+        NotForSale,
+        PriceTooLow,
+        BuyFromSelf,
+        NoAutoBreedConsent,
+        InvalidNonce,
+        KittyOnCooldown,
+        NameTooLong,
+        InvalidName,
+        MetadataTooLong,
+        InvalidCollection,
+        MaxSupplyReached,
+        BreedingDisabled,
+        NotApproved,
+        ApproveToSelf,
+        KittyLocked,
+        NotLocked,
+        InvalidParameters,
+        AlreadyPaused,
+        NotPaused,
+        AccountBlocked,
+        NotBlocked,
+        ReasonTooLong,
+        InvalidExpiry,
+        TooManyBuyOrders,
+        UnknownOrder,
+        OrderMismatch,
+        TooManyFloorBids,
+        UnknownBid,
+        InvalidQuantity,
+        NoLayaway,
+        LayawayExpired,
+        LayawayNotExpired,
+        NoLoan,
+        LoanFunded,
+        LoanNotFunded,
+        LoanExpired,
+        LoanNotExpired,
+        InvalidLoanTerms,
+        NoVault,
+        InvalidShares,
+        NotAllShares,
+        NoShares,
+        BoughtOut,
+        NotBoughtOut,
+        ProceedsBelowExistentialDeposit,
+    }
+
+	#[pallet::pallet]
+    #[pallet::generate_store(pub(super) trait Store)]
+	pub struct Pallet<T, I = ()>(PhantomData<(T, I)>);
+
+	#[pallet::call]
+	impl<T: Config<I>, I: 'static> Pallet<T, I> {
+
+		/// Create a new kitty in a collection
+		#[pallet::weight(T::WeightInfo::create())]
+		pub fn create(origin: OriginFor<T>, class_id: ClassIdOf<T, I>) -> DispatchResult {
+			let sender = ensure_signed(origin)?;
+            Self::ensure_not_blocked(&sender)?;
+
+            let dna = Self::random_value(&sender);
+
+			// Create and store kitty
+			let kitty = Kitty::new(dna);
+            let kitty_id = Self::mint(&sender, class_id, kitty.clone())?;
+            Self::record_owner_change(class_id, kitty_id, None, sender.clone(), OwnershipChange::Mint);
+
+			// Emit event
+			Self::deposit_event(Event::KittyCreated(sender, class_id, kitty_id, kitty));
+
+			Ok(())
+		}
+
+        /// Breed kitties
+        /// The breeder pays the `breed_fee` parameter
+        #[pallet::weight(T::WeightInfo::breed())]
+        #[transactional]
+        pub fn breed(origin: OriginFor<T>, class_id: ClassIdOf<T, I>, kitty_id_1: KittyIndexOf<T, I>, kitty_id_2: KittyIndexOf<T, I>) -> DispatchResult {
+            // get the sender
+            let sender = ensure_signed(origin)?;
+            Self::ensure_not_blocked(&sender)?;
+
+            // use the kitties getter (Self::kitties) to get the kitties from their ids
+            // since the getter returns an optional kitty, check if it is Ok or None
+            //  if the getter returns None, the kitty does not exist,
+            //  so early return InvalidKittyId to the calling function
+            //  (because of the ?)
+            let kitty1 = Self::kitties(&sender, class_id, kitty_id_1).ok_or(Error::<T, I>::InvalidKittyId)?;
+            let kitty2 = Self::kitties(&sender, class_id, kitty_id_2).ok_or(Error::<T, I>::InvalidKittyId)?;
+
+            let fee = Self::parameters().breed_fee;
+            if !fee.is_zero() {
+                // the fee is burned
+                T::Currency::withdraw(&sender, fee, WithdrawReasons::FEE, ExistenceRequirement::KeepAlive)?;
+            }
+
+            Self::do_breed(sender, class_id, (kitty_id_1, kitty1), (kitty_id_2, kitty2))
+        }
+
+        /// Transfer a kitty to a new owner
+        #[pallet::weight(T::WeightInfo::transfer())]
+        pub fn transfer(origin: OriginFor<T>, to: T::AccountId, class_id: ClassIdOf<T, I>, kitty_id: KittyIndexOf<T, I>) -> DispatchResult {
+            let sender = ensure_signed(origin)?;
+
+            Self::do_transfer(sender, to, class_id, kitty_id)
+        }
+
+        /// Set a price for a kitty for sale
+        /// None to delist the kitty
+        /// If a buy order pays at least the price for the kitty, the kitty is sold into the order
+        /// right away, for the price
+        #[pallet::weight(T::WeightInfo::set_price().max(T::WeightInfo::fill_order()))]
+        #[transactional]
+        pub fn set_price(origin: OriginFor<T>, class_id: ClassIdOf<T, I>, kitty_id: KittyIndexOf<T, I>, new_price: Option<BalanceOf<T, I>>) -> DispatchResult {
+            let sender = ensure_signed(origin)?;
+
+            // ensure the sender is the owner of the kitty id
+            ensure!(Self::is_owner(&sender, class_id, kitty_id), Error::<T, I>::NotOwner);
+            // a locked kitty can not be sold, and a blocked account can not sell, but a kitty can
+            // always be delisted
+            if let Some(price) = new_price {
+                Self::ensure_unlocked(class_id, kitty_id)?;
+                Self::ensure_not_blocked(&sender)?;
+
+                if let Some((order_id, order)) = Self::take_matching_buy_order(&sender, class_id, kitty_id, price) {
+                    return Self::execute_buy_order(class_id, order_id, order, sender, kitty_id, price);
+                }
+            }
+
+            // keep the sorted listings in sync
+            Self::update_listing(class_id, kitty_id, Self::kitty_prices(class_id, kitty_id), new_price);
+
+            // set the price
+            KittyPrices::<T, I>::mutate_exists(class_id, kitty_id, |price| *price = new_price);
+            // mutate_exists() will check if the new_price is None and add new_price to KittyPrices
+            // if it is not None.
+            // Otherwise, it will remove the kitty_id from KittyPrices
+
+            Self::deposit_event(Event::KittyPriceUpdated(sender, class_id, kitty_id, new_price));
+
+            Ok(())
+        }
+
+        /// Buy a kitty
+        #[pallet::weight(T::WeightInfo::buy())]
+        #[transactional]
+        pub fn buy(origin: OriginFor<T>, owner: T::AccountId, class_id: ClassIdOf<T, I>, kitty_id: KittyIndexOf<T, I>, max_price: BalanceOf<T, I>) -> DispatchResult {
+            let sender = ensure_signed(origin)?;
+
+            // you should not be able to buy a kitty from yourself
+            // (this could also be an early return without an error message,
+            // but it does not really make sense to buy a kitty from yourself)
+            ensure!(sender != owner, Error::<T, I>::BuyFromSelf);
+            // the kitties that a blocked account listed before it was blocked can not be bought
+            // either, so the account can not sell stolen kitties
+            Self::ensure_not_blocked(&sender)?;
+            Self::ensure_not_blocked(&owner)?;
+
+            // read and delete the kitty price
+            KittyPrices::<T, I>::try_mutate_exists(class_id, kitty_id, |price| -> DispatchResult {
+                // remove the price of the kitty (and ensure it is actually for sale) as it
+                // will be bought
+                let price = price.take().ok_or(Error::<T, I>::NotForSale)?;
+                Self::remove_listing(class_id, kitty_id, price);
+
+                // the new owner has not agreed to auto breeding, nor approved anyone
+                Self::remove_auto_breed_candidate(class_id, kitty_id);
+                KittyApprovals::<T, I>::remove(class_id, kitty_id);
+
+                // ensure the buyer is not overpaying
+                ensure!(max_price >= price, Error::<T, I>::PriceTooLow);
+
+                // do the actual transfer
+
+                // since now both transfers can fail, they should be atomic
+                //  (which is done by #[transactional], which will revert all storages
+                //  that were changed in buy()'s body, if something fails)
+
+                // send `price` from the sender to the owner of the kitty
+                //  ExistenceRequirement::KeepAlive will ensure that the transfer will not kill
+                //  the account of the sender if there is no more money left
+                //  (the price is paid first, so the deposit for the name of the kitty is only
+                //  reserved from what is left)
+                T::Currency::transfer(&sender, &owner, price, ExistenceRequirement::KeepAlive)?;
+
+                // tranfer the ownership of the kitty
+                Self::transfer_kitty(&owner, &sender, class_id, kitty_id)?;
+                Self::record_owner_change(class_id, kitty_id, Some(owner.clone()), sender.clone(), OwnershipChange::Sale(price));
+
+                Self::deposit_event(Event::KittySold(owner, sender, class_id, kitty_id, price));
+
+                Ok(())
+            })
+        }
+
+        /// Place an order to buy any kitty of a collection whose dna matches `value` in the bits
+        /// that are selected by `mask`, for at most `max_price`
+        /// The price is reserved until the order is filled or cancelled
+        /// If the order book is full, the order has to pay more than the lowest order, which is
+        /// cancelled
+        #[pallet::weight(T::WeightInfo::place_buy_order())]
+        #[transactional]
+        pub fn place_buy_order(origin: OriginFor<T>, class_id: ClassIdOf<T, I>, mask: [u8; 16], value: [u8; 16], max_price: BalanceOf<T, I>) -> DispatchResult {
+            let buyer = ensure_signed(origin)?;
+
+            ensure!(max_price >= Self::parameters().min_order_price, Error::<T, I>::PriceTooLow);
+            Self::ensure_not_blocked(&buyer)?;
+            Self::ensure_collection(class_id)?;
+
+            let order_id = Self::next_order_id()?;
+            let evicted = BuyOrders::<T, I>::try_mutate(class_id, |orders| -> Result<_, DispatchError> {
+                // the lowest order is the last one, and of the lowest orders the newest one
+                let evicted = if (orders.len() as u32) >= Self::parameters().max_buy_orders {
+                    ensure!(orders.last().map_or(false, |(_, order)| order.max_price < max_price), Error::<T, I>::TooManyBuyOrders);
+                    orders.pop()
+                } else {
+                    None
+                };
+
+                T::Currency::reserve(&buyer, max_price)?;
+
+                // the new order goes after the orders with the same price, so older orders are
+                // filled first
+                let index = orders.iter().position(|(_, order)| order.max_price < max_price).unwrap_or_else(|| orders.len());
+                orders.insert(index, (order_id, BuyOrder { buyer: buyer.clone(), mask, value, max_price }));
+
+                Ok(evicted)
+            })?;
+
+            if let Some((evicted_id, evicted)) = evicted {
+                T::Currency::unreserve(&evicted.buyer, evicted.max_price);
+                Self::deposit_event(Event::BuyOrderCancelled(class_id, evicted_id));
+            }
+
+            Self::deposit_event(Event::BuyOrderPlaced(buyer, class_id, order_id, max_price));
+
+            Ok(())
+        }
+
+        /// Cancel a buy order and unreserve its funds
+        #[pallet::weight(T::WeightInfo::cancel_buy_order())]
+        pub fn cancel_buy_order(origin: OriginFor<T>, class_id: ClassIdOf<T, I>, order_id: OrderId) -> DispatchResult {
+            let sender = ensure_signed(origin)?;
+
+            let order = Self::take_buy_order(class_id, order_id, |order| {
+                ensure!(order.buyer == sender, Error::<T, I>::NotOwner);
+                Ok(())
+            })?;
+            T::Currency::unreserve(&order.buyer, order.max_price);
+
+            Self::deposit_event(Event::BuyOrderCancelled(class_id, order_id));
+
+            Ok(())
+        }
+
+        /// Sell a kitty into a buy order, for the highest price of the order
+        #[pallet::weight(T::WeightInfo::fill_order())]
+        #[transactional]
+        pub fn fill_order(origin: OriginFor<T>, class_id: ClassIdOf<T, I>, order_id: OrderId, kitty_id: KittyIndexOf<T, I>) -> DispatchResult {
+            let sender = ensure_signed(origin)?;
+
+            let kitty = Self::kitties(&sender, class_id, kitty_id).ok_or(Error::<T, I>::NotOwner)?;
+            Self::ensure_not_blocked(&sender)?;
+
+            let order = Self::take_buy_order(class_id, order_id, |order| {
+                ensure!(order.buyer != sender, Error::<T, I>::BuyFromSelf);
+                ensure!(order.matches(&kitty), Error::<T, I>::OrderMismatch);
+                Ok(())
+            })?;
+            let price = order.max_price;
+
+            Self::execute_buy_order(class_id, order_id, order, sender, kitty_id, price)
+        }
+
+        /// Bid `amount` for each of `quantity` kitties of a collection, whatever kitties they are
+        /// The funds for all kitties are reserved until they are bought or the bid is cancelled
+        /// If the order book is full, the bid has to be higher than the lowest bid, which is
+        /// cancelled
+        #[pallet::weight(T::WeightInfo::place_floor_bid())]
+        #[transactional]
+        pub fn place_floor_bid(origin: OriginFor<T>, class_id: ClassIdOf<T, I>, amount: BalanceOf<T, I>, quantity: u32) -> DispatchResult {
+            let bidder = ensure_signed(origin)?;
+
+            ensure!(quantity > 0, Error::<T, I>::InvalidQuantity);
+            ensure!(amount >= Self::parameters().min_order_price, Error::<T, I>::PriceTooLow);
+            Self::ensure_not_blocked(&bidder)?;
+            Self::ensure_collection(class_id)?;
+
+            let total = amount.checked_mul(&BalanceOf::<T, I>::from(quantity)).ok_or(ArithmeticError::Overflow)?;
+            let bid_id = Self::next_bid_id()?;
+            let evicted = FloorBids::<T, I>::try_mutate(class_id, |bids| -> Result<_, DispatchError> {
+                // the lowest bid is the last one, and of the lowest bids the newest one
+                let evicted = if (bids.len() as u32) >= Self::parameters().max_floor_bids {
+                    ensure!(bids.last().map_or(false, |(_, bid)| bid.amount < amount), Error::<T, I>::TooManyFloorBids);
+                    bids.pop()
+                } else {
+                    None
+                };
+
+                T::Currency::reserve(&bidder, total)?;
+
+                // the new bid goes after the bids with the same amount, so older bids are
+                // listed first
+                let index = bids.iter().position(|(_, bid)| bid.amount < amount).unwrap_or_else(|| bids.len());
+                bids.insert(index, (bid_id, FloorBid { bidder: bidder.clone(), amount, quantity }));
+
+                Ok(evicted)
+            })?;
+
+            if let Some((evicted_id, evicted)) = evicted {
+                let refund = evicted.amount * BalanceOf::<T, I>::from(evicted.quantity);
+                T::Currency::unreserve(&evicted.bidder, refund);
+                Self::deposit_event(Event::FloorBidCancelled(class_id, evicted_id, refund));
+            }
+
+            Self::deposit_event(Event::FloorBidPlaced(bidder, class_id, bid_id, amount, quantity));
+
+            Ok(())
+        }
+
+        /// Cancel the remaining quantity of a floor bid and unreserve its funds
+        #[pallet::weight(T::WeightInfo::cancel_floor_bid())]
+        pub fn cancel_floor_bid(origin: OriginFor<T>, class_id: ClassIdOf<T, I>, bid_id: BidId) -> DispatchResult {
+            let sender = ensure_signed(origin)?;
+
+            let bid = Self::take_floor_bid(class_id, bid_id, |bid| {
+                ensure!(bid.bidder == sender, Error::<T, I>::NotOwner);
+                Ok(())
+            })?;
+            let refund = bid.amount * BalanceOf::<T, I>::from(bid.quantity);
+            T::Currency::unreserve(&bid.bidder, refund);
+
+            Self::deposit_event(Event::FloorBidCancelled(class_id, bid_id, refund));
+
+            Ok(())
+        }
+
+        /// Sell any kitty of the collection into a floor bid, for the amount of the bid
+        #[pallet::weight(T::WeightInfo::sell_into_bid())]
+        #[transactional]
+        pub fn sell_into_bid(origin: OriginFor<T>, class_id: ClassIdOf<T, I>, bid_id: BidId, kitty_id: KittyIndexOf<T, I>) -> DispatchResult {
+            let sender = ensure_signed(origin)?;
+
+            ensure!(Self::is_owner(&sender, class_id, kitty_id), Error::<T, I>::NotOwner);
+            Self::ensure_not_blocked(&sender)?;
+
+            let bid = FloorBids::<T, I>::try_mutate_exists(class_id, |maybe_bids| -> Result<FloorBidOf<T, I>, DispatchError> {
+                let bids = maybe_bids.as_mut().ok_or(Error::<T, I>::UnknownBid)?;
+                let index = bids.iter().position(|(id, _)| *id == bid_id).ok_or(Error::<T, I>::UnknownBid)?;
+                ensure!(bids[index].1.bidder != sender, Error::<T, I>::BuyFromSelf);
+
+                // a bid is partially filled until its quantity is used up
+                bids[index].1.quantity -= 1;
+                let bid = if bids[index].1.quantity == 0 {
+                    bids.remove(index).1
+                } else {
+                    bids[index].1.clone()
+                };
+                if bids.is_empty() {
+                    *maybe_bids = None;
+                }
+
+                Ok(bid)
+            })?;
+
+            Self::sell_from_reserve(class_id, sender, bid.bidder, kitty_id, bid.amount, bid.amount)?;
+            Self::deposit_event(Event::FloorBidFilled(class_id, bid_id, kitty_id, bid.quantity));
+
+            Ok(())
+        }
+
+        /// Buy a kitty that is for sale in instalments
+        /// A share of the price is reserved as deposit, and the kitty is locked until the rest of
+        /// the price is paid with `pay_instalment`, or the deadline passes
+        #[pallet::weight(T::WeightInfo::start_layaway())]
+        #[transactional]
+        pub fn start_layaway(origin: OriginFor<T>, owner: T::AccountId, class_id: ClassIdOf<T, I>, kitty_id: KittyIndexOf<T, I>, max_price: BalanceOf<T, I>) -> DispatchResult {
+            let sender = ensure_signed(origin)?;
+
+            ensure!(sender != owner, Error::<T, I>::BuyFromSelf);
+            ensure!(Self::is_owner(&owner, class_id, kitty_id), Error::<T, I>::NotOwner);
+            Self::ensure_not_blocked(&sender)?;
+            Self::ensure_not_blocked(&owner)?;
+
+            let price = Self::kitty_prices(class_id, kitty_id).ok_or(Error::<T, I>::NotForSale)?;
+            ensure!(max_price >= price, Error::<T, I>::PriceTooLow);
+
+            let parameters = Self::parameters();
+            let deposit = parameters.layaway_deposit * price;
+            T::Currency::reserve(&sender, deposit)?;
+
+            // locking the kitty also delists it
+            <Self as KittyLockManager<_, _>>::lock(class_id, kitty_id, LockReason::Layaway)?;
+
+            let deadline = frame_system::Pallet::<T>::block_number() + parameters.layaway_period;
+            Layaways::<T, I>::insert(class_id, kitty_id, Layaway {
+                buyer: sender.clone(),
+                seller: owner,
+                price,
+                deposit,
+                paid: deposit,
+                deadline,
+            });
+
+            Self::deposit_event(Event::LayawayStarted(sender, class_id, kitty_id, price, deposit, deadline));
+
+            Ok(())
+        }
+
+        /// Pay an instalment for a kitty that is bought in instalments
+        /// The kitty is transferred to the buyer with the final payment, anything that is paid
+        /// above the price is not charged
+        #[pallet::weight(T::WeightInfo::pay_instalment())]
+        #[transactional]
+        pub fn pay_instalment(origin: OriginFor<T>, class_id: ClassIdOf<T, I>, kitty_id: KittyIndexOf<T, I>, amount: BalanceOf<T, I>) -> DispatchResult {
+            let sender = ensure_signed(origin)?;
+
+            let mut layaway = Self::layaway(class_id, kitty_id).ok_or(Error::<T, I>::NoLayaway)?;
+            ensure!(layaway.buyer == sender, Error::<T, I>::NotOwner);
+            ensure!(frame_system::Pallet::<T>::block_number() <= layaway.deadline, Error::<T, I>::LayawayExpired);
+
+            let amount = amount.min(layaway.price - layaway.paid);
+            T::Currency::reserve(&sender, amount)?;
+            layaway.paid += amount;
+
+            let remaining = layaway.price - layaway.paid;
+            Self::deposit_event(Event::InstalmentPaid(class_id, kitty_id, amount, remaining));
+
+            // the blocklist is only checked when the layaway starts, so a buyer that is blocked
+            // afterwards can still finish paying instead of forfeiting the deposit. This is the
+            // only way a blocked account can receive a kitty.
+            if remaining.is_zero() {
+                Layaways::<T, I>::remove(class_id, kitty_id);
+                <Self as KittyLockManager<_, _>>::unlock(class_id, kitty_id, LockReason::Layaway)?;
+                Self::move_kitty(&layaway.seller, &layaway.buyer, class_id, kitty_id)?;
+                Self::settle_sale(class_id, layaway.seller, layaway.buyer, kitty_id, layaway.price, layaway.paid)?;
+            } else {
+                Layaways::<T, I>::insert(class_id, kitty_id, layaway);
+            }
+
+            Ok(())
+        }
+
+        /// End the purchase of a kitty in instalments after the buyer missed the deadline
+        /// The seller keeps a share of the deposit, the rest of the payments is refunded, and the
+        /// kitty is listed for its old price again
+        /// Anyone can end a defaulted layaway
+        #[pallet::weight(T::WeightInfo::claim_layaway_default())]
+        #[transactional]
+        pub fn claim_layaway_default(origin: OriginFor<T>, class_id: ClassIdOf<T, I>, kitty_id: KittyIndexOf<T, I>) -> DispatchResult {
+            ensure_signed(origin)?;
+
+            let layaway = Self::layaway(class_id, kitty_id).ok_or(Error::<T, I>::NoLayaway)?;
+            ensure!(frame_system::Pallet::<T>::block_number() > layaway.deadline, Error::<T, I>::LayawayNotExpired);
+
+            Layaways::<T, I>::remove(class_id, kitty_id);
+            <Self as KittyLockManager<_, _>>::unlock(class_id, kitty_id, LockReason::Layaway)?;
+
+            let forfeit = Self::parameters().layaway_forfeit * layaway.deposit;
+            T::Currency::unreserve(&layaway.buyer, layaway.paid);
+            T::Currency::transfer(&layaway.buyer, &layaway.seller, forfeit, ExistenceRequirement::AllowDeath)?;
+
+            // the kitty is listed again at its old price
+            Self::update_listing(class_id, kitty_id, None, Some(layaway.price));
+            KittyPrices::<T, I>::insert(class_id, kitty_id, layaway.price);
+            Self::deposit_event(Event::KittyPriceUpdated(layaway.seller, class_id, kitty_id, Some(layaway.price)));
+
+            Self::deposit_event(Event::LayawayDefaulted(class_id, kitty_id, forfeit));
+
+            Ok(())
+        }
+
+        /// Ask for a loan of `principal` that is repaid with `interest` within `duration` blocks
+        /// after it is funded, and escrow a kitty as collateral
+        #[pallet::weight(T::WeightInfo::request_loan())]
+        #[transactional]
+        pub fn request_loan(origin: OriginFor<T>, class_id: ClassIdOf<T, I>, kitty_id: KittyIndexOf<T, I>, principal: BalanceOf<T, I>, interest: BalanceOf<T, I>, duration: T::BlockNumber) -> DispatchResult {
+            let sender = ensure_signed(origin)?;
+
+            ensure!(!principal.is_zero() && !duration.is_zero(), Error::<T, I>::InvalidLoanTerms);
+            ensure!(Self::is_owner(&sender, class_id, kitty_id), Error::<T, I>::NotOwner);
+            Self::ensure_not_blocked(&sender)?;
+
+            let loan_id = Self::next_loan_id()?;
+            let escrow = Self::loan_account(loan_id);
+            Self::transfer_kitty(&sender, &escrow, class_id, kitty_id)?;
+            Self::on_owner_changed(class_id, kitty_id);
+            Self::record_owner_change(class_id, kitty_id, Some(sender.clone()), escrow, OwnershipChange::Transfer);
+
+            Loans::<T, I>::insert(class_id, kitty_id, Loan {
+                id: loan_id,
+                borrower: sender.clone(),
+                principal,
+                interest,
+                duration,
+                funded: None,
+            });
+
+            Self::deposit_event(Event::LoanRequested(sender, class_id, kitty_id, principal, interest, duration));
+
+            Ok(())
+        }
+
+        /// Cancel a loan request that is not funded yet and get the kitty back
+        #[pallet::weight(T::WeightInfo::cancel_loan())]
+        #[transactional]
+        pub fn cancel_loan(origin: OriginFor<T>, class_id: ClassIdOf<T, I>, kitty_id: KittyIndexOf<T, I>) -> DispatchResult {
+            let sender = ensure_signed(origin)?;
+
+            let loan = Self::loan(class_id, kitty_id).ok_or(Error::<T, I>::NoLoan)?;
+            ensure!(loan.borrower == sender, Error::<T, I>::NotOwner);
+            ensure!(loan.funded.is_none(), Error::<T, I>::LoanFunded);
+
+            Loans::<T, I>::remove(class_id, kitty_id);
+            Self::release_collateral(&loan, &sender, class_id, kitty_id)?;
+
+            Self::deposit_event(Event::LoanCancelled(class_id, kitty_id));
+
+            Ok(())
+        }
+
+        /// Fund a loan, the principal is paid to the borrower right away
+        #[pallet::weight(T::WeightInfo::fund_loan())]
+        #[transactional]
+        pub fn fund_loan(origin: OriginFor<T>, class_id: ClassIdOf<T, I>, kitty_id: KittyIndexOf<T, I>) -> DispatchResult {
+            let sender = ensure_signed(origin)?;
+
+            Self::ensure_not_blocked(&sender)?;
+
+            let deadline = Loans::<T, I>::try_mutate(class_id, kitty_id, |maybe_loan| -> Result<T::BlockNumber, DispatchError> {
+                let loan = maybe_loan.as_mut().ok_or(Error::<T, I>::NoLoan)?;
+                ensure!(loan.funded.is_none(), Error::<T, I>::LoanFunded);
+                ensure!(loan.borrower != sender, Error::<T, I>::BuyFromSelf);
+
+                T::Currency::transfer(&sender, &loan.borrower, loan.principal, ExistenceRequirement::KeepAlive)?;
+
+                let deadline = frame_system::Pallet::<T>::block_number() + loan.duration;
+                loan.funded = Some((sender.clone(), deadline));
+
+                Ok(deadline)
+            })?;
+
+            Self::deposit_event(Event::LoanFunded(sender, class_id, kitty_id, deadline));
+
+            Ok(())
+        }
+
+        /// Repay a loan with its interest and get the kitty back
+        #[pallet::weight(T::WeightInfo::repay_loan())]
+        #[transactional]
+        pub fn repay_loan(origin: OriginFor<T>, class_id: ClassIdOf<T, I>, kitty_id: KittyIndexOf<T, I>) -> DispatchResult {
+            let sender = ensure_signed(origin)?;
+
+            let loan = Self::loan(class_id, kitty_id).ok_or(Error::<T, I>::NoLoan)?;
+            ensure!(loan.borrower == sender, Error::<T, I>::NotOwner);
+            let (lender, deadline) = loan.funded.ok_or(Error::<T, I>::LoanNotFunded)?;
+            ensure!(frame_system::Pallet::<T>::block_number() <= deadline, Error::<T, I>::LoanExpired);
+
+            let amount = loan.principal.checked_add(&loan.interest).ok_or(ArithmeticError::Overflow)?;
+            T::Currency::transfer(&sender, &lender, amount, ExistenceRequirement::KeepAlive)?;
+
+            Loans::<T, I>::remove(class_id, kitty_id);
+            Self::release_collateral(&loan, &sender, class_id, kitty_id)?;
+
+            Self::deposit_event(Event::LoanRepaid(class_id, kitty_id, amount));
+
+            Ok(())
+        }
+
+        /// Claim the kitty that backs a loan that was not repaid in time
+        #[pallet::weight(T::WeightInfo::claim_collateral())]
+        #[transactional]
+        pub fn claim_collateral(origin: OriginFor<T>, class_id: ClassIdOf<T, I>, kitty_id: KittyIndexOf<T, I>) -> DispatchResult {
+            let sender = ensure_signed(origin)?;
+
+            let loan = Self::loan(class_id, kitty_id).ok_or(Error::<T, I>::NoLoan)?;
+            let (lender, deadline) = loan.funded.ok_or(Error::<T, I>::LoanNotFunded)?;
+            ensure!(lender == sender, Error::<T, I>::NotOwner);
+            ensure!(frame_system::Pallet::<T>::block_number() > deadline, Error::<T, I>::LoanNotExpired);
+
+            Loans::<T, I>::remove(class_id, kitty_id);
+            Self::release_collateral(&loan, &sender, class_id, kitty_id)?;
+
+            Self::deposit_event(Event::CollateralClaimed(sender, class_id, kitty_id));
+
+            Ok(())
+        }
+
+        /// Put a kitty into a vault and mint `shares` shares of it for the owner, in a new asset
+        /// class. Anyone can buy the kitty out of the vault for `reserve_price`.
+        #[pallet::weight(T::WeightInfo::fractionalise())]
+        #[transactional]
+        pub fn fractionalise(origin: OriginFor<T>, class_id: ClassIdOf<T, I>, kitty_id: KittyIndexOf<T, I>, shares: BalanceOf<T, I>, reserve_price: BalanceOf<T, I>) -> DispatchResult {
+            let sender = ensure_signed(origin)?;
+
+            ensure!(!shares.is_zero(), Error::<T, I>::InvalidShares);
+            ensure!(!reserve_price.is_zero(), Error::<T, I>::PriceTooLow);
+            ensure!(Self::is_owner(&sender, class_id, kitty_id), Error::<T, I>::NotOwner);
+            Self::ensure_not_blocked(&sender)?;
+
+            let asset_id = Self::next_asset_id()?;
+            let vault_account = Self::vault_account(asset_id);
+            T::Shares::create(asset_id, &vault_account)?;
+            T::Shares::mint(asset_id, &sender, shares)?;
+
+            Self::transfer_kitty(&sender, &vault_account, class_id, kitty_id)?;
+            Self::on_owner_changed(class_id, kitty_id);
+            Self::record_owner_change(class_id, kitty_id, Some(sender.clone()), vault_account, OwnershipChange::Transfer);
+
+            Vaults::<T, I>::insert(asset_id, Vault { class_id, kitty_id, reserve_price, proceeds: None });
+            KittyShares::<T, I>::insert(class_id, kitty_id, asset_id);
+
+            Self::deposit_event(Event::KittyFractionalised(sender, class_id, kitty_id, asset_id, shares, reserve_price));
+
+            Ok(())
+        }
+
+        /// Burn all shares of a kitty and take it out of its vault
+        #[pallet::weight(T::WeightInfo::redeem())]
+        #[transactional]
+        pub fn redeem(origin: OriginFor<T>, asset_id: AssetIdOf<T, I>) -> DispatchResult {
+            let sender = ensure_signed(origin)?;
+
+            let vault = Self::vault(asset_id).ok_or(Error::<T, I>::NoVault)?;
+            ensure!(vault.proceeds.is_none(), Error::<T, I>::BoughtOut);
+
+            let shares = T::Shares::total_issuance(asset_id);
+            ensure!(T::Shares::balance(asset_id, &sender) == shares, Error::<T, I>::NotAllShares);
+            T::Shares::burn(asset_id, &sender, shares)?;
+            T::Shares::destroy(asset_id)?;
+
+            Vaults::<T, I>::remove(asset_id);
+            KittyShares::<T, I>::remove(vault.class_id, vault.kitty_id);
+            let vault_account = Self::vault_account(asset_id);
+            Self::transfer_kitty(&vault_account, &sender, vault.class_id, vault.kitty_id)?;
+            Self::record_owner_change(vault.class_id, vault.kitty_id, Some(vault_account), sender.clone(), OwnershipChange::Transfer);
+
+            Self::deposit_event(Event::KittyRedeemed(sender, vault.class_id, vault.kitty_id));
+
+            Ok(())
+        }
+
+        /// Buy a kitty out of its vault for the reserve price
+        /// The price is kept by the vault, and the holders of the shares claim their part of it
+        /// with `claim_proceeds`
+        #[pallet::weight(T::WeightInfo::buyout())]
+        #[transactional]
+        pub fn buyout(origin: OriginFor<T>, asset_id: AssetIdOf<T, I>) -> DispatchResult {
+            let sender = ensure_signed(origin)?;
+
+            Self::ensure_not_blocked(&sender)?;
+
+            let vault_account = Self::vault_account(asset_id);
+            let (class_id, kitty_id, price) = Vaults::<T, I>::try_mutate(asset_id, |maybe_vault| -> Result<_, DispatchError> {
+                let vault = maybe_vault.as_mut().ok_or(Error::<T, I>::NoVault)?;
+                ensure!(vault.proceeds.is_none(), Error::<T, I>::BoughtOut);
+
+                // the proceeds are tracked by the vault rather than held by an account, so no claim
+                // is ever lost to the existential deposit
+                T::Currency::withdraw(&sender, vault.reserve_price, WithdrawReasons::TRANSFER, ExistenceRequirement::KeepAlive)?;
+                vault.proceeds = Some(vault.reserve_price);
+
+                Ok((vault.class_id, vault.kitty_id, vault.reserve_price))
+            })?;
+
+            KittyShares::<T, I>::remove(class_id, kitty_id);
+            Self::transfer_kitty(&vault_account, &sender, class_id, kitty_id)?;
+            Self::record_owner_change(class_id, kitty_id, Some(vault_account), sender.clone(), OwnershipChange::Sale(price));
+
+            Self::deposit_event(Event::KittyBoughtOut(sender, class_id, kitty_id, price));
+
+            Ok(())
+        }
+
+        /// Burn all shares of a kitty that was bought out, for their part of the reserve price
+        #[pallet::weight(T::WeightInfo::claim_proceeds())]
+        #[transactional]
+        pub fn claim_proceeds(origin: OriginFor<T>, asset_id: AssetIdOf<T, I>) -> DispatchResult {
+            let sender = ensure_signed(origin)?;
+
+            let shares = T::Shares::balance(asset_id, &sender);
+            ensure!(!shares.is_zero(), Error::<T, I>::NoShares);
+            let total_shares = T::Shares::total_issuance(asset_id);
+
+            let (amount, last) = Vaults::<T, I>::try_mutate_exists(asset_id, |maybe_vault| -> Result<_, DispatchError> {
+                let vault = maybe_vault.as_mut().ok_or(Error::<T, I>::NoVault)?;
+                let proceeds = vault.proceeds.ok_or(Error::<T, I>::NotBoughtOut)?;
+
+                // the proceeds are split over the shares that are left, so the last holder gets
+                // what is left after rounding
+                let amount = proceeds.checked_mul(&shares).ok_or(ArithmeticError::Overflow)? / total_shares;
+                let last = shares == total_shares;
+                if last {
+                    *maybe_vault = None;
+                } else {
+                    vault.proceeds = Some(proceeds - amount);
+                }
+
+                Ok((amount, last))
+            })?;
+
+            T::Shares::burn(asset_id, &sender, shares)?;
+            if last {
+                T::Shares::destroy(asset_id)?;
+            }
+            // an account without funds can not be created with less than the existential deposit,
+            // so the claim fails instead of losing the proceeds
+            let credit = T::Currency::deposit_creating(&sender, amount);
+            ensure!(credit.peek() == amount, Error::<T, I>::ProceedsBelowExistentialDeposit);
+
+            Self::deposit_event(Event::ProceedsClaimed(sender, asset_id, shares, amount));
+
+            Ok(())
+        }
+
+        /// Give a kitty a name
+        /// An empty name clears the name and refunds its deposit
+        #[pallet::weight(T::WeightInfo::set_name())]
+        pub fn set_name(origin: OriginFor<T>, class_id: ClassIdOf<T, I>, kitty_id: KittyIndexOf<T, I>, name: Vec<u8>) -> DispatchResult {
+            let sender = ensure_signed(origin)?;
+
+            ensure!(Self::is_owner(&sender, class_id, kitty_id), Error::<T, I>::NotOwner);
+            Self::ensure_valid_name(&name)?;
+
+            // the deposit covers both the name and the metadata
+            let metadata_len = KittyMetadata::<T, I>::decode_len(class_id, kitty_id).unwrap_or(0);
+            Self::update_deposit(&sender, class_id, kitty_id, name.len() + metadata_len)?;
+
+            if name.is_empty() {
+                KittyNames::<T, I>::remove(class_id, kitty_id);
+            } else {
+                KittyNames::<T, I>::insert(class_id, kitty_id, &name);
+            }
+
+            Self::deposit_event(Event::KittyNameUpdated(sender, class_id, kitty_id, name));
+
+            Ok(())
+        }
+
+        /// Set the metadata of a kitty
+        /// Empty metadata clears the metadata and refunds its deposit
+        #[pallet::weight(T::WeightInfo::set_metadata())]
+        pub fn set_metadata(origin: OriginFor<T>, class_id: ClassIdOf<T, I>, kitty_id: KittyIndexOf<T, I>, metadata: Vec<u8>) -> DispatchResult {
+            let sender = ensure_signed(origin)?;
+
+            ensure!(Self::is_owner(&sender, class_id, kitty_id), Error::<T, I>::NotOwner);
+            ensure!(metadata.len() as u32 <= Self::parameters().max_metadata_length, Error::<T, I>::MetadataTooLong);
+
+            let name_len = KittyNames::<T, I>::decode_len(class_id, kitty_id).unwrap_or(0);
+            Self::update_deposit(&sender, class_id, kitty_id, name_len + metadata.len())?;
+
+            if metadata.is_empty() {
+                KittyMetadata::<T, I>::remove(class_id, kitty_id);
+            } else {
+                KittyMetadata::<T, I>::insert(class_id, kitty_id, metadata);
+            }
+
+            Self::deposit_event(Event::KittyMetadataUpdated(sender, class_id, kitty_id));
+
+            Ok(())
+        }
+
+        /// Burn a kitty
+        /// The deposit for its name and metadata is refunded
+        #[pallet::weight(T::WeightInfo::burn())]
+        pub fn burn(origin: OriginFor<T>, class_id: ClassIdOf<T, I>, kitty_id: KittyIndexOf<T, I>) -> DispatchResult {
+            let sender = ensure_signed(origin)?;
+
+            // the NFT backend ensures the sender is the owner of the kitty
+            Self::ensure_collection(class_id)?;
+            Self::ensure_unlocked(class_id, kitty_id)?;
+            T::Nft::burn(&sender, class_id, kitty_id)?;
+
+            // remove everything that is stored about the kitty
+            KittiesByOwner::<T, I>::remove((sender.clone(), class_id), OrderedKittyIndex(kitty_id));
+            Self::on_owner_changed(class_id, kitty_id);
+            LastBreedBlock::<T, I>::remove(class_id, kitty_id);
+            KittyNames::<T, I>::remove(class_id, kitty_id);
+            KittyMetadata::<T, I>::remove(class_id, kitty_id);
+            OwnershipHistory::<T, I>::remove(class_id, kitty_id);
+            if let Some((depositor, deposit)) = KittyDeposits::<T, I>::take(class_id, kitty_id) {
+                T::Currency::unreserve(&depositor, deposit);
+            }
+
+            Self::deposit_event(Event::KittyBurned(sender, class_id, kitty_id));
+
+            Ok(())
+        }
+
+        /// Allow (or forbid) the offchain worker to auto breed a kitty
+        #[pallet::weight(T::WeightInfo::set_auto_breed_consent())]
+        pub fn set_auto_breed_consent(origin: OriginFor<T>, class_id: ClassIdOf<T, I>, kitty_id: KittyIndexOf<T, I>, consent: bool) -> DispatchResult {
+            let sender = ensure_signed(origin)?;
+
+            // only the owner can decide whether their kitty is bred automatically
+            ensure!(Self::is_owner(&sender, class_id, kitty_id), Error::<T, I>::NotOwner);
+
+            if consent {
+                Self::add_auto_breed_candidate(class_id, kitty_id);
+            } else {
+                Self::remove_auto_breed_candidate(class_id, kitty_id);
+            }
+
+            Self::deposit_event(Event::AutoBreedConsentUpdated(sender, class_id, kitty_id, consent));
+
+            Ok(())
+        }
+
+        // auto breed feature that is used by the offchain worker
+        // (pre_dispatch() repeats the checks of validate_unsigned() when the transaction is
+        // included in a block, so its weight is added as well)
+        #[pallet::weight(T::WeightInfo::auto_breed().saturating_add(T::WeightInfo::validate_unsigned()))]
+        pub fn auto_breed(origin: OriginFor<T>, class_id: ClassIdOf<T, I>, kitty_id_1: KittyIndexOf<T, I>, kitty_id_2: KittyIndexOf<T, I>, nonce: u32, _solution: u128) -> DispatchResult {
+            // ensure this is an unsigned transaction because the offchain worker is designed for a
+            // PoW approach, so anyone can become a miner
+            // anyone with a valid solution nonce will be able to participate; they don't need an
+            // account (or tokens to pay for a transaction)
+            ensure_none(origin)?;
+
+            // the nonce should match the auto_breed_nonce, otherwise it is a replay attack
+            ensure!(nonce == Self::auto_breed_nonce(), Error::<T, I>::InvalidNonce);
+
+            // ensure the kitty ids are valid and get the kitties
+            let owner = Self::owner_of(class_id, kitty_id_1).ok_or(Error::<T, I>::InvalidKittyId)?;
+            let kitty1 = Self::kitty(class_id, kitty_id_1).ok_or(Error::<T, I>::InvalidKittyId)?;
+            let kitty2 = Self::kitty(class_id, kitty_id_2).ok_or(Error::<T, I>::InvalidKittyId)?;
+
+            // the new kitten is minted to the owner of the first kitty, so both owners
+            // must have opted in to auto breeding
+            ensure!(AutoBreedConsent::<T, I>::contains_key(class_id, kitty_id_1), Error::<T, I>::NoAutoBreedConsent);
+            ensure!(AutoBreedConsent::<T, I>::contains_key(class_id, kitty_id_2), Error::<T, I>::NoAutoBreedConsent);
+
+            Self::do_breed(owner, class_id, (kitty_id_1, kitty1), (kitty_id_2, kitty2))?;
+
+            // consume the nonce to render the current solution no longer valid
+            // (this is done here and not in validate_unsigned(), because the validation also
+            // runs in the transaction pool, where it must not change the state)
+            AutoBreedNonce::<T, I>::mutate(|nonce| *nonce = nonce.saturating_add(1));
+
+            Ok(())
+        }
+
+        /// Create a new collection of kitties
+        #[pallet::weight(T::WeightInfo::create_collection())]
+        pub fn create_collection(origin: OriginFor<T>, name: Vec<u8>, max_supply: Option<u32>, breeding_enabled: bool) -> DispatchResult {
+            T::CollectionOrigin::ensure_origin(origin)?;
+
+            Self::ensure_valid_name(&name)?;
+
+            let data = KittyClassData { name, max_supply, breeding_enabled };
+            // the collections are not owned by an account, they are managed by the CollectionOrigin
+            let class_id = T::Nft::create_class(&Default::default(), data.clone())?;
+            Collections::<T, I>::insert(class_id, ());
+
+            Self::deposit_event(Event::CollectionCreated(class_id, data));
+
+            Ok(())
+        }
+
+        /// Block an account, for example one that sells stolen kitties
+        /// Blocking an account that is already blocked replaces its reason and expiry
+        #[pallet::weight(T::WeightInfo::block_account())]
+        pub fn block_account(origin: OriginFor<T>, who: T::AccountId, reason: Vec<u8>, expiry: Option<T::BlockNumber>) -> DispatchResult {
+            T::AdminOrigin::ensure_origin(origin)?;
+
+            ensure!(reason.len() as u32 <= Self::parameters().max_reason_length, Error::<T, I>::ReasonTooLong);
+            // the expired blocks are removed at the start of a block, so an expiry in the past
+            // would never be removed
+            if let Some(expiry) = expiry {
+                ensure!(expiry > frame_system::Pallet::<T>::block_number(), Error::<T, I>::InvalidExpiry);
+            }
+
+            if let Some(old_entry) = BlockedAccounts::<T, I>::get(&who) {
+                Self::remove_block_expiry(&who, old_entry.expiry);
+            }
+            if let Some(expiry) = expiry {
+                BlockExpiries::<T, I>::append(expiry, &who);
+            }
+            BlockedAccounts::<T, I>::insert(&who, BlockEntry { reason: reason.clone(), expiry });
+
+            Self::deposit_event(Event::AccountBlocked(who, reason, expiry));
+
+            Ok(())
+        }
+
+        /// Unblock an account before its block expires
+        #[pallet::weight(T::WeightInfo::unblock_account())]
+        pub fn unblock_account(origin: OriginFor<T>, who: T::AccountId) -> DispatchResult {
+            T::AdminOrigin::ensure_origin(origin)?;
+
+            let entry = BlockedAccounts::<T, I>::take(&who).ok_or(Error::<T, I>::NotBlocked)?;
+            Self::remove_block_expiry(&who, entry.expiry);
+
+            Self::deposit_event(Event::AccountUnblocked(who));
+
+            Ok(())
+        }
+
+        /// Change the parameters of the game
+        #[pallet::weight(T::WeightInfo::set_parameters())]
+        pub fn set_parameters(origin: OriginFor<T>, parameters: KittyParametersOf<T, I>) -> DispatchResult {
+            T::AdminOrigin::ensure_origin(origin)?;
+
+            ensure!(parameters.is_valid(), Error::<T, I>::InvalidParameters);
+
+            let changes = Self::parameters().diff(&parameters);
+            Parameters::<T, I>::put(parameters);
+
+            Self::deposit_event(Event::ParametersUpdated(changes));
+
+            Ok(())
+        }
+
+        /// Stop accepting a kind of calls, for example to stop the sales during an incident
+        /// The runtime has to reject the paused calls with `is_call_paused` in its
+        /// `BaseCallFilter`
+        #[pallet::weight(T::WeightInfo::pause())]
+        pub fn pause(origin: OriginFor<T>, call_kind: CallKind) -> DispatchResult {
+            T::AdminOrigin::ensure_origin(origin)?;
+
+            ensure!(!PausedCalls::<T, I>::contains_key(call_kind), Error::<T, I>::AlreadyPaused);
+            PausedCalls::<T, I>::insert(call_kind, ());
+
+            Self::deposit_event(Event::CallPaused(call_kind));
+
+            Ok(())
+        }
+
+        /// Accept a kind of calls again
+        #[pallet::weight(T::WeightInfo::unpause())]
+        pub fn unpause(origin: OriginFor<T>, call_kind: CallKind) -> DispatchResult {
+            T::AdminOrigin::ensure_origin(origin)?;
+
+            ensure!(PausedCalls::<T, I>::contains_key(call_kind), Error::<T, I>::NotPaused);
+            PausedCalls::<T, I>::remove(call_kind);
+
+            Self::deposit_event(Event::CallUnpaused(call_kind));
+
+            Ok(())
+        }
+
+        /// Allow an account to transfer a kitty on behalf of its owner
+        /// None clears the approval
+        /// The approval is cleared when the kitty changes its owner
+        #[pallet::weight(T::WeightInfo::approve())]
+        pub fn approve(origin: OriginFor<T>, class_id: ClassIdOf<T, I>, kitty_id: KittyIndexOf<T, I>, approved: Option<T::AccountId>) -> DispatchResult {
+            let sender = ensure_signed(origin)?;
+
+            let owner = Self::owner_of(class_id, kitty_id).ok_or(Error::<T, I>::InvalidKittyId)?;
+            // the operators of the owner can approve accounts as well
+            ensure!(sender == owner || Self::is_approved_for_all(&owner, &sender), Error::<T, I>::NotOwner);
+            ensure!(approved.as_ref() != Some(&owner), Error::<T, I>::ApproveToSelf);
+
+            KittyApprovals::<T, I>::mutate_exists(class_id, kitty_id, |account| *account = approved.clone());
+
+            Self::deposit_event(Event::Approval(owner, class_id, kitty_id, approved));
+
+            Ok(())
+        }
+
+        /// Allow (or forbid) an operator to transfer all kitties of the sender
+        #[pallet::weight(T::WeightInfo::set_approval_for_all())]
+        pub fn set_approval_for_all(origin: OriginFor<T>, operator: T::AccountId, approved: bool) -> DispatchResult {
+            let sender = ensure_signed(origin)?;
+
+            ensure!(sender != operator, Error::<T, I>::ApproveToSelf);
+
+            if approved {
+                OperatorApprovals::<T, I>::insert(&sender, &operator, ());
+            } else {
+                OperatorApprovals::<T, I>::remove(&sender, &operator);
+            }
+
+            Self::deposit_event(Event::ApprovalForAll(sender, operator, approved));
+
+            Ok(())
+        }
+
+        /// Transfer a kitty on behalf of its owner
+        /// The sender has to be approved for the kitty, or be an operator of the owner
+        #[pallet::weight(T::WeightInfo::transfer_from())]
+        pub fn transfer_from(origin: OriginFor<T>, owner: T::AccountId, to: T::AccountId, class_id: ClassIdOf<T, I>, kitty_id: KittyIndexOf<T, I>) -> DispatchResult {
+            let sender = ensure_signed(origin)?;
+
+            ensure!(
+                sender == owner
+                    || Self::approved(class_id, kitty_id).as_ref() == Some(&sender)
+                    || Self::is_approved_for_all(&owner, &sender),
+                Error::<T, I>::NotApproved
+            );
+
+            // the NFT backend ensures `owner` is the owner of the kitty
+            Self::do_transfer(owner, to, class_id, kitty_id)
+        }
+	}
+
+    // need to implement this to be able to use unsigned transactions
+    // otherwise people could fill the blocks with unsigned transactions
+    //  (even if the _nonce and _solution are validated inside the auto_breed() function,
+    //  the transaction will be included in the block, as it would be already too late to reject
+    //  the transaction)
+    #[pallet::validate_unsigned]
+    impl<T: Config<I>, I: 'static> frame_support::unsigned::ValidateUnsigned for Pallet<T, I> {
+        type Call = Call<T, I>;
+
+        // validate_unsigned() wil be executed before a transaction is accepted in a transaction pool
         fn validate_unsigned(_source: TransactionSource, call: &Self::Call) -> TransactionValidity {
             match *call {
                 // the only unsigned transaction is for auto_breed(), so we only care about it
-                Call::auto_breed(kitty_id_1, kitty_id_2, nonce, solution) => {
-                    // validate the solution to verify the work performed by the worker
-                    if Self::validate_solution(kitty_id_1, kitty_id_2, nonce, solution) {
-                        // if the solution is valid, the nonce should also match the auto_breed_nonce
-                        // otherwise it is a replay attack
-                        if nonce != Self::auto_breed_nonce() {
-                            return InvalidTransaction::BadProof.into();
-                        }
-
-                        // if the nonce is valid, increase it with 1, to render the current
-                        // solution no longer valid
-                        AutoBreedNonce::<T>::mutate(|nonce| *nonce = nonce.saturating_add(1));
-
-                        // return a valid transaction
-                        ValidTransaction::with_tag_prefix("kitties")    // there could be different cathegories of transactions
-                            .longevity(64_u64)  // how many blocks the transaction is valid for;
-                                                // if after 64 blocks the transaction is still not
-                                                // confirmed, it will be discarded
-                            .propagate(true)    // since anyone can become a miner this transaction has to be sent to other nodes as well
-                                                // eventually reaching a validator or creator node
-                            .build()
+                Call::auto_breed(class_id, kitty_id_1, kitty_id_2, nonce, solution) => {
+                    // reject the transaction if it would fail when dispatched, so it does not
+                    // waste block space
+                    if let Err(error) = Self::check_auto_breed(class_id, kitty_id_1, kitty_id_2, nonce, solution) {
+                        return error.into();
+                    }
+
+                    let current_nonce = Self::auto_breed_nonce();
+
+                    // return a valid transaction
+                    // (the tags are prefixed with the name of the pallet instance, because every
+                    // instance has its own nonces)
+                    let valid_tx = ValidTransaction::with_tag_prefix(<Self as PalletInfoAccess>::name())
+                        .priority(Self::auto_breed_priority())
+                        .and_provides(nonce)    // only one solution per nonce can be in the pool
+                        .longevity(64_u64)  // how many blocks the transaction is valid for;
+                                            // if after 64 blocks the transaction is still not
+                                            // confirmed, it will be discarded
+                        .propagate(true);   // since anyone can become a miner this transaction has to be sent to other nodes as well
+                                            // eventually reaching a validator or creator node
+
+                    // a solution for a future nonce has to wait for the previous nonce to be consumed
+                    if nonce > current_nonce {
+                        valid_tx.and_requires(nonce - 1).build()
                     } else {
-                        InvalidTransaction::BadProof.into()
+                        valid_tx.build()
                     }
                 },
                 // in the default case, this would be an invalid unsigned transaction
@@ -327,6 +2214,24 @@ pub mod pallet {
                 _ => InvalidTransaction::Call.into(),
             }
         }
+
+        // pre_dispatch() is executed right before the transaction is included in a block, so
+        // solutions that became stale while waiting in the pool are dropped
+        fn pre_dispatch(call: &Self::Call) -> Result<(), TransactionValidityError> {
+            match *call {
+                Call::auto_breed(class_id, kitty_id_1, kitty_id_2, nonce, solution) => {
+                    Self::check_auto_breed(class_id, kitty_id_1, kitty_id_2, nonce, solution)?;
+
+                    // only the solution for the current nonce can be included
+                    if nonce != Self::auto_breed_nonce() {
+                        return Err(InvalidTransaction::Future.into());
+                    }
+
+                    Ok(())
+                },
+                _ => Err(InvalidTransaction::Call.into()),
+            }
+        }
     }
 }
 
@@ -342,18 +2247,250 @@ fn combine_dna(dna1: u8, dna2: u8, selector: u8) -> u8 {
     (!selector & dna1) | (selector & dna2)
 }
 
-impl<T: Config> Pallet<T> {
-    fn kitties(owner: &T::AccountId, kitty_id: KittyIndexOf<T>) -> Option<Kitty> {
-        // get the tokens for the class_id and the kitty_id
-        orml_nft::Pallet::<T>::tokens(Self::class_id(), kitty_id).and_then(|x| {
-            // check the owner
-            if x.owner == *owner {
-                // if `owner` is the owner of the kitty, return the data
-                Some(x.data)
-            } else {
-                None
-            }
-        })
+// functions used by the runtime
+impl<T: Config<I>, I: 'static> Pallet<T, I> {
+    /// Get the kind of a call. None means the call can not be paused.
+    pub fn call_kind(call: &Call<T, I>) -> Option<CallKind> {
+        match call {
+            Call::create(..) => Some(CallKind::Create),
+            Call::breed(..) => Some(CallKind::Breed),
+            // buy orders and floor bids can always be cancelled, and instalments can always be
+            // paid, so buyers don't lose their funds while trading is paused
+            Call::buy(..) | Call::place_buy_order(..) | Call::fill_order(..) |
+                Call::place_floor_bid(..) | Call::sell_into_bid(..) | Call::start_layaway(..) |
+                Call::buyout(..) => Some(CallKind::Buy),
+            Call::set_price(..) => Some(CallKind::SetPrice),
+            Call::auto_breed(..) => Some(CallKind::AutoBreed),
+            _ => None,
+        }
+    }
+
+    /// Whether a call is paused, for `CheckKittiesPaused` and the `BaseCallFilter` of the runtime
+    pub fn is_call_paused(call: &Call<T, I>) -> bool {
+        Self::call_kind(call).map_or(false, Self::is_paused)
+    }
+
+    /// Whether a kind of calls is paused
+    pub fn is_paused(call_kind: CallKind) -> bool {
+        PausedCalls::<T, I>::contains_key(call_kind)
+    }
+}
+
+/// Rejects the signed transactions of the calls that are paused, while they are validated.
+/// It has to come before `ChargeTransactionPayment` in the `SignedExtra` of the runtime, so a
+/// paused call does not pay a fee. The `BaseCallFilter` only runs at dispatch, after the fee is
+/// charged.
+#[derive(Encode, Decode, Clone, Eq, PartialEq)]
+pub struct CheckKittiesPaused<T: Config<I> + Send + Sync, I: 'static + Send + Sync = ()>(PhantomData<(T, I)>);
+
+impl<T: Config<I> + Send + Sync, I: 'static + Send + Sync> CheckKittiesPaused<T, I> {
+    pub fn new() -> Self {
+        Self(PhantomData)
+    }
+}
+
+impl<T: Config<I> + Send + Sync, I: 'static + Send + Sync> sp_std::fmt::Debug for CheckKittiesPaused<T, I> {
+    #[cfg(feature = "std")]
+    fn fmt(&self, f: &mut sp_std::fmt::Formatter) -> sp_std::fmt::Result {
+        write!(f, "CheckKittiesPaused")
+    }
+
+    #[cfg(not(feature = "std"))]
+    fn fmt(&self, _: &mut sp_std::fmt::Formatter) -> sp_std::fmt::Result {
+        Ok(())
+    }
+}
+
+impl<T: Config<I> + Send + Sync, I: 'static + Send + Sync> SignedExtension for CheckKittiesPaused<T, I>
+where
+    <T as frame_system::Config>::Call: IsSubType<Call<T, I>>,
+{
+    const IDENTIFIER: &'static str = "CheckKittiesPaused";
+    type AccountId = T::AccountId;
+    type Call = <T as frame_system::Config>::Call;
+    type AdditionalSigned = ();
+    type Pre = ();
+
+    fn additional_signed(&self) -> Result<(), TransactionValidityError> {
+        Ok(())
+    }
+
+    fn validate(&self, _who: &Self::AccountId, call: &Self::Call, _info: &DispatchInfoOf<Self::Call>, _len: usize) -> TransactionValidity {
+        match call.is_sub_type() {
+            Some(call) if Pallet::<T, I>::is_call_paused(call) => InvalidTransaction::Call.into(),
+            _ => Ok(ValidTransaction::default()),
+        }
+    }
+
+    fn pre_dispatch(self, who: &Self::AccountId, call: &Self::Call, info: &DispatchInfoOf<Self::Call>, len: usize) -> Result<Self::Pre, TransactionValidityError> {
+        self.validate(who, call, info, len).map(|_| ())
+    }
+}
+
+// functions used by the runtime API
+impl<T: Config<I>, I: 'static> Pallet<T, I> {
+    /// Get the settings of a collection
+    pub fn collection(class_id: ClassIdOf<T, I>) -> Option<KittyClassData> {
+        if !Collections::<T, I>::contains_key(class_id) {
+            return None;
+        }
+
+        T::Nft::class_data(class_id)
+    }
+
+    /// Get a kitty by its collection and id
+    pub fn kitty(class_id: ClassIdOf<T, I>, kitty_id: KittyIndexOf<T, I>) -> Option<Kitty> {
+        if !Collections::<T, I>::contains_key(class_id) {
+            return None;
+        }
+
+        T::Nft::token_data(class_id, kitty_id)
+    }
+
+    /// Get the owner of a kitty
+    pub fn owner_of(class_id: ClassIdOf<T, I>, kitty_id: KittyIndexOf<T, I>) -> Option<T::AccountId> {
+        if !Collections::<T, I>::contains_key(class_id) {
+            return None;
+        }
+
+        T::Nft::owner(class_id, kitty_id)
+    }
+
+    /// Get a page of the kitties of `owner` in a collection, ordered by their ids, starting
+    /// after the kitty with id `after`.
+    ///
+    /// New kitties always get a higher id than the existing ones, so the pages stay stable
+    /// while new kitties are minted.
+    pub fn kitties_of(owner: &T::AccountId, class_id: ClassIdOf<T, I>, after: Option<KittyIndexOf<T, I>>, limit: u32) -> KittiesPage<KittyIndexOf<T, I>> {
+        let limit = limit.max(1).min(MAX_PAGE_SIZE) as usize;
+
+        if !Collections::<T, I>::contains_key(class_id) {
+            return KittiesPage { kitties: Vec::new(), next: None };
+        }
+
+        // the page starts right after the key of the cursor, whether the owner still has that
+        // kitty or not
+        let prefix = (owner.clone(), class_id);
+        let keys = match after {
+            Some(after) => {
+                let cursor = KittiesByOwner::<T, I>::hashed_key_for(&prefix, OrderedKittyIndex(after));
+                KittiesByOwner::<T, I>::iter_prefix_from(&prefix, cursor)
+            },
+            None => KittiesByOwner::<T, I>::iter_prefix(&prefix),
+        };
+
+        // one more kitty than fits in the page is read to know whether there is a next page
+        let mut kitty_ids: Vec<KittyIndexOf<T, I>> = keys.map(|(kitty_id, _)| kitty_id.0).take(limit + 1).collect();
+
+        // if there are more kitties than fit in this page, the last kitty of the page is the
+        // cursor of the next page
+        let next = if kitty_ids.len() > limit {
+            kitty_ids.truncate(limit);
+            kitty_ids.last().copied()
+        } else {
+            None
+        };
+
+        let kitties = kitty_ids.into_iter()
+            .take(limit)
+            .filter_map(|kitty_id| Self::kitty(class_id, kitty_id).map(|kitty| (kitty_id, kitty)))
+            .collect();
+
+        KittiesPage { kitties, next }
+    }
+
+    /// Whether `operator` may transfer all kitties of `owner`
+    pub fn is_approved_for_all(owner: &T::AccountId, operator: &T::AccountId) -> bool {
+        OperatorApprovals::<T, I>::contains_key(owner, operator)
+    }
+
+    /// Get a page of the kitties of a collection that are for sale, cheapest first
+    pub fn listings(class_id: ClassIdOf<T, I>, start: u32, limit: u32) -> Vec<(KittyIndexOf<T, I>, BalanceOf<T, I>)> {
+        ListingsByPrice::<T, I>::iter_prefix(class_id)
+            .skip(start as usize)
+            .take(limit.min(MAX_PAGE_SIZE) as usize)
+            .map(|(key, _)| (key.kitty_id, key.price))
+            .collect()
+    }
+
+    /// Get the lowest price of all kitties of a collection that are for sale
+    pub fn floor_price(class_id: ClassIdOf<T, I>) -> Option<BalanceOf<T, I>> {
+        ListingsByPrice::<T, I>::iter_prefix(class_id).next().map(|(key, _)| key.price)
+    }
+
+    /// The account of a vault, that holds its kitty until it is redeemed or bought out
+    pub fn vault_account(asset_id: AssetIdOf<T, I>) -> T::AccountId {
+        T::PalletId::get().into_sub_account(asset_id)
+    }
+
+    /// The account that escrows the kitty of a loan. The id is tagged, so that it never derives
+    /// the same account as the id of a vault.
+    pub fn loan_account(loan_id: LoanId) -> T::AccountId {
+        T::PalletId::get().into_sub_account((b"loan", loan_id))
+    }
+}
+
+impl<T: Config<I>, I: 'static> KittyLockManager<ClassIdOf<T, I>, KittyIndexOf<T, I>> for Pallet<T, I> {
+    fn lock(class_id: ClassIdOf<T, I>, kitty_id: KittyIndexOf<T, I>, reason: LockReason) -> DispatchResult {
+        let owner = Self::owner_of(class_id, kitty_id).ok_or(Error::<T, I>::InvalidKittyId)?;
+
+        KittyLocks::<T, I>::try_mutate(class_id, kitty_id, |lock| -> DispatchResult {
+            ensure!(lock.is_none(), Error::<T, I>::KittyLocked);
+            *lock = Some(reason);
+
+            Ok(())
+        })?;
+
+        // a locked kitty can not be bought, so it is no longer for sale
+        if let Some(price) = KittyPrices::<T, I>::take(class_id, kitty_id) {
+            Self::remove_listing(class_id, kitty_id, price);
+            Self::deposit_event(Event::KittyPriceUpdated(owner, class_id, kitty_id, None));
+        }
+
+        Self::deposit_event(Event::KittyLocked(class_id, kitty_id, reason));
+
+        Ok(())
+    }
+
+    fn unlock(class_id: ClassIdOf<T, I>, kitty_id: KittyIndexOf<T, I>, reason: LockReason) -> DispatchResult {
+        // only the lock of the same reason can be removed, so the pallets can not remove the
+        // locks of each other
+        KittyLocks::<T, I>::try_mutate_exists(class_id, kitty_id, |lock| -> DispatchResult {
+            ensure!(*lock == Some(reason), Error::<T, I>::NotLocked);
+            *lock = None;
+
+            Ok(())
+        })?;
+
+        Self::deposit_event(Event::KittyUnlocked(class_id, kitty_id, reason));
+
+        Ok(())
+    }
+
+    fn lock_reason(class_id: ClassIdOf<T, I>, kitty_id: KittyIndexOf<T, I>) -> Option<LockReason> {
+        Self::kitty_lock(class_id, kitty_id)
+    }
+}
+
+impl<T: Config<I>, I: 'static> Pallet<T, I> {
+    fn is_owner(who: &T::AccountId, class_id: ClassIdOf<T, I>, kitty_id: KittyIndexOf<T, I>) -> bool {
+        Self::owner_of(class_id, kitty_id).as_ref() == Some(who)
+    }
+
+    fn ensure_collection(class_id: ClassIdOf<T, I>) -> DispatchResult {
+        ensure!(Collections::<T, I>::contains_key(class_id), Error::<T, I>::InvalidCollection);
+
+        Ok(())
+    }
+
+    fn kitties(owner: &T::AccountId, class_id: ClassIdOf<T, I>, kitty_id: KittyIndexOf<T, I>) -> Option<Kitty> {
+        // check the owner
+        if Self::is_owner(owner, class_id, kitty_id) {
+            // if `owner` is the owner of the kitty, return the data
+            Self::kitty(class_id, kitty_id)
+        } else {
+            None
+        }
     }
 
     fn random_value(sender: &T::AccountId) -> [u8; 16] {
@@ -371,11 +2508,39 @@ impl<T: Config> Pallet<T> {
         payload.using_encoded(blake2_128)
     }
 
-    fn do_breed(owner: T::AccountId, kitty1: Kitty, kitty2: Kitty) -> DispatchResult {
-        ensure!(kitty1.gender() != kitty2.gender(), Error::<T>::SameGender);
+    // mint a kitty into a collection, unless the collection is full
+    fn mint(owner: &T::AccountId, class_id: ClassIdOf<T, I>, kitty: Kitty) -> Result<KittyIndexOf<T, I>, DispatchError> {
+        let collection = Self::collection(class_id).ok_or(Error::<T, I>::InvalidCollection)?;
+
+        if let Some(max_supply) = collection.max_supply {
+            ensure!(T::Nft::total_issuance(class_id) < max_supply.into(), Error::<T, I>::MaxSupplyReached);
+        }
+
+        let kitty_id = T::Nft::mint(owner, class_id, kitty)?;
+        KittiesByOwner::<T, I>::insert((owner.clone(), class_id), OrderedKittyIndex(kitty_id), ());
+
+        Ok(kitty_id)
+    }
+
+    fn ensure_valid_name(name: &[u8]) -> DispatchResult {
+        ensure!(name.len() as u32 <= Self::parameters().max_name_length, Error::<T, I>::NameTooLong);
+        // names are shown to the users, so they have to be valid UTF-8
+        ensure!(sp_std::str::from_utf8(name).is_ok(), Error::<T, I>::InvalidName);
 
-        let kitty1_dna = kitty1.0;
-        let kitty2_dna = kitty2.0;
+        Ok(())
+    }
+
+    fn is_breeding_enabled(class_id: ClassIdOf<T, I>) -> bool {
+        Self::parameters().breeding_enabled && Self::collection(class_id).map_or(false, |collection| collection.breeding_enabled)
+    }
+
+    fn do_breed(owner: T::AccountId, class_id: ClassIdOf<T, I>, (kitty_id_1, kitty1): (KittyIndexOf<T, I>, Kitty), (kitty_id_2, kitty2): (KittyIndexOf<T, I>, Kitty)) -> DispatchResult {
+        ensure!(Self::is_breeding_enabled(class_id), Error::<T, I>::BreedingDisabled);
+        ensure!(kitty1.gender() != kitty2.gender(), Error::<T, I>::SameGender);
+        ensure!(Self::is_ready_to_breed(class_id, kitty_id_1) && Self::is_ready_to_breed(class_id, kitty_id_2), Error::<T, I>::KittyOnCooldown);
+
+        let kitty1_dna = kitty1.dna;
+        let kitty2_dna = kitty2.dna;
 
         // generate a random value for the dna
         // the selector will decide whether to pick the dna from parent 1 or 2
@@ -387,22 +2552,407 @@ impl<T: Config> Pallet<T> {
             new_dna[i] = combine_dna(kitty1_dna[i], kitty2_dna[i], selector[i]);
         }
 
-        // create the new kitty
-        let new_kitty = Kitty(new_dna);
+        // create the new kitty, one generation after its older parent
+        let new_kitty = Kitty {
+            dna: new_dna,
+            generation: kitty1.generation.max(kitty2.generation).saturating_add(1),
+        };
+
+        // mint the new kitty to the storage (in the collection of its parents)
+        let kitty_id = Self::mint(&owner, class_id, new_kitty.clone())?;
+        Self::record_owner_change(class_id, kitty_id, None, owner.clone(), OwnershipChange::Breed);
 
-        // mint the new kitty to the storage
-        let kitty_id = orml_nft::Pallet::<T>::mint(&owner, Self::class_id(), Vec::new(), new_kitty.clone())?;
+        // the parents have to rest before they can breed again
+        let now = frame_system::Pallet::<T>::block_number();
+        LastBreedBlock::<T, I>::insert(class_id, kitty_id_1, now);
+        LastBreedBlock::<T, I>::insert(class_id, kitty_id_2, now);
 
         // deposit an event to indicate what happened on the blockchain
-        Self::deposit_event(Event::KittyBred(owner, kitty_id, new_kitty));
+        Self::deposit_event(Event::KittyBred(owner, class_id, kitty_id, new_kitty));
+
+        Ok(())
+    }
+
+    fn auto_breed_priority() -> TransactionPriority {
+        UNSIGNED_TXS_PRIORITY.saturating_add(Self::parameters().difficulty.into())
+    }
+
+    fn is_ready_to_breed(class_id: ClassIdOf<T, I>, kitty_id: KittyIndexOf<T, I>) -> bool {
+        Self::last_breed_block(class_id, kitty_id).map_or(true, |block| {
+            frame_system::Pallet::<T>::block_number() >= block.saturating_add(Self::parameters().breed_cooldown)
+        })
+    }
+
+    // check everything that auto_breed() and the transaction pool need to know about the
+    // solution and the kitties, without changing the state
+    fn check_auto_breed(class_id: ClassIdOf<T, I>, kitty_id_1: KittyIndexOf<T, I>, kitty_id_2: KittyIndexOf<T, I>, nonce: u32, solution: u128) -> Result<(), InvalidTransaction> {
+        // validate the solution to verify the work performed by the worker
+        if !Self::validate_solution(class_id, kitty_id_1, kitty_id_2, nonce, solution) {
+            return Err(InvalidTransaction::BadProof);
+        }
+
+        // a solution for an already consumed nonce can never be included
+        if nonce < Self::auto_breed_nonce() {
+            return Err(InvalidTransaction::Stale);
+        }
+
+        ensure!(Self::parameters().auto_breeding_enabled && Self::is_breeding_enabled(class_id), AutoBreedValidityError::BreedingDisabled);
+        // the transaction pool does not run the call filter of the runtime
+        ensure!(!Self::is_paused(CallKind::AutoBreed), AutoBreedValidityError::Paused);
+
+        // the kitties may have been burned or bred since the solution was found
+        let kitty1 = Self::kitty(class_id, kitty_id_1).ok_or(AutoBreedValidityError::InvalidKittyId)?;
+        let kitty2 = Self::kitty(class_id, kitty_id_2).ok_or(AutoBreedValidityError::InvalidKittyId)?;
+
+        ensure!(kitty1.gender() != kitty2.gender(), AutoBreedValidityError::SameGender);
+        ensure!(Self::is_ready_to_breed(class_id, kitty_id_1) && Self::is_ready_to_breed(class_id, kitty_id_2), AutoBreedValidityError::OnCooldown);
+        ensure!(AutoBreedConsent::<T, I>::contains_key(class_id, kitty_id_1) && AutoBreedConsent::<T, I>::contains_key(class_id, kitty_id_2), AutoBreedValidityError::NoConsent);
+
+        Ok(())
+    }
+
+    fn do_transfer(from: T::AccountId, to: T::AccountId, class_id: ClassIdOf<T, I>, kitty_id: KittyIndexOf<T, I>) -> DispatchResult {
+        Self::transfer_kitty(&from, &to, class_id, kitty_id)?;
+
+        // if the owner does not transfer to themselves, remove the kitty price and deposit
+        // the KittyTransferred event
+        if from != to {
+            Self::on_owner_changed(class_id, kitty_id);
+            Self::record_owner_change(class_id, kitty_id, Some(from.clone()), to.clone(), OwnershipChange::Transfer);
+
+            Self::deposit_event(Event::KittyTransferred(from, to, class_id, kitty_id));
+        }
+
+        Ok(())
+    }
+
+    // every change of the owner of a kitty has to go through here, so blocked accounts do not
+    // receive kitties
+    fn transfer_kitty(from: &T::AccountId, to: &T::AccountId, class_id: ClassIdOf<T, I>, kitty_id: KittyIndexOf<T, I>) -> DispatchResult {
+        Self::ensure_not_blocked(to)?;
+        Self::move_kitty(from, to, class_id, kitty_id)
+    }
+
+    // move a kitty without checking the blocklist, only `transfer_kitty` and the final
+    // instalment of a layaway use it. Locked kitties stay where they are.
+    fn move_kitty(from: &T::AccountId, to: &T::AccountId, class_id: ClassIdOf<T, I>, kitty_id: KittyIndexOf<T, I>) -> DispatchResult {
+        Self::ensure_collection(class_id)?;
+        Self::ensure_unlocked(class_id, kitty_id)?;
+
+        T::Nft::transfer(from, to, class_id, kitty_id)?;
+
+        KittiesByOwner::<T, I>::remove((from.clone(), class_id), OrderedKittyIndex(kitty_id));
+        KittiesByOwner::<T, I>::insert((to.clone(), class_id), OrderedKittyIndex(kitty_id), ());
+        Self::move_deposit(to, class_id, kitty_id);
+
+        Ok(())
+    }
+
+    // the deposit for the name and metadata of a kitty is paid by its owner, so it is refunded
+    // to the previous owner and reserved from the new one
+    fn move_deposit(to: &T::AccountId, class_id: ClassIdOf<T, I>, kitty_id: KittyIndexOf<T, I>) {
+        let (depositor, deposit) = match KittyDeposits::<T, I>::take(class_id, kitty_id) {
+            Some(deposit) => deposit,
+            None => return,
+        };
+
+        T::Currency::unreserve(&depositor, deposit);
+
+        // a transfer must not fail because of the name, so it is cleared if the new owner can
+        // not pay for it
+        if T::Currency::reserve(to, deposit).is_ok() {
+            KittyDeposits::<T, I>::insert(class_id, kitty_id, (to.clone(), deposit));
+        } else {
+            KittyNames::<T, I>::remove(class_id, kitty_id);
+            KittyMetadata::<T, I>::remove(class_id, kitty_id);
+        }
+    }
+
+    fn ensure_unlocked(class_id: ClassIdOf<T, I>, kitty_id: KittyIndexOf<T, I>) -> DispatchResult {
+        ensure!(!KittyLocks::<T, I>::contains_key(class_id, kitty_id), Error::<T, I>::KittyLocked);
+
+        Ok(())
+    }
+
+    // the assets pallet can also create asset classes on its own, so the ids that are taken
+    // already are skipped
+    fn next_asset_id() -> Result<AssetIdOf<T, I>, DispatchError> {
+        NextAssetId::<T, I>::try_mutate(|next_id| -> Result<AssetIdOf<T, I>, DispatchError> {
+            let mut asset_id = *next_id;
+            while T::Shares::exists(asset_id) {
+                asset_id = asset_id.checked_add(&One::one()).ok_or(ArithmeticError::Overflow)?;
+            }
+            *next_id = asset_id.checked_add(&One::one()).ok_or(ArithmeticError::Overflow)?;
+            Ok(asset_id)
+        })
+    }
+
+    fn next_order_id() -> Result<OrderId, DispatchError> {
+        NextOrderId::<T, I>::try_mutate(|next_id| -> Result<OrderId, DispatchError> {
+            let order_id = *next_id;
+            *next_id = next_id.checked_add(1).ok_or(ArithmeticError::Overflow)?;
+            Ok(order_id)
+        })
+    }
+
+    // remove a buy order after `check` accepted it
+    fn take_buy_order(class_id: ClassIdOf<T, I>, order_id: OrderId, check: impl FnOnce(&BuyOrderOf<T, I>) -> DispatchResult) -> Result<BuyOrderOf<T, I>, DispatchError> {
+        BuyOrders::<T, I>::try_mutate_exists(class_id, |maybe_orders| {
+            let orders = maybe_orders.as_mut().ok_or(Error::<T, I>::UnknownOrder)?;
+            let index = orders.iter().position(|(id, _)| *id == order_id).ok_or(Error::<T, I>::UnknownOrder)?;
+            check(&orders[index].1)?;
+
+            let (_, order) = orders.remove(index);
+            // collections without orders don't need an entry
+            if orders.is_empty() {
+                *maybe_orders = None;
+            }
+
+            Ok(order)
+        })
+    }
+
+    // remove the best buy order that pays at least `price` for a kitty
+    fn take_matching_buy_order(seller: &T::AccountId, class_id: ClassIdOf<T, I>, kitty_id: KittyIndexOf<T, I>, price: BalanceOf<T, I>) -> Option<(OrderId, BuyOrderOf<T, I>)> {
+        let kitty = Self::kitty(class_id, kitty_id)?;
+
+        // the orders are sorted by their price, so the search can stop at the first order
+        // that pays less
+        let order_id = Self::buy_orders(class_id).into_iter()
+            .take_while(|(_, order)| order.max_price >= price)
+            .find(|(_, order)| order.buyer != *seller && !BlockedAccounts::<T, I>::contains_key(&order.buyer) && order.matches(&kitty))
+            .map(|(order_id, _)| order_id)?;
+
+        Self::take_buy_order(class_id, order_id, |_| Ok(())).ok().map(|order| (order_id, order))
+    }
+
+    // sell a kitty into a buy order that was removed already, for a price of at most the
+    // highest price of the order
+    fn execute_buy_order(class_id: ClassIdOf<T, I>, order_id: OrderId, order: BuyOrderOf<T, I>, seller: T::AccountId, kitty_id: KittyIndexOf<T, I>, price: BalanceOf<T, I>) -> DispatchResult {
+        Self::sell_from_reserve(class_id, seller, order.buyer, kitty_id, price, order.max_price)?;
+        Self::deposit_event(Event::BuyOrderFilled(class_id, order_id, kitty_id));
+
+        Ok(())
+    }
+
+    // sell a kitty to a buyer that reserved `reserved` for it, the price is paid from the
+    // reserved funds and the rest is refunded
+    fn sell_from_reserve(class_id: ClassIdOf<T, I>, seller: T::AccountId, buyer: T::AccountId, kitty_id: KittyIndexOf<T, I>, price: BalanceOf<T, I>, reserved: BalanceOf<T, I>) -> DispatchResult {
+        Self::transfer_kitty(&seller, &buyer, class_id, kitty_id)?;
+        Self::settle_sale(class_id, seller, buyer, kitty_id, price, reserved)
+    }
+
+    // pay for a kitty that was moved to its buyer already from the funds the buyer reserved
+    fn settle_sale(class_id: ClassIdOf<T, I>, seller: T::AccountId, buyer: T::AccountId, kitty_id: KittyIndexOf<T, I>, price: BalanceOf<T, I>, reserved: BalanceOf<T, I>) -> DispatchResult {
+        Self::on_owner_changed(class_id, kitty_id);
+
+        T::Currency::unreserve(&buyer, reserved);
+        T::Currency::transfer(&buyer, &seller, price, ExistenceRequirement::AllowDeath)?;
+
+        Self::record_owner_change(class_id, kitty_id, Some(seller.clone()), buyer.clone(), OwnershipChange::Sale(price));
+        Self::deposit_event(Event::KittySold(seller, buyer, class_id, kitty_id, price));
+
+        Ok(())
+    }
+
+    fn next_loan_id() -> Result<LoanId, DispatchError> {
+        NextLoanId::<T, I>::try_mutate(|next_id| -> Result<LoanId, DispatchError> {
+            let loan_id = *next_id;
+            *next_id = next_id.checked_add(1).ok_or(ArithmeticError::Overflow)?;
+            Ok(loan_id)
+        })
+    }
+
+    // move the kitty of a loan that is removed out of its escrow
+    fn release_collateral(loan: &LoanOf<T, I>, to: &T::AccountId, class_id: ClassIdOf<T, I>, kitty_id: KittyIndexOf<T, I>) -> DispatchResult {
+        let escrow = Self::loan_account(loan.id);
+        Self::transfer_kitty(&escrow, to, class_id, kitty_id)?;
+        Self::record_owner_change(class_id, kitty_id, Some(escrow), to.clone(), OwnershipChange::Transfer);
+
+        Ok(())
+    }
+
+    fn next_bid_id() -> Result<BidId, DispatchError> {
+        NextBidId::<T, I>::try_mutate(|next_id| -> Result<BidId, DispatchError> {
+            let bid_id = *next_id;
+            *next_id = next_id.checked_add(1).ok_or(ArithmeticError::Overflow)?;
+            Ok(bid_id)
+        })
+    }
+
+    // remove a floor bid after `check` accepted it
+    fn take_floor_bid(class_id: ClassIdOf<T, I>, bid_id: BidId, check: impl FnOnce(&FloorBidOf<T, I>) -> DispatchResult) -> Result<FloorBidOf<T, I>, DispatchError> {
+        FloorBids::<T, I>::try_mutate_exists(class_id, |maybe_bids| {
+            let bids = maybe_bids.as_mut().ok_or(Error::<T, I>::UnknownBid)?;
+            let index = bids.iter().position(|(id, _)| *id == bid_id).ok_or(Error::<T, I>::UnknownBid)?;
+            check(&bids[index].1)?;
+
+            let (_, bid) = bids.remove(index);
+            // collections without bids don't need an entry
+            if bids.is_empty() {
+                *maybe_bids = None;
+            }
+
+            Ok(bid)
+        })
+    }
+
+    fn record_owner_change(class_id: ClassIdOf<T, I>, kitty_id: KittyIndexOf<T, I>, from: Option<T::AccountId>, to: T::AccountId, kind: OwnershipChange<BalanceOf<T, I>>) {
+        let max_length = Self::parameters().max_history_length as usize;
+        if max_length == 0 {
+            return;
+        }
+
+        let record = OwnershipRecord { from, to, block: frame_system::Pallet::<T>::block_number(), kind };
+        OwnershipHistory::<T, I>::mutate(class_id, kitty_id, |history| {
+            // the history is a ring buffer, so the oldest changes make room for the new one
+            if history.len() >= max_length {
+                history.drain(..history.len() + 1 - max_length);
+            }
+            history.push(record);
+        });
+    }
+
+    fn ensure_not_blocked(who: &T::AccountId) -> DispatchResult {
+        ensure!(!BlockedAccounts::<T, I>::contains_key(who), Error::<T, I>::AccountBlocked);
+
+        Ok(())
+    }
+
+    fn remove_block_expiry(who: &T::AccountId, expiry: Option<T::BlockNumber>) {
+        if let Some(expiry) = expiry {
+            BlockExpiries::<T, I>::mutate_exists(expiry, |accounts| {
+                if let Some(list) = accounts {
+                    list.retain(|account| account != who);
+                    if list.is_empty() {
+                        *accounts = None;
+                    }
+                }
+            });
+        }
+    }
+
+    // unblock the accounts whose block expires at `now`
+    fn expire_blocks(now: T::BlockNumber) -> Weight {
+        let expired = BlockExpiries::<T, I>::take(now);
+
+        for who in &expired {
+            BlockedAccounts::<T, I>::remove(who);
+            Self::deposit_event(Event::AccountUnblocked(who.clone()));
+        }
+
+        T::DbWeight::get().reads_writes(1, 1 + expired.len() as Weight)
+    }
+
+    // clear the state that the previous owner of a kitty has set
+    fn on_owner_changed(class_id: ClassIdOf<T, I>, kitty_id: KittyIndexOf<T, I>) {
+        if let Some(price) = KittyPrices::<T, I>::take(class_id, kitty_id) {
+            Self::remove_listing(class_id, kitty_id, price);
+        }
+        Self::remove_auto_breed_candidate(class_id, kitty_id);
+        KittyApprovals::<T, I>::remove(class_id, kitty_id);
+    }
+
+    // reserve the deposit for `len` bytes of name and metadata of a kitty from `who`,
+    // and refund the previous deposit
+    fn update_deposit(who: &T::AccountId, class_id: ClassIdOf<T, I>, kitty_id: KittyIndexOf<T, I>, len: usize) -> DispatchResult {
+        let deposit = Self::parameters().deposit_per_byte.saturating_mul((len as u32).into());
+        let old_deposit = Self::kitty_deposit(class_id, kitty_id);
+
+        match old_deposit {
+            // the owner paid the previous deposit, so only the difference is reserved or refunded
+            Some((ref depositor, old_amount)) if depositor == who => {
+                if deposit > old_amount {
+                    T::Currency::reserve(who, deposit - old_amount)?;
+                } else {
+                    T::Currency::unreserve(who, old_amount - deposit);
+                }
+            },
+            // the previous owner paid the previous deposit
+            _ => {
+                T::Currency::reserve(who, deposit)?;
+
+                if let Some((depositor, old_amount)) = old_deposit {
+                    T::Currency::unreserve(&depositor, old_amount);
+                }
+            },
+        }
+
+        if deposit.is_zero() {
+            KittyDeposits::<T, I>::remove(class_id, kitty_id);
+        } else {
+            KittyDeposits::<T, I>::insert(class_id, kitty_id, (who.clone(), deposit));
+        }
 
         Ok(())
     }
 
-    fn run_offchain_worker() -> Result<(), ()> {
-        // declare a storage lock with key "kitties/lock"
-        // it will spend 1 block worth of time to run the offchain worker
-        let mut lock = StorageLock::<'_, BlockAndTime<frame_system::Pallet<T>>>::with_block_deadline(&b"kitties/lock"[..], 1);
+    // move a kitty in the sorted listings from its old price to its new price
+    fn update_listing(class_id: ClassIdOf<T, I>, kitty_id: KittyIndexOf<T, I>, old_price: Option<BalanceOf<T, I>>, new_price: Option<BalanceOf<T, I>>) {
+        if let Some(price) = old_price {
+            ListingsByPrice::<T, I>::remove(class_id, ListingKey { price, kitty_id });
+        }
+        if let Some(price) = new_price {
+            ListingsByPrice::<T, I>::insert(class_id, ListingKey { price, kitty_id }, ());
+        }
+    }
+
+    fn remove_listing(class_id: ClassIdOf<T, I>, kitty_id: KittyIndexOf<T, I>, price: BalanceOf<T, I>) {
+        Self::update_listing(class_id, kitty_id, Some(price), None);
+    }
+
+    fn add_auto_breed_candidate(class_id: ClassIdOf<T, I>, kitty_id: KittyIndexOf<T, I>) {
+        if AutoBreedConsent::<T, I>::contains_key(class_id, kitty_id) {
+            return;
+        }
+
+        // append the kitty at the end of the candidates index of its collection
+        let index = Self::auto_breed_candidate_count(class_id);
+        AutoBreedCandidates::<T, I>::insert(class_id, index, kitty_id);
+        AutoBreedConsent::<T, I>::insert(class_id, kitty_id, index);
+        AutoBreedCandidateCount::<T, I>::insert(class_id, index.saturating_add(1));
+    }
+
+    fn remove_auto_breed_candidate(class_id: ClassIdOf<T, I>, kitty_id: KittyIndexOf<T, I>) {
+        if let Some(index) = AutoBreedConsent::<T, I>::take(class_id, kitty_id) {
+            let last_index = Self::auto_breed_candidate_count(class_id).saturating_sub(1);
+
+            // move the last candidate into the freed slot, so the index stays contiguous
+            if index != last_index {
+                if let Some(last_kitty_id) = AutoBreedCandidates::<T, I>::get(class_id, last_index) {
+                    AutoBreedCandidates::<T, I>::insert(class_id, index, last_kitty_id);
+                    AutoBreedConsent::<T, I>::insert(class_id, last_kitty_id, index);
+                }
+            }
+
+            AutoBreedCandidates::<T, I>::remove(class_id, last_index);
+            // the offchain worker iterates the counts, so empty collections are removed
+            if last_index == 0 {
+                AutoBreedCandidateCount::<T, I>::remove(class_id);
+            } else {
+                AutoBreedCandidateCount::<T, I>::insert(class_id, last_index);
+            }
+        }
+    }
+
+    fn run_offchain_worker(now: T::BlockNumber) -> Result<(), ()> {
+        // the node operator can change how (and if) the miner runs
+        let config = MinerConfig::load();
+
+        if !config.enabled || config.max_iterations == 0 || !Self::parameters().auto_breeding_enabled || Self::is_paused(CallKind::AutoBreed) {
+            return Ok(());
+        }
+
+        // only run once every `interval` blocks
+        if !(now % T::BlockNumber::from(config.interval)).is_zero() {
+            return Ok(());
+        }
+
+        // declare a storage lock with key "<pallet name>/lock", so every instance of the pallet
+        // has its own lock
+        // it will spend at most `interval` blocks worth of time to run the offchain worker
+        let lock_key = [<Self as PalletInfoAccess>::name().as_bytes(), b"/lock"].concat();
+        let mut lock = StorageLock::<'_, BlockAndTime<frame_system::Pallet<T>>>::with_block_deadline(&lock_key, config.interval);
         // try to acquire the lock; if another offchain worker with that key is already running and
         // holding the lock, the try_lock() line will fail, so a new offchain worker will not be run
         let _guard = lock.try_lock().map_err(|_| ())?;
@@ -412,36 +2962,37 @@ impl<T: Config> Pallet<T> {
         // generate random numbers using ChaChaRng
         let mut rng = ChaChaRng::from_seed(random_seed);
 
-        // get the kitty count and convert it to u32
-        // this will only work if kitty_count <= u32::max_value()
-        //  it will fail if there are more kitties than the maximum number of kitties
-        let kitty_count = TryInto::<u32>::try_into(orml_nft::Pallet::<T>::next_token_id(Self::class_id())).map_err(|_| ())?;
+        // kitties can only be bred with kitties of the same collection, so only the collections
+        // that allow breeding and have enough candidates can be picked
+        let collections: Vec<(ClassIdOf<T, I>, u32)> = AutoBreedCandidateCount::<T, I>::iter()
+            .filter(|(class_id, candidate_count)| *candidate_count >= 2 && Self::is_breeding_enabled(*class_id))
+            .collect();
 
-        // if there are no kitties, there is nothing to be done
-        if kitty_count == 0 {
+        // if there are not enough candidates, there is nothing to be done
+        if collections.is_empty() {
             return Ok(());
         }
 
-        // set max iterations so the offchain worker does not work forever
-        const MAX_ITERATIONS: u128 = 500;
+        // pick a random collection
+        let (class_id, candidate_count) = collections[rng.next_u32() as usize % collections.len()];
 
         // get the latest nonce
         let nonce = Self::auto_breed_nonce();
 
-        // keep count of the remaining iterations
-        let mut remaining_iterations = MAX_ITERATIONS;
+        // keep count of the remaining iterations, so the offchain worker does not work forever
+        let mut remaining_iterations = config.max_iterations as u128;
 
         // pick a random pair of kitties
         let (kitty_1, kitty_2) = loop {
-            // get 2 u32 random numbers and convert them to kitty ids
-            let kitty_id_1: KittyIndexOf<T> = (rng.next_u32() % kitty_count).into();
-            let kitty_id_2: KittyIndexOf<T> = (rng.next_u32() % kitty_count).into();
+            // get 2 u32 random numbers and use them to pick 2 candidates
+            let kitty_id_1 = AutoBreedCandidates::<T, I>::get(class_id, rng.next_u32() % candidate_count).ok_or(())?;
+            let kitty_id_2 = AutoBreedCandidates::<T, I>::get(class_id, rng.next_u32() % candidate_count).ok_or(())?;
 
             // get the kitties with these ids
-            let kitty_1 = orml_nft::Pallet::<T>::tokens(Self::class_id(), kitty_id_1).ok_or(())?;
-            let kitty_2 = orml_nft::Pallet::<T>::tokens(Self::class_id(), kitty_id_2).ok_or(())?;
+            let kitty_1 = Self::kitty(class_id, kitty_id_1).ok_or(())?;
+            let kitty_2 = Self::kitty(class_id, kitty_id_2).ok_or(())?;
 
-            if kitty_1.data.gender() != kitty_2.data.gender() {
+            if kitty_1.gender() != kitty_2.gender() && Self::is_ready_to_breed(class_id, kitty_id_1) && Self::is_ready_to_breed(class_id, kitty_id_2) {
                 break (kitty_id_1, kitty_id_2);
             }
 
@@ -467,8 +3018,8 @@ impl<T: Config> Pallet<T> {
             let solution = (solution_prefix << 32) + i;
             // if the miner is lucky and a solution was found, submit an unsigned transaction with
             // the solution
-            if Self::validate_solution(kitty_1, kitty_2, nonce, solution) {
-                let _ = SubmitTransaction::<T, Call<T>>::submit_unsigned_transaction(Call::<T>::auto_breed(kitty_1, kitty_2, nonce, solution).into());
+            if Self::validate_solution(class_id, kitty_1, kitty_2, nonce, solution) {
+                let _ = SubmitTransaction::<T, Call<T, I>>::submit_unsigned_transaction(Call::<T, I>::auto_breed(class_id, kitty_1, kitty_2, nonce, solution).into());
                 break;
             }
         }
@@ -476,13 +3027,13 @@ impl<T: Config> Pallet<T> {
         Ok(())
     }
 
-    fn validate_solution(kitty_id_1: KittyIndexOf<T>, kitty_id_2: KittyIndexOf<T>, nonce: u32, solution: u128) -> bool {
-        let payload = (kitty_id_1, kitty_id_2, nonce, solution);
+    fn validate_solution(class_id: ClassIdOf<T, I>, kitty_id_1: KittyIndexOf<T, I>, kitty_id_2: KittyIndexOf<T, I>, nonce: u32, solution: u128) -> bool {
+        let payload = (class_id, kitty_id_1, kitty_id_2, nonce, solution);
         // hash the payload
         let hash = payload.using_encoded(blake2_128);
         // convert the 128-bit hash to a u128 number
         let hash_value = u128::from_le_bytes(hash);
-        let difficulty = T::DefaultDifficulty::get();
+        let difficulty = Self::parameters().difficulty;
 
         // create a random chance of finding a valid solution (based on difficulty)
         // for example if difficulty == 2, then there is a 50% chance of finding a solution
@@ -0,0 +1,294 @@
+//! Storage migrations of the kitties pallet.
+//!
+//! `migrate()` runs every migration that the current `StorageVersion` has not seen yet, so it is
+//! safe to call it on every runtime upgrade.
+//!
+//! An instance of the pallet that is added to a running chain has no `StorageVersion` either,
+//! but it has no state of V1, so it starts with the latest layout.
+//!
+//! Before V3 the kitties could only be stored in orml_nft, so the migrations change the storage
+//! of orml_nft as well. They are not run by the hooks of the pallet, because the pallet does not
+//! know its NFT backend; the runtime has to run `MigrateFromOrml` instead.
+
+use super::*;
+
+use frame_support::{
+    storage::migration::{have_storage_value, storage_key_iter, take_storage_value},
+    traits::{OnRuntimeUpgrade, PalletInfoAccess},
+    ReversibleStorageHasher,
+};
+
+/// A kitties pallet that stores its kitties in orml_nft
+pub trait OrmlConfig<I: 'static = ()>:
+    Config<I> + orml_nft::Config<ClassId = ClassIdOf<Self, I>, TokenId = KittyIndexOf<Self, I>, TokenData = Kitty, ClassData = KittyClassData>
+{}
+
+impl<T, I: 'static> OrmlConfig<I> for T
+where
+    T: Config<I> + orml_nft::Config<ClassId = ClassIdOf<T, I>, TokenId = KittyIndexOf<T, I>, TokenData = Kitty, ClassData = KittyClassData>,
+{}
+
+/// Runs the migrations of a kitties pallet that stores its kitties in orml_nft.
+/// Add it to the `OnRuntimeUpgrade` of the executive.
+pub struct MigrateFromOrml<T, I = ()>(PhantomData<(T, I)>);
+
+impl<T: OrmlConfig<I>, I: 'static> OnRuntimeUpgrade for MigrateFromOrml<T, I> {
+    fn on_runtime_upgrade() -> Weight {
+        migrate::<T, I>()
+    }
+
+    #[cfg(feature = "try-runtime")]
+    fn pre_upgrade() -> Result<(), &'static str> {
+        pre_migrate::<T, I>()
+    }
+
+    #[cfg(feature = "try-runtime")]
+    fn post_upgrade() -> Result<(), &'static str> {
+        post_migrate::<T, I>()
+    }
+}
+
+/// Start a new instance of the pallet with the latest layout. Does nothing if the instance has
+/// state of an older layout.
+pub fn initialize<T: Config<I>, I: 'static>() -> Weight {
+    let weight = T::DbWeight::get().reads(2);
+
+    if StorageVersion::<T, I>::get() == Releases::V1_0_0 && !v2::has_v1_state::<T, I>() {
+        StorageVersion::<T, I>::put(Releases::V3_0_0);
+        return weight.saturating_add(T::DbWeight::get().writes(1));
+    }
+
+    weight
+}
+
+/// Run all migrations that have not been applied yet
+pub fn migrate<T: OrmlConfig<I>, I: 'static>() -> Weight {
+    // a new instance of the pallet has nothing to migrate
+    let mut weight = initialize::<T, I>();
+
+    if StorageVersion::<T, I>::get() == Releases::V1_0_0 {
+        weight = weight.saturating_add(v2::migrate::<T, I>());
+    }
+
+    if StorageVersion::<T, I>::get() == Releases::V2_0_0 {
+        weight = weight.saturating_add(v3::migrate::<T, I>());
+    }
+
+    weight
+}
+
+/// Check the state before the migrations are run
+#[cfg(any(test, feature = "try-runtime"))]
+pub fn pre_migrate<T: OrmlConfig<I>, I: 'static>() -> Result<(), &'static str> {
+    match StorageVersion::<T, I>::get() {
+        Releases::V1_0_0 if v2::has_v1_state::<T, I>() => v2::pre_migrate::<T, I>(),
+        _ => Ok(()),
+    }
+}
+
+/// Check the state after the migrations were run
+#[cfg(any(test, feature = "try-runtime"))]
+pub fn post_migrate<T: OrmlConfig<I>, I: 'static>() -> Result<(), &'static str> {
+    ensure!(StorageVersion::<T, I>::get() == Releases::V3_0_0, "the storage version was not updated");
+
+    v2::post_migrate::<T, I>()?;
+    v3::post_migrate::<T, I>()
+}
+
+/// Moves the single collection of V1 into the collections of V2, and gives every kitty a
+/// generation.
+pub mod v2 {
+    use super::*;
+
+    /// The kitty of V1 was only its dna
+    #[derive(Encode, Decode, Clone, RuntimeDebug, PartialEq, Eq)]
+    pub struct OldKitty(pub [u8; 16]);
+
+    /// The orml_nft class of V1 had no class data
+    pub type OldClassInfoOf<T> = orml_nft::ClassInfo<<T as orml_nft::Config>::TokenId, <T as frame_system::Config>::AccountId, (), orml_nft::ClassMetadataOf<T>>;
+    /// The orml_nft token of V1 stored the old kitty
+    pub type OldTokenInfoOf<T> = orml_nft::TokenInfo<<T as frame_system::Config>::AccountId, OldKitty, orml_nft::TokenMetadataOf<T>>;
+
+    // the name of the pallet in the runtime, which is the prefix of its storage
+    fn pallet_name<T: Config<I>, I: 'static>() -> &'static [u8] {
+        <Pallet<T, I> as PalletInfoAccess>::name().as_bytes()
+    }
+
+    /// Whether the storage of the pallet is in the V1 layout, which always has a `ClassId`
+    pub fn has_v1_state<T: Config<I>, I: 'static>() -> bool {
+        have_storage_value(pallet_name::<T, I>(), b"ClassId", &[])
+    }
+
+    // remove all entries of a V1 map that was keyed by the kitty id
+    fn drain_map<K: Decode, V: Decode, H: ReversibleStorageHasher>(pallet: &[u8], item: &[u8]) -> Vec<(K, V)> {
+        storage_key_iter::<K, V, H>(pallet, item).drain().collect()
+    }
+
+    pub fn migrate<T: OrmlConfig<I>, I: 'static>() -> Weight {
+        let pallet = pallet_name::<T, I>();
+        let mut reads: Weight = 0;
+        let mut writes: Weight = 0;
+
+        // the only collection of V1 becomes an ordinary collection
+        let class_id: ClassIdOf<T, I> = take_storage_value(pallet, b"ClassId", &[]).unwrap_or_default();
+        reads += 1;
+        writes += 1;
+
+        orml_nft::Classes::<T>::translate_values::<OldClassInfoOf<T>, _>(|class| {
+            reads += 1;
+            writes += 1;
+
+            Some(orml_nft::ClassInfo {
+                metadata: class.metadata,
+                total_issuance: class.total_issuance,
+                owner: class.owner,
+                data: KittyClassData::default(),
+            })
+        });
+
+        // the generation of the existing kitties is not known, so they are all treated as
+        // the first generation
+        orml_nft::Tokens::<T>::translate_values::<OldTokenInfoOf<T>, _>(|token| {
+            reads += 1;
+            writes += 1;
+
+            Some(orml_nft::TokenInfo {
+                metadata: token.metadata,
+                owner: token.owner,
+                data: Kitty::new(token.data.0),
+            })
+        });
+
+        // everything that was stored about a kitty is now keyed by its collection as well
+        // (the old and the new entries share the same prefix, so the old entries are all
+        // removed before the new ones are inserted)
+        let prices = drain_map::<KittyIndexOf<T, I>, BalanceOf<T, I>, Blake2_128Concat>(pallet, b"KittyPrices");
+        let names = drain_map::<KittyIndexOf<T, I>, Vec<u8>, Blake2_128Concat>(pallet, b"KittyNames");
+        let metadata = drain_map::<KittyIndexOf<T, I>, Vec<u8>, Blake2_128Concat>(pallet, b"KittyMetadata");
+        let deposits = drain_map::<KittyIndexOf<T, I>, (T::AccountId, BalanceOf<T, I>), Blake2_128Concat>(pallet, b"KittyDeposits");
+        let next_breed_blocks = drain_map::<KittyIndexOf<T, I>, T::BlockNumber, Blake2_128Concat>(pallet, b"NextBreedBlock");
+        let consents = drain_map::<KittyIndexOf<T, I>, u32, Blake2_128Concat>(pallet, b"AutoBreedConsent");
+        let candidates = drain_map::<u32, KittyIndexOf<T, I>, Twox64Concat>(pallet, b"AutoBreedCandidates");
+        let listings: Option<Vec<(BalanceOf<T, I>, KittyIndexOf<T, I>)>> = take_storage_value(pallet, b"ListingsByPrice", &[]);
+        let candidate_count: Option<u32> = take_storage_value(pallet, b"AutoBreedCandidateCount", &[]);
+
+        let moved = (prices.len() + listings.as_ref().map_or(0, |listings| listings.len()) + names.len() + metadata.len() + deposits.len() + next_breed_blocks.len() + consents.len() + candidates.len()) as Weight + 2;
+        reads += moved;
+        // every entry is removed and inserted again
+        writes += moved * 2;
+
+        for (kitty_id, price) in prices {
+            KittyPrices::<T, I>::insert(class_id, kitty_id, price);
+        }
+        for (kitty_id, name) in names {
+            KittyNames::<T, I>::insert(class_id, kitty_id, name);
+        }
+        for (kitty_id, metadata) in metadata {
+            KittyMetadata::<T, I>::insert(class_id, kitty_id, metadata);
+        }
+        for (kitty_id, deposit) in deposits {
+            KittyDeposits::<T, I>::insert(class_id, kitty_id, deposit);
+        }
+        // v1 stored the first block at which a kitty can breed again
+        for (kitty_id, block) in next_breed_blocks {
+            LastBreedBlock::<T, I>::insert(class_id, kitty_id, block.saturating_sub(T::BreedCooldown::get()));
+        }
+        for (kitty_id, index) in consents {
+            AutoBreedConsent::<T, I>::insert(class_id, kitty_id, index);
+        }
+        for (index, kitty_id) in candidates {
+            AutoBreedCandidates::<T, I>::insert(class_id, index, kitty_id);
+        }
+        for (price, kitty_id) in listings.unwrap_or_default() {
+            ListingsByPrice::<T, I>::insert(class_id, ListingKey { price, kitty_id }, ());
+        }
+        if let Some(candidate_count) = candidate_count.filter(|count| *count > 0) {
+            AutoBreedCandidateCount::<T, I>::insert(class_id, candidate_count);
+        }
+
+        StorageVersion::<T, I>::put(Releases::V2_0_0);
+        writes += 1;
+
+        T::DbWeight::get().reads_writes(reads, writes)
+    }
+
+    #[cfg(any(test, feature = "try-runtime"))]
+    pub fn pre_migrate<T: OrmlConfig<I>, I: 'static>() -> Result<(), &'static str> {
+        let pallet = pallet_name::<T, I>();
+
+        // the kitties of V1 all belong to the class in `ClassId`
+        let class_id: ClassIdOf<T, I> = frame_support::storage::migration::get_storage_value(pallet, b"ClassId", &[])
+            .ok_or("the class of the kitties is not set")?;
+        ensure!(orml_nft::Classes::<T>::contains_key(class_id), "the class of the kitties does not exist");
+
+        Ok(())
+    }
+
+    #[cfg(any(test, feature = "try-runtime"))]
+    pub fn post_migrate<T: OrmlConfig<I>, I: 'static>() -> Result<(), &'static str> {
+        let pallet = pallet_name::<T, I>();
+
+        ensure!(
+            frame_support::storage::migration::get_storage_value::<ClassIdOf<T, I>>(pallet, b"ClassId", &[]).is_none(),
+            "the class of V1 was not removed",
+        );
+
+        // iterating skips the values that can not be decoded, so every class and every kitty
+        // has been translated if the numbers match
+        for (class_id, class) in orml_nft::Classes::<T>::iter() {
+            let tokens = orml_nft::Tokens::<T>::iter_prefix_values(class_id).count() as u32;
+            ensure!(class.total_issuance == tokens.into(), "a kitty could not be translated");
+        }
+
+        for (class_id, kitty_id, _) in KittyPrices::<T, I>::iter() {
+            ensure!(orml_nft::Tokens::<T>::contains_key(class_id, kitty_id), "a price belongs to a kitty that does not exist");
+        }
+
+        Ok(())
+    }
+}
+
+/// Gives the collections to the instance of the pallet. Before V3 there could only be one
+/// instance, so every class of orml_nft belongs to it.
+///
+/// The kitties of every owner are indexed by the pallet as well, the index of orml_nft is not
+/// sorted.
+pub mod v3 {
+    use super::*;
+
+    pub fn migrate<T: OrmlConfig<I>, I: 'static>() -> Weight {
+        let mut classes: Weight = 0;
+        let mut kitties: Weight = 0;
+
+        for (class_id, _) in orml_nft::Classes::<T>::iter() {
+            Collections::<T, I>::insert(class_id, ());
+            classes += 1;
+        }
+
+        for (owner, (class_id, kitty_id), _) in orml_nft::TokensByOwner::<T>::iter() {
+            KittiesByOwner::<T, I>::insert((owner, class_id), OrderedKittyIndex(kitty_id), ());
+            kitties += 1;
+        }
+
+        StorageVersion::<T, I>::put(Releases::V3_0_0);
+
+        T::DbWeight::get().reads_writes(classes + kitties, classes + kitties + 1)
+    }
+
+    #[cfg(any(test, feature = "try-runtime"))]
+    pub fn post_migrate<T: OrmlConfig<I>, I: 'static>() -> Result<(), &'static str> {
+        for (class_id, _) in Collections::<T, I>::iter() {
+            ensure!(orml_nft::Classes::<T>::contains_key(class_id), "a collection does not exist");
+        }
+
+        for (class_id, _, _) in KittyPrices::<T, I>::iter() {
+            ensure!(Collections::<T, I>::contains_key(class_id), "a price belongs to a collection of another instance");
+        }
+
+        for ((owner, class_id), kitty_id, _) in KittiesByOwner::<T, I>::iter() {
+            ensure!(orml_nft::TokensByOwner::<T>::contains_key(owner, (class_id, kitty_id.0)), "an indexed kitty belongs to someone else");
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,171 @@
+//! The NFT pallets that can hold the kitties.
+//!
+//! The game logic only uses the operations of `NftBackend`, so the kitties can be stored in
+//! orml_nft or in pallet_uniques (with `UniquesBackend`) without changing the rest of the pallet.
+
+use super::*;
+
+use frame_support::traits::tokens::nonfungibles::{Create, Inspect, Mutate, Transfer};
+use sp_runtime::{
+    traits::{AtLeast32BitUnsigned, CheckedAdd, One, Saturating},
+    ArithmeticError,
+};
+
+/// The operations of an NFT pallet that are needed to store the kitties
+pub trait NftBackend<AccountId> {
+    /// The id of a collection
+    type ClassId: Parameter + Member + AtLeast32BitUnsigned + Default + Copy;
+    /// The id of a kitty, unique within its collection
+    type TokenId: Parameter + Member + AtLeast32BitUnsigned + Default + Copy;
+
+    /// Create a new class and return its id
+    fn create_class(owner: &AccountId, data: KittyClassData) -> Result<Self::ClassId, DispatchError>;
+    /// Get the data of a class. None means the class does not exist.
+    fn class_data(class_id: Self::ClassId) -> Option<KittyClassData>;
+    /// Get the number of tokens that exist in a class
+    fn total_issuance(class_id: Self::ClassId) -> Self::TokenId;
+    /// Mint a new token and return its id. Every new token of a class gets a higher id than the
+    /// tokens that were minted before.
+    fn mint(owner: &AccountId, class_id: Self::ClassId, data: Kitty) -> Result<Self::TokenId, DispatchError>;
+    /// Transfer a token. Fails if `from` is not the owner of the token.
+    fn transfer(from: &AccountId, to: &AccountId, class_id: Self::ClassId, token_id: Self::TokenId) -> DispatchResult;
+    /// Burn a token. Fails if `owner` is not the owner of the token.
+    fn burn(owner: &AccountId, class_id: Self::ClassId, token_id: Self::TokenId) -> DispatchResult;
+    /// Get the owner of a token. None means the token does not exist.
+    fn owner(class_id: Self::ClassId, token_id: Self::TokenId) -> Option<AccountId>;
+    /// Get the kitty of a token
+    fn token_data(class_id: Self::ClassId, token_id: Self::TokenId) -> Option<Kitty>;
+}
+
+/// orml_nft stores the kitty as the token data, and the settings of a collection as the class data
+impl<T> NftBackend<T::AccountId> for orml_nft::Pallet<T>
+where
+    T: orml_nft::Config<TokenData = Kitty, ClassData = KittyClassData>,
+{
+    type ClassId = T::ClassId;
+    type TokenId = T::TokenId;
+
+    fn create_class(owner: &T::AccountId, data: KittyClassData) -> Result<Self::ClassId, DispatchError> {
+        orml_nft::Pallet::<T>::create_class(owner, Vec::new(), data)
+    }
+
+    fn class_data(class_id: Self::ClassId) -> Option<KittyClassData> {
+        orml_nft::Pallet::<T>::classes(class_id).map(|class| class.data)
+    }
+
+    fn total_issuance(class_id: Self::ClassId) -> Self::TokenId {
+        orml_nft::Pallet::<T>::classes(class_id).map(|class| class.total_issuance).unwrap_or_default()
+    }
+
+    fn mint(owner: &T::AccountId, class_id: Self::ClassId, data: Kitty) -> Result<Self::TokenId, DispatchError> {
+        orml_nft::Pallet::<T>::mint(owner, class_id, Vec::new(), data)
+    }
+
+    fn transfer(from: &T::AccountId, to: &T::AccountId, class_id: Self::ClassId, token_id: Self::TokenId) -> DispatchResult {
+        orml_nft::Pallet::<T>::transfer(from, to, (class_id, token_id))
+    }
+
+    fn burn(owner: &T::AccountId, class_id: Self::ClassId, token_id: Self::TokenId) -> DispatchResult {
+        orml_nft::Pallet::<T>::burn(owner, (class_id, token_id))
+    }
+
+    fn owner(class_id: Self::ClassId, token_id: Self::TokenId) -> Option<T::AccountId> {
+        orml_nft::Pallet::<T>::tokens(class_id, token_id).map(|token| token.owner)
+    }
+
+    fn token_data(class_id: Self::ClassId, token_id: Self::TokenId) -> Option<Kitty> {
+        orml_nft::Pallet::<T>::tokens(class_id, token_id).map(|token| token.data)
+    }
+}
+
+/// Stores the kitties in pallet_uniques, through the kitties pallet of instance `I`.
+///
+/// pallet_uniques only keeps the owners of the tokens and does not pick their ids, so the kitties,
+/// the settings of the collections, the number of kitties and the next ids are kept in the
+/// `Uniques*` storage of the kitties pallet. `U` is the instance of pallet_uniques.
+///
+/// A runtime uses it with `type Nft = UniquesBackend<Runtime, KittiesInstance, UniquesInstance>`.
+pub struct UniquesBackend<T, I = (), U = ()>(PhantomData<(T, I, U)>);
+
+/// The runtime must not include the calls of pallet_uniques, otherwise the kitties could be
+/// transferred and burned without this pallet. The classes are created without a free holding,
+/// so `ClassDeposit` and `InstanceDeposit` are reserved from the owner of the class.
+impl<T, I, U> NftBackend<T::AccountId> for UniquesBackend<T, I, U>
+where
+    T: Config<I, Nft = UniquesBackend<T, I, U>> + pallet_uniques::Config<U>,
+    I: 'static,
+    U: 'static,
+    <T as pallet_uniques::Config<U>>::ClassId: AtLeast32BitUnsigned,
+    <T as pallet_uniques::Config<U>>::InstanceId: AtLeast32BitUnsigned,
+{
+    type ClassId = <T as pallet_uniques::Config<U>>::ClassId;
+    type TokenId = <T as pallet_uniques::Config<U>>::InstanceId;
+
+    fn create_class(owner: &T::AccountId, data: KittyClassData) -> Result<Self::ClassId, DispatchError> {
+        // the instances of the kitties pallet can share an instance of pallet_uniques, so the ids
+        // that are taken already are skipped
+        let class_id = UniquesNextClassId::<T, I>::try_mutate(|next_id| -> Result<Self::ClassId, DispatchError> {
+            let mut class_id = *next_id;
+            while <pallet_uniques::Pallet<T, U> as Inspect<T::AccountId>>::class_owner(&class_id).is_some() {
+                class_id = class_id.checked_add(&One::one()).ok_or(ArithmeticError::Overflow)?;
+            }
+            *next_id = class_id.checked_add(&One::one()).ok_or(ArithmeticError::Overflow)?;
+            Ok(class_id)
+        })?;
+
+        <pallet_uniques::Pallet<T, U> as Create<T::AccountId>>::create_class(&class_id, owner, owner)?;
+
+        UniquesClassData::<T, I>::insert(class_id, data);
+
+        Ok(class_id)
+    }
+
+    fn class_data(class_id: Self::ClassId) -> Option<KittyClassData> {
+        UniquesClassData::<T, I>::get(class_id)
+    }
+
+    fn total_issuance(class_id: Self::ClassId) -> Self::TokenId {
+        UniquesTotalIssuance::<T, I>::get(class_id)
+    }
+
+    fn mint(owner: &T::AccountId, class_id: Self::ClassId, data: Kitty) -> Result<Self::TokenId, DispatchError> {
+        let token_id = UniquesNextTokenId::<T, I>::try_mutate(class_id, |next_id| -> Result<Self::TokenId, DispatchError> {
+            let token_id = *next_id;
+            *next_id = token_id.checked_add(&One::one()).ok_or(ArithmeticError::Overflow)?;
+            Ok(token_id)
+        })?;
+
+        <pallet_uniques::Pallet<T, U> as Mutate<T::AccountId>>::mint_into(&class_id, &token_id, owner)?;
+
+        UniquesTokenData::<T, I>::insert(class_id, token_id, data);
+        UniquesTotalIssuance::<T, I>::mutate(class_id, |issuance| *issuance = issuance.saturating_add(One::one()));
+
+        Ok(token_id)
+    }
+
+    fn transfer(from: &T::AccountId, to: &T::AccountId, class_id: Self::ClassId, token_id: Self::TokenId) -> DispatchResult {
+        // pallet_uniques transfers a token from whoever owns it
+        ensure!(Self::owner(class_id, token_id).as_ref() == Some(from), pallet_uniques::Error::<T, U>::NoPermission);
+
+        <pallet_uniques::Pallet<T, U> as Transfer<T::AccountId>>::transfer(&class_id, &token_id, to)
+    }
+
+    fn burn(owner: &T::AccountId, class_id: Self::ClassId, token_id: Self::TokenId) -> DispatchResult {
+        ensure!(Self::owner(class_id, token_id).as_ref() == Some(owner), pallet_uniques::Error::<T, U>::NoPermission);
+
+        <pallet_uniques::Pallet<T, U> as Mutate<T::AccountId>>::burn_from(&class_id, &token_id)?;
+
+        UniquesTokenData::<T, I>::remove(class_id, token_id);
+        UniquesTotalIssuance::<T, I>::mutate(class_id, |issuance| *issuance = issuance.saturating_sub(One::one()));
+
+        Ok(())
+    }
+
+    fn owner(class_id: Self::ClassId, token_id: Self::TokenId) -> Option<T::AccountId> {
+        <pallet_uniques::Pallet<T, U> as Inspect<T::AccountId>>::owner(&class_id, &token_id)
+    }
+
+    fn token_data(class_id: Self::ClassId, token_id: Self::TokenId) -> Option<Kitty> {
+        UniquesTokenData::<T, I>::get(class_id, token_id)
+    }
+}
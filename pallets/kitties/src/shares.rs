@@ -0,0 +1,70 @@
+//! The fungible assets pallets that can hold the shares of fractionalised kitties.
+//!
+//! The game logic only uses the operations of `SharesBackend`, like it does for the NFTs with
+//! `NftBackend`.
+
+use super::*;
+
+use frame_support::traits::tokens::fungibles::{Create, Destroy, Inspect, Mutate};
+use sp_runtime::traits::{AtLeast32BitUnsigned, One};
+
+/// The operations of a fungible assets pallet that are needed to fractionalise kitties
+pub trait SharesBackend<AccountId, Balance> {
+    /// The id of an asset class
+    type AssetId: Parameter + Member + AtLeast32BitUnsigned + Default + Copy;
+
+    /// Check if an asset class exists
+    fn exists(asset_id: Self::AssetId) -> bool;
+    /// Create a new asset class that is administered by `admin`
+    fn create(asset_id: Self::AssetId, admin: &AccountId) -> DispatchResult;
+    /// Destroy an asset class and all of its shares
+    fn destroy(asset_id: Self::AssetId) -> DispatchResult;
+    /// Mint new shares
+    fn mint(asset_id: Self::AssetId, who: &AccountId, amount: Balance) -> DispatchResult;
+    /// Burn shares. Fails if `who` does not hold `amount` shares.
+    fn burn(asset_id: Self::AssetId, who: &AccountId, amount: Balance) -> DispatchResult;
+    /// Get the number of shares `who` holds
+    fn balance(asset_id: Self::AssetId, who: &AccountId) -> Balance;
+    /// Get the number of shares that exist
+    fn total_issuance(asset_id: Self::AssetId) -> Balance;
+}
+
+/// The shares are sufficient assets with a minimum balance of one, so any account can hold them.
+impl<T, I> SharesBackend<T::AccountId, T::Balance> for pallet_assets::Pallet<T, I>
+where
+    T: pallet_assets::Config<I>,
+    I: 'static,
+    T::AssetId: AtLeast32BitUnsigned + Default,
+{
+    type AssetId = T::AssetId;
+
+    // every existing asset class has a minimum balance above zero
+    fn exists(asset_id: Self::AssetId) -> bool {
+        !<Self as Inspect<T::AccountId>>::minimum_balance(asset_id).is_zero()
+    }
+
+    fn create(asset_id: Self::AssetId, admin: &T::AccountId) -> DispatchResult {
+        <Self as Create<T::AccountId>>::create(asset_id, admin.clone(), true, One::one())
+    }
+
+    fn destroy(asset_id: Self::AssetId) -> DispatchResult {
+        let witness = <Self as Destroy<T::AccountId>>::get_destroy_witness(&asset_id).ok_or(pallet_assets::Error::<T, I>::Unknown)?;
+        <Self as Destroy<T::AccountId>>::destroy(asset_id, witness, None).map(|_| ())
+    }
+
+    fn mint(asset_id: Self::AssetId, who: &T::AccountId, amount: T::Balance) -> DispatchResult {
+        <Self as Mutate<T::AccountId>>::mint_into(asset_id, who, amount)
+    }
+
+    fn burn(asset_id: Self::AssetId, who: &T::AccountId, amount: T::Balance) -> DispatchResult {
+        <Self as Mutate<T::AccountId>>::burn_from(asset_id, who, amount).map(|_| ())
+    }
+
+    fn balance(asset_id: Self::AssetId, who: &T::AccountId) -> T::Balance {
+        <Self as Inspect<T::AccountId>>::balance(asset_id, who)
+    }
+
+    fn total_issuance(asset_id: Self::AssetId) -> T::Balance {
+        <Self as Inspect<T::AccountId>>::total_issuance(asset_id)
+    }
+}
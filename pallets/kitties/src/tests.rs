@@ -1,10 +1,22 @@
 use super::*;
 
 use crate as kitties;
-use sp_core::H256;
-use frame_support::{parameter_types, assert_ok, assert_noop};
+use sp_core::{
+    H256,
+    offchain::{testing::TestOffchainExt, OffchainDbExt, OffchainWorkerExt},
+};
+use frame_support::{
+    parameter_types, assert_ok, assert_noop,
+    storage::{migration::put_storage_value, unhashed},
+    instances::{Instance1, Instance2},
+    traits::{tokens::nonfungibles::Inspect, Filter, PalletInfoAccess},
+    unsigned::ValidateUnsigned,
+    weights::{GetDispatchInfo, IdentityFee},
+    StorageHasher, PalletId,
+};
+use frame_system::EnsureRoot;
 use sp_runtime::{
-    traits::{BlakeTwo256, IdentityLookup}, testing::Header, testing::TestXt,
+    traits::{BlakeTwo256, Dispatchable, IdentityLookup, SignedExtension}, testing::Header, testing::TestXt,
 };
 
 type UncheckedExtrinsic = frame_system::mocking::MockUncheckedExtrinsic<Test>;
@@ -21,8 +33,13 @@ frame_support::construct_runtime!(
         // the three pallets included in the Test runtime
         System: frame_system::{Pallet, Call, Config, Storage, Event<T>},    // System pallet - always a requirement
         Balances: pallet_balances::{Pallet, Call, Storage, Config<T>, Event<T>},    // Balances pallet - used to deal with kitties' prices and exchanges
-        KittiesModule: kitties::{Pallet, Call, Storage, Event<T>, Config},          // the kitties pallet
+        TransactionPayment: pallet_transaction_payment::{Pallet, Storage},  // charges the fees of the signed transactions
+        KittiesModule: kitties::{Pallet, Call, Storage, Event<T>, Config<T>},          // the kitties pallet
+        OtherKitties: kitties::<Instance1>::{Pallet, Call, Storage, Event<T>, Config<T>},  // a second, independent kitty game
+        UniqueKitties: kitties::<Instance2>::{Pallet, Call, Storage, Event<T>, Config<T>},  // a kitty game that is stored in pallet_uniques
         Nft: orml_nft::{Pallet, Storage, Config<T>},
+        Uniques: pallet_uniques::{Pallet, Storage, Event<T>},
+        Assets: pallet_assets::{Pallet, Call, Storage, Event<T>},
     }
 );
 
@@ -44,10 +61,24 @@ parameter_types! {
     pub const SS58Prefix: u8 = 42;
 }
 
+// reject the paused calls of every kitty game, like the runtime does
+pub struct PausedCallsFilter;
+
+impl Filter<Call> for PausedCallsFilter {
+    fn filter(call: &Call) -> bool {
+        match call {
+            Call::KittiesModule(call) => !KittiesModule::is_call_paused(call),
+            Call::OtherKitties(call) => !OtherKitties::is_call_paused(call),
+            Call::UniqueKitties(call) => !UniqueKitties::is_call_paused(call),
+            _ => true,
+        }
+    }
+}
+
 // use the unit type "()" for most of the types
 // as it provides the default mocking behavior
 impl frame_system::Config for Test {
-    type BaseCallFilter = ();
+    type BaseCallFilter = PausedCallsFilter;
     type BlockWeights = ();
     type BlockLength = ();
     type DbWeight = ();
@@ -74,7 +105,7 @@ impl frame_system::Config for Test {
 // --------------------------------------
 // parameter types for the balances pallet
 parameter_types! {
-    pub const ExistentialDeposit: u64 = 1;
+    pub static ExistentialDeposit: u64 = 1;
 }
 
 impl pallet_balances::Config for Test {
@@ -89,6 +120,19 @@ impl pallet_balances::Config for Test {
     type ReserveIdentifier = ();
 }
 
+// --------------------------------------
+// parameter types for the transaction payment pallet
+parameter_types! {
+    pub const TransactionByteFee: u64 = 1;
+}
+
+impl pallet_transaction_payment::Config for Test {
+    type OnChargeTransaction = pallet_transaction_payment::CurrencyAdapter<Balances, ()>;
+    type TransactionByteFee = TransactionByteFee;
+    type WeightToFee = IdentityFee<u64>;
+    type FeeMultiplierUpdate = ();
+}
+
 // --------------------------------------
 // parameter types for the randomness collective pallet
 // create a static global variable that can be used by the unit tests
@@ -112,24 +156,160 @@ parameter_types! {
 impl orml_nft::Config for Test {
     type ClassId = u32;
     type TokenId = u32;
-    type ClassData = ();
+    type ClassData = KittyClassData;
     type TokenData = Kitty;
     type MaxClassMetadata = MaxClassMetadata;
     type MaxTokenMetadata = MaxTokenMetadata;
 }
 
+parameter_types! {
+    pub const ClassDeposit: u64 = 0;
+    pub const InstanceDeposit: u64 = 0;
+    pub const MetadataDepositBase: u64 = 0;
+    pub const AttributeDepositBase: u64 = 0;
+    pub const UniquesDepositPerByte: u64 = 0;
+    pub const StringLimit: u32 = 50;
+    pub const KeyLimit: u32 = 32;
+    pub const ValueLimit: u32 = 64;
+}
+
+impl pallet_uniques::Config for Test {
+    type Event = Event;
+    type ClassId = u32;
+    type InstanceId = u32;
+    type Currency = Balances;
+    type ForceOrigin = EnsureRoot<u64>;
+    type ClassDeposit = ClassDeposit;
+    type InstanceDeposit = InstanceDeposit;
+    type MetadataDepositBase = MetadataDepositBase;
+    type AttributeDepositBase = AttributeDepositBase;
+    type DepositPerByte = UniquesDepositPerByte;
+    type StringLimit = StringLimit;
+    type KeyLimit = KeyLimit;
+    type ValueLimit = ValueLimit;
+    type WeightInfo = ();
+}
+
+parameter_types! {
+    pub const AssetDeposit: u64 = 0;
+    pub const ApprovalDeposit: u64 = 0;
+    pub const MetadataDepositPerByte: u64 = 0;
+}
+
+impl pallet_assets::Config for Test {
+    type Event = Event;
+    type Balance = u64;
+    type AssetId = u32;
+    type Currency = Balances;
+    type ForceOrigin = EnsureRoot<u64>;
+    type AssetDeposit = AssetDeposit;
+    type MetadataDepositBase = MetadataDepositBase;
+    type MetadataDepositPerByte = MetadataDepositPerByte;
+    type ApprovalDeposit = ApprovalDeposit;
+    type StringLimit = StringLimit;
+    type Freezer = ();
+    type Extra = ();
+    type WeightInfo = ();
+}
+
 parameter_types! {
     pub const DefaultDifficulty: u32 = 10;
+    pub const BreedCooldown: u64 = 5;
+    pub const MaxNameLength: u32 = 8;
+    pub const MaxMetadataLength: u32 = 16;
+    pub const DepositPerByte: u64 = 1;
+    pub const MaxReasonLength: u32 = 16;
+    pub const MaxHistoryLength: u32 = 3;
+    pub const MaxBuyOrders: u32 = 2;
+    pub const MinOrderPrice: u64 = 10;
+    pub const MaxFloorBids: u32 = 2;
+    pub const LayawayDeposit: Perbill = Perbill::from_percent(20);
+    pub const LayawayPeriod: u64 = 10;
+    pub const LayawayForfeit: Perbill = Perbill::from_percent(50);
+    pub const KittiesPalletId: PalletId = PalletId(*b"py/kitty");
 }
 
 // --------------------------------------
 // parameter types for the kitties pallet
 impl Config for Test {
     type Event = Event;
+    type Nft = Nft;
+    type Randomness = MockRandom;
+    type Currency = Balances;
+    type CollectionOrigin = EnsureRoot<u64>;
+    type AdminOrigin = EnsureRoot<u64>;
+    type WeightInfo = ();
+    type PalletId = KittiesPalletId;
+    type Shares = Assets;
+    type DefaultDifficulty = DefaultDifficulty;
+    type BreedCooldown = BreedCooldown;
+    type MaxNameLength = MaxNameLength;
+    type MaxMetadataLength = MaxMetadataLength;
+    type DepositPerByte = DepositPerByte;
+    type MaxReasonLength = MaxReasonLength;
+    type MaxHistoryLength = MaxHistoryLength;
+    type MaxBuyOrders = MaxBuyOrders;
+    type MaxFloorBids = MaxFloorBids;
+    type MinOrderPrice = MinOrderPrice;
+    type LayawayDeposit = LayawayDeposit;
+    type LayawayPeriod = LayawayPeriod;
+    type LayawayForfeit = LayawayForfeit;
+}
+
+parameter_types! {
+    pub const OtherDifficulty: u32 = 2;
+    pub const OtherPalletId: PalletId = PalletId(*b"py/kitt1");
+    pub const UniqueKittiesPalletId: PalletId = PalletId(*b"py/kitt2");
+}
+
+impl Config<Instance1> for Test {
+    type Event = Event;
+    type Nft = Nft;
     type Randomness = MockRandom;
     type Currency = Balances;
+    type CollectionOrigin = EnsureRoot<u64>;
+    type AdminOrigin = EnsureRoot<u64>;
     type WeightInfo = ();
+    type PalletId = OtherPalletId;
+    type Shares = Assets;
+    type DefaultDifficulty = OtherDifficulty;
+    type BreedCooldown = BreedCooldown;
+    type MaxNameLength = MaxNameLength;
+    type MaxMetadataLength = MaxMetadataLength;
+    type DepositPerByte = DepositPerByte;
+    type MaxReasonLength = MaxReasonLength;
+    type MaxHistoryLength = MaxHistoryLength;
+    type MaxBuyOrders = MaxBuyOrders;
+    type MaxFloorBids = MaxFloorBids;
+    type MinOrderPrice = MinOrderPrice;
+    type LayawayDeposit = LayawayDeposit;
+    type LayawayPeriod = LayawayPeriod;
+    type LayawayForfeit = LayawayForfeit;
+}
+
+impl Config<Instance2> for Test {
+    type Event = Event;
+    type Nft = UniquesBackend<Test, Instance2>;
+    type Randomness = MockRandom;
+    type Currency = Balances;
+    type CollectionOrigin = EnsureRoot<u64>;
+    type AdminOrigin = EnsureRoot<u64>;
+    type WeightInfo = ();
+    type PalletId = UniqueKittiesPalletId;
+    type Shares = Assets;
     type DefaultDifficulty = DefaultDifficulty;
+    type BreedCooldown = BreedCooldown;
+    type MaxNameLength = MaxNameLength;
+    type MaxMetadataLength = MaxMetadataLength;
+    type DepositPerByte = DepositPerByte;
+    type MaxReasonLength = MaxReasonLength;
+    type MaxHistoryLength = MaxHistoryLength;
+    type MaxBuyOrders = MaxBuyOrders;
+    type MaxFloorBids = MaxFloorBids;
+    type MinOrderPrice = MinOrderPrice;
+    type LayawayDeposit = LayawayDeposit;
+    type LayawayPeriod = LayawayPeriod;
+    type LayawayForfeit = LayawayForfeit;
 }
 
 // construct the runtime for the unit tests
@@ -144,7 +324,11 @@ pub fn new_test_ext() -> sp_io::TestExternalities {
         balances: vec![(100, 80)],
     }.assimilate_storage(&mut t).unwrap();
 
-    <crate::GenesisConfig as GenesisBuild<Test>>::assimilate_storage(&crate::GenesisConfig::default(), &mut t).unwrap();
+    <crate::GenesisConfig<Test> as GenesisBuild<Test>>::assimilate_storage(&crate::GenesisConfig::default(), &mut t).unwrap();
+    // the second game starts without collections
+    <crate::GenesisConfig<Test, Instance1> as GenesisBuild<Test, Instance1>>::assimilate_storage(&crate::GenesisConfig { collections: Vec::new(), ..Default::default() }, &mut t).unwrap();
+    // the third game stores its kitties in pallet_uniques
+    <crate::GenesisConfig<Test, Instance2> as GenesisBuild<Test, Instance2>>::assimilate_storage(&crate::GenesisConfig::default(), &mut t).unwrap();
 
 
     let mut t: sp_io::TestExternalities = t.into();
@@ -156,52 +340,66 @@ pub fn new_test_ext() -> sp_io::TestExternalities {
     t
 }
 
+// the collection that is created at genesis
+const CLASS_ID: u32 = 0;
+
+// brute force a valid auto breed solution, like the offchain worker does
+fn find_solution(kitty_id_1: u32, kitty_id_2: u32, nonce: u32) -> u128 {
+    (0u128..).find(|solution| KittiesModule::validate_solution(CLASS_ID, kitty_id_1, kitty_id_2, nonce, *solution)).unwrap()
+}
+
 // standard unit test
 #[test]
 fn can_create() {
     // new_test_ext().execute_with will set up the environment for the test runtime
     new_test_ext().execute_with(|| {
         // Origin is created by the construct_runtime! macro
-        assert_ok!(KittiesModule::create(Origin::signed(100)));
+        assert_ok!(KittiesModule::create(Origin::signed(100), CLASS_ID));
 
-        let kitty = Kitty([59, 250, 138, 82, 209, 39, 141, 109, 163, 238, 183, 145, 235, 168, 18, 122]);
+        let kitty = Kitty::new([59, 250, 138, 82, 209, 39, 141, 109, 163, 238, 183, 145, 235, 168, 18, 122]);
 
-        assert_eq!(KittiesModule::kitties(&100, 0), Some(kitty.clone()));
-        assert_eq!(Nft::tokens(KittiesModule::class_id(), 0).unwrap().owner, 100);
+        assert_eq!(KittiesModule::kitties(&100, CLASS_ID, 0), Some(kitty.clone()));
+        assert_eq!(Nft::tokens(CLASS_ID, 0).unwrap().owner, 100);
 
-        System::assert_last_event(Event::KittiesModule(crate::Event::<Test>::KittyCreated(100, 0, kitty)));
+        System::assert_last_event(Event::KittiesModule(crate::Event::<Test>::KittyCreated(100, CLASS_ID, 0, kitty)));
     });
 }
 
 #[test]
 fn gender() {
-    assert_eq!(Kitty([0; 16]).gender(), KittyGender::Male);
-    assert_eq!(Kitty([1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]).gender(), KittyGender::Female);
+    assert_eq!(Kitty::new([0; 16]).gender(), KittyGender::Male);
+    assert_eq!(Kitty::new([1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]).gender(), KittyGender::Female);
 }
 
 #[test]
 fn can_breed() {
     new_test_ext().execute_with(|| {
-        assert_ok!(KittiesModule::create(Origin::signed(100)));
+        assert_ok!(KittiesModule::create(Origin::signed(100), CLASS_ID));
 
         // set the MockRandom to ensure the second kitty has a different gender
         MockRandom::set(H256::from([2; 32]));
 
-        assert_ok!(KittiesModule::create(Origin::signed(100)));
+        assert_ok!(KittiesModule::create(Origin::signed(100), CLASS_ID));
 
         // assert_noop will assert there is no state change caused by the function call and that breed() return the given error
-        assert_noop!(KittiesModule::breed(Origin::signed(100), 0, 11), Error::<Test>::InvalidKittyId);
-        assert_noop!(KittiesModule::breed(Origin::signed(100), 0, 0), Error::<Test>::SameGender);
-        assert_noop!(KittiesModule::breed(Origin::signed(101), 0, 1), Error::<Test>::InvalidKittyId);
+        assert_noop!(KittiesModule::breed(Origin::signed(100), CLASS_ID, 0, 11), Error::<Test>::InvalidKittyId);
+        assert_noop!(KittiesModule::breed(Origin::signed(100), CLASS_ID, 0, 0), Error::<Test>::SameGender);
+        assert_noop!(KittiesModule::breed(Origin::signed(101), CLASS_ID, 0, 1), Error::<Test>::InvalidKittyId);
+
+        assert_ok!(KittiesModule::breed(Origin::signed(100), CLASS_ID, 0, 1));
 
-        assert_ok!(KittiesModule::breed(Origin::signed(100), 0, 1));
+        let kitty = Kitty { dna: [187, 250, 235, 118, 211, 247, 237, 253, 187, 239, 191, 185, 239, 171, 211, 122], generation: 1 };
 
-        let kitty = Kitty([187, 250, 235, 118, 211, 247, 237, 253, 187, 239, 191, 185, 239, 171, 211, 122]);
+        assert_eq!(KittiesModule::kitties(&100, CLASS_ID, 2), Some(kitty.clone()));
+        assert_eq!(Nft::tokens(CLASS_ID, 2).unwrap().owner, 100);
 
-        assert_eq!(KittiesModule::kitties(&100, 2), Some(kitty.clone()));
-        assert_eq!(Nft::tokens(KittiesModule::class_id(), 2).unwrap().owner, 100);
+        System::assert_last_event(Event::KittiesModule(crate::Event::<Test>::KittyBred(100u64, CLASS_ID, 2u32, kitty)));
 
-        System::assert_last_event(Event::KittiesModule(crate::Event::<Test>::KittyBred(100u64, 2u32, kitty)));
+        // the parents have to wait for the cooldown before they can breed again
+        assert_noop!(KittiesModule::breed(Origin::signed(100), CLASS_ID, 0, 1), Error::<Test>::KittyOnCooldown);
+
+        System::set_block_number(1 + BreedCooldown::get());
+        assert_ok!(KittiesModule::breed(Origin::signed(100), CLASS_ID, 0, 1));
     });
 }
 
@@ -209,45 +407,45 @@ fn can_breed() {
 fn can_transfer() {
     new_test_ext().execute_with(|| {
         // create a kitty
-        assert_ok!(KittiesModule::create(Origin::signed(100)));
+        assert_ok!(KittiesModule::create(Origin::signed(100), CLASS_ID));
         // set a price for the newly created kitty
-        assert_ok!(KittiesModule::set_price(Origin::signed(100), 0, Some(20)));
+        assert_ok!(KittiesModule::set_price(Origin::signed(100), CLASS_ID, 0, Some(20)));
 
         // no one other than the owner should be able to transfer that kitty
-        assert_noop!(KittiesModule::transfer(Origin::signed(101), 102, 0), orml_nft::Error::<Test>::NoPermission);
+        assert_noop!(KittiesModule::transfer(Origin::signed(101), 102, CLASS_ID, 0), orml_nft::Error::<Test>::NoPermission);
 
         // transfer the kitty to a new owner
-        assert_ok!(KittiesModule::transfer(Origin::signed(100), 103, 0));
+        assert_ok!(KittiesModule::transfer(Origin::signed(100), 103, CLASS_ID, 0));
 
         // now the previous owner can no longer transfer that kitty
-        assert_noop!(KittiesModule::transfer(Origin::signed(100), 103, 0), orml_nft::Error::<Test>::NoPermission);
+        assert_noop!(KittiesModule::transfer(Origin::signed(100), 103, CLASS_ID, 0), orml_nft::Error::<Test>::NoPermission);
         // after the transfer the price of the kitty should be reset
-        assert_eq!(KittyPrices::<Test>::contains_key(0), false);
+        assert_eq!(KittyPrices::<Test>::contains_key(CLASS_ID, 0), false);
 
         // account 103 should now have the kitty with id 0
-        assert_eq!(Nft::tokens(KittiesModule::class_id(), 0).unwrap().owner, 103);
+        assert_eq!(Nft::tokens(CLASS_ID, 0).unwrap().owner, 103);
 
         // the last event on the blockchain should be kitty transfer
-        System::assert_last_event(Event::KittiesModule(crate::Event::<Test>::KittyTransferred(100, 103, 0)));
+        System::assert_last_event(Event::KittiesModule(crate::Event::<Test>::KittyTransferred(100, 103, CLASS_ID, 0)));
     });
 }
 
 #[test]
 fn handle_self_transfer() {
     new_test_ext().execute_with(|| {
-        assert_ok!(KittiesModule::create(Origin::signed(100)));
+        assert_ok!(KittiesModule::create(Origin::signed(100), CLASS_ID));
 
         // reset the events state to ensure that no events were transmitted
         // after the creation of the kitty
         System::reset_events();
 
         // user should not be able to transfer kitties they don't own
-        assert_noop!(KittiesModule::transfer(Origin::signed(100), 100, 10), orml_nft::Error::<Test>::TokenNotFound);
+        assert_noop!(KittiesModule::transfer(Origin::signed(100), 100, CLASS_ID, 10), orml_nft::Error::<Test>::TokenNotFound);
 
         // tranferring a kitty you own to yourself should do nothing
-        assert_ok!(KittiesModule::transfer(Origin::signed(100), 100, 0));
+        assert_ok!(KittiesModule::transfer(Origin::signed(100), 100, CLASS_ID, 0));
 
-        assert_eq!(Nft::tokens(KittiesModule::class_id(), 0).unwrap().owner, 100);
+        assert_eq!(Nft::tokens(CLASS_ID, 0).unwrap().owner, 100);
 
         // there should be no event after the system event reset, because no transfer
         // should have been executed
@@ -260,31 +458,31 @@ fn can_set_price() {
     new_test_ext().execute_with(|| {
         // create a kitty for account with id 100
         // the newly created kitty will have id 0
-        assert_ok!(KittiesModule::create(Origin::signed(100)));
+        assert_ok!(KittiesModule::create(Origin::signed(100), CLASS_ID));
 
         // account 101 should not be able to set the price for 100's kitty
-        assert_noop!(KittiesModule::set_price(Origin::signed(101), 0, Some(15)), Error::<Test>::NotOwner);
+        assert_noop!(KittiesModule::set_price(Origin::signed(101), CLASS_ID, 0, Some(15)), Error::<Test>::NotOwner);
 
         // account 100 should be able to set the price of its own kitty
-        assert_ok!(KittiesModule::set_price(Origin::signed(100), 0, Some(20)));
+        assert_ok!(KittiesModule::set_price(Origin::signed(100), CLASS_ID, 0, Some(20)));
 
         // a KittyPriceUpdated event should have been submitted after the price change
-        System::assert_last_event(Event::KittiesModule(crate::Event::KittyPriceUpdated(100, 0, Some(20))));
+        System::assert_last_event(Event::KittiesModule(crate::Event::KittyPriceUpdated(100, CLASS_ID, 0, Some(20))));
 
         // kitty 0's price should be correctly set now
-        assert_eq!(KittiesModule::kitty_prices(0), Some(20));
+        assert_eq!(KittiesModule::kitty_prices(CLASS_ID, 0), Some(20));
 
         // setting a kitty not for sale is the same as setting its price to None
-        assert_ok!(KittiesModule::set_price(Origin::signed(100), 0, None));
+        assert_ok!(KittiesModule::set_price(Origin::signed(100), CLASS_ID, 0, None));
 
         // now kitty 0 should no longer be for sale
-        assert_eq!(KittiesModule::kitty_prices(0), None);
+        assert_eq!(KittiesModule::kitty_prices(CLASS_ID, 0), None);
         // and KittyPrices should no longer have kitty 0's id
         //  (as the kitty is no longer for sale and its price was set to None)
-        assert_eq!(KittyPrices::<Test>::contains_key(0), false);
+        assert_eq!(KittyPrices::<Test>::contains_key(CLASS_ID, 0), false);
 
         // a KittyPriceUpdated event should have been submitted after removing kitty 0's price
-        System::assert_last_event(Event::KittiesModule(crate::Event::KittyPriceUpdated(100, 0, None)));
+        System::assert_last_event(Event::KittiesModule(crate::Event::KittyPriceUpdated(100, CLASS_ID, 0, None)));
     });
 }
 
@@ -292,32 +490,32 @@ fn can_set_price() {
 fn can_buy() {
     new_test_ext().execute_with(|| {
         // create a new kitty
-        assert_ok!(KittiesModule::create(Origin::signed(1000)));
+        assert_ok!(KittiesModule::create(Origin::signed(1000), CLASS_ID));
 
         // buying from account 1000 a kitty with id 1 for 200 should throw a NotForSale error
         //  (because a kitty with that id does not exist)
-        assert_noop!(KittiesModule::buy(Origin::signed(100), 1000, 1, 200), Error::<Test>::NotForSale);
+        assert_noop!(KittiesModule::buy(Origin::signed(100), 1000, CLASS_ID, 1, 200), Error::<Test>::NotForSale);
         // it should not be possible to buy a kitty from oneself
-        assert_noop!(KittiesModule::buy(Origin::signed(1000), 1000, 0, 200), Error::<Test>::BuyFromSelf);
+        assert_noop!(KittiesModule::buy(Origin::signed(1000), 1000, CLASS_ID, 0, 200), Error::<Test>::BuyFromSelf);
         // kitty 0 is not yet for sale, so it cannot be bought
-        assert_noop!(KittiesModule::buy(Origin::signed(100), 1000, 0, 200), Error::<Test>::NotForSale);
+        assert_noop!(KittiesModule::buy(Origin::signed(100), 1000, CLASS_ID, 0, 200), Error::<Test>::NotForSale);
 
         // list the kitty for sale by setting its price
-        assert_ok!(KittiesModule::set_price(Origin::signed(1000), 0, Some(200)));
+        assert_ok!(KittiesModule::set_price(Origin::signed(1000), CLASS_ID, 0, Some(200)));
 
         // it should not be possible to buy the kitty for a lower price
-        assert_noop!(KittiesModule::buy(Origin::signed(100), 1000, 0, 199), Error::<Test>::PriceTooLow);
+        assert_noop!(KittiesModule::buy(Origin::signed(100), 1000, CLASS_ID, 0, 199), Error::<Test>::PriceTooLow);
         // (even if the user has the money)
-        assert_noop!(KittiesModule::buy(Origin::signed(100), 1000, 0, 70), Error::<Test>::PriceTooLow);
+        assert_noop!(KittiesModule::buy(Origin::signed(100), 1000, CLASS_ID, 0, 70), Error::<Test>::PriceTooLow);
 
         // a user without enough money should not be able to buy the kitty either
-        assert_noop!(KittiesModule::buy(Origin::signed(100), 1000, 0, 200), pallet_balances::Error::<Test, _>::InsufficientBalance);
+        assert_noop!(KittiesModule::buy(Origin::signed(100), 1000, CLASS_ID, 0, 200), pallet_balances::Error::<Test, _>::InsufficientBalance);
 
         // owner should be able to set a new price
-        assert_ok!(KittiesModule::set_price(Origin::signed(1000), 0, Some(20)));
+        assert_ok!(KittiesModule::set_price(Origin::signed(1000), CLASS_ID, 0, Some(20)));
 
         // now the buyer should have enough money
-        assert_ok!(KittiesModule::buy(Origin::signed(100), 1000, 0, 80));
+        assert_ok!(KittiesModule::buy(Origin::signed(100), 1000, CLASS_ID, 0, 80));
 
         // now the buyer should have payed only the original cost of the kitty
         assert_eq!(Balances::free_balance(100), 80 - 20);
@@ -325,11 +523,1600 @@ fn can_buy() {
         assert_eq!(Balances::free_balance(1000), 20);
 
         // now after the transfer the kitty should no longer have a price
-        assert_eq!(KittyPrices::<Test>::contains_key(0), false);
+        assert_eq!(KittyPrices::<Test>::contains_key(CLASS_ID, 0), false);
         // and the owner of the kitty with id 0 should be the buyer
-        assert_eq!(Nft::tokens(KittiesModule::class_id(), 0).unwrap().owner, 100);
+        assert_eq!(Nft::tokens(CLASS_ID, 0).unwrap().owner, 100);
 
         // a KittySold event should have been submitted after selling the kitty
-        System::assert_last_event(Event::KittiesModule(crate::Event::KittySold(1000, 100, 0, 20)));
+        System::assert_last_event(Event::KittiesModule(crate::Event::KittySold(1000, 100, CLASS_ID, 0, 20)));
+    });
+}
+
+#[test]
+fn can_set_auto_breed_consent() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(KittiesModule::create(Origin::signed(100), CLASS_ID));
+        assert_ok!(KittiesModule::create(Origin::signed(100), CLASS_ID));
+        assert_ok!(KittiesModule::create(Origin::signed(101), CLASS_ID));
+
+        // only the owner can opt a kitty in to auto breeding
+        assert_noop!(KittiesModule::set_auto_breed_consent(Origin::signed(101), CLASS_ID, 0, true), Error::<Test>::NotOwner);
+
+        assert_ok!(KittiesModule::set_auto_breed_consent(Origin::signed(100), CLASS_ID, 0, true));
+        System::assert_last_event(Event::KittiesModule(crate::Event::AutoBreedConsentUpdated(100, CLASS_ID, 0, true)));
+
+        assert_ok!(KittiesModule::set_auto_breed_consent(Origin::signed(100), CLASS_ID, 1, true));
+        assert_ok!(KittiesModule::set_auto_breed_consent(Origin::signed(101), CLASS_ID, 2, true));
+
+        // opting in twice should not add the kitty to the index twice
+        assert_ok!(KittiesModule::set_auto_breed_consent(Origin::signed(100), CLASS_ID, 0, true));
+        assert_eq!(KittiesModule::auto_breed_candidate_count(CLASS_ID), 3);
+
+        // opting out should move the last candidate into the freed slot
+        assert_ok!(KittiesModule::set_auto_breed_consent(Origin::signed(100), CLASS_ID, 0, false));
+        System::assert_last_event(Event::KittiesModule(crate::Event::AutoBreedConsentUpdated(100, CLASS_ID, 0, false)));
+
+        assert_eq!(KittiesModule::auto_breed_candidate_count(CLASS_ID), 2);
+        assert_eq!(KittiesModule::auto_breed_consent(CLASS_ID, 0), None);
+        assert_eq!(KittiesModule::auto_breed_consent(CLASS_ID, 2), Some(0));
+        assert_eq!(AutoBreedCandidates::<Test>::get(CLASS_ID, 0), Some(2));
+        assert_eq!(AutoBreedCandidates::<Test>::get(CLASS_ID, 1), Some(1));
+        assert_eq!(AutoBreedCandidates::<Test>::get(CLASS_ID, 2), None);
+
+        // transferring a kitty should revoke the consent of the previous owner
+        assert_ok!(KittiesModule::transfer(Origin::signed(101), 100, CLASS_ID, 2));
+        assert_eq!(KittiesModule::auto_breed_consent(CLASS_ID, 2), None);
+        assert_eq!(KittiesModule::auto_breed_candidate_count(CLASS_ID), 1);
+    });
+}
+
+#[test]
+fn auto_breed_requires_consent() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(KittiesModule::create(Origin::signed(100), CLASS_ID));
+
+        // ensure the second kitty has a different gender
+        MockRandom::set(H256::from([2; 32]));
+
+        assert_ok!(KittiesModule::create(Origin::signed(100), CLASS_ID));
+        assert_ok!(KittiesModule::transfer(Origin::signed(100), 101, CLASS_ID, 1));
+
+        // auto breed can only be submitted as an unsigned transaction
+        assert_noop!(KittiesModule::auto_breed(Origin::signed(100), CLASS_ID, 0, 1, 0, 0), DispatchError::BadOrigin);
+
+        // none of the owners opted in
+        assert_noop!(KittiesModule::auto_breed(Origin::none(), CLASS_ID, 0, 1, 0, 0), Error::<Test>::NoAutoBreedConsent);
+
+        // only one of the owners opted in
+        assert_ok!(KittiesModule::set_auto_breed_consent(Origin::signed(100), CLASS_ID, 0, true));
+        assert_noop!(KittiesModule::auto_breed(Origin::none(), CLASS_ID, 0, 1, 0, 0), Error::<Test>::NoAutoBreedConsent);
+
+        assert_ok!(KittiesModule::set_auto_breed_consent(Origin::signed(101), CLASS_ID, 1, true));
+
+        // the nonce has to match the current auto breed nonce
+        assert_noop!(KittiesModule::auto_breed(Origin::none(), CLASS_ID, 0, 1, 1, 0), Error::<Test>::InvalidNonce);
+
+        assert_ok!(KittiesModule::auto_breed(Origin::none(), CLASS_ID, 0, 1, 0, 0));
+
+        // the kitten belongs to the owner of the first kitty
+        assert_eq!(Nft::tokens(CLASS_ID, 2).unwrap().owner, 100);
+    });
+}
+
+#[test]
+fn auto_breed_priority_grows_with_the_difficulty() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(KittiesModule::create(Origin::signed(100), CLASS_ID));
+        MockRandom::set(H256::from([2; 32]));
+        assert_ok!(KittiesModule::create(Origin::signed(100), CLASS_ID));
+
+        assert_ok!(KittiesModule::set_auto_breed_consent(Origin::signed(100), CLASS_ID, 0, true));
+        assert_ok!(KittiesModule::set_auto_breed_consent(Origin::signed(100), CLASS_ID, 1, true));
+
+        // a solution for the default difficulty is also a solution for a difficulty of 1
+        let call = crate::Call::auto_breed(CLASS_ID, 0, 1, 0, find_solution(0, 1, 0));
+        let priority = || KittiesModule::validate_unsigned(TransactionSource::External, &call).unwrap().priority;
+
+        assert_ok!(KittiesModule::set_parameters(Origin::root(), KittyParameters { difficulty: 1, ..KittiesModule::parameters() }));
+        let easy = priority();
+        assert_eq!(easy, UNSIGNED_TXS_PRIORITY + 1);
+
+        assert_ok!(KittiesModule::set_parameters(Origin::root(), KittyParameters { difficulty: DefaultDifficulty::get(), ..KittiesModule::parameters() }));
+        assert!(priority() > easy);
+    });
+}
+
+#[test]
+fn auto_breed_nonce_is_consumed_on_dispatch() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(KittiesModule::create(Origin::signed(100), CLASS_ID));
+        MockRandom::set(H256::from([2; 32]));
+        assert_ok!(KittiesModule::create(Origin::signed(100), CLASS_ID));
+
+        assert_ok!(KittiesModule::set_auto_breed_consent(Origin::signed(100), CLASS_ID, 0, true));
+        assert_ok!(KittiesModule::set_auto_breed_consent(Origin::signed(100), CLASS_ID, 1, true));
+
+        let call = crate::Call::auto_breed(CLASS_ID, 0, 1, 0, find_solution(0, 1, 0));
+
+        // an invalid solution should be rejected
+        let bad_solution = (0u128..).find(|solution| !KittiesModule::validate_solution(CLASS_ID, 0, 1, 0, *solution)).unwrap();
+        assert_eq!(
+            KittiesModule::validate_unsigned(TransactionSource::External, &crate::Call::auto_breed(CLASS_ID, 0, 1, 0, bad_solution)),
+            Err(InvalidTransaction::BadProof.into()),
+        );
+
+        // the pool can validate the same transaction any number of times
+        let valid = KittiesModule::validate_unsigned(TransactionSource::External, &call).unwrap();
+        assert_eq!(KittiesModule::validate_unsigned(TransactionSource::InBlock, &call), Ok(valid.clone()));
+
+        // validation should not change the state
+        assert_eq!(KittiesModule::auto_breed_nonce(), 0);
+
+        assert_eq!(valid.priority, UNSIGNED_TXS_PRIORITY + DefaultDifficulty::get() as u64);
+        assert_eq!(valid.provides, vec![("KittiesModule", 0u32).encode()]);
+        assert!(valid.requires.is_empty());
+
+        // import the transaction in a block
+        assert_ok!(KittiesModule::auto_breed(Origin::none(), CLASS_ID, 0, 1, 0, find_solution(0, 1, 0)));
+        assert_eq!(KittiesModule::auto_breed_nonce(), 1);
+
+        // the solution can not be included again
+        assert_eq!(KittiesModule::validate_unsigned(TransactionSource::External, &call), Err(InvalidTransaction::Stale.into()));
+        assert_noop!(KittiesModule::auto_breed(Origin::none(), CLASS_ID, 0, 1, 0, find_solution(0, 1, 0)), Error::<Test>::InvalidNonce);
+
+        // a solution for a future nonce has to wait for the current nonce to be consumed
+        System::set_block_number(1 + BreedCooldown::get());
+        let future_call = crate::Call::auto_breed(CLASS_ID, 0, 1, 2, find_solution(0, 1, 2));
+        let valid = KittiesModule::validate_unsigned(TransactionSource::External, &future_call).unwrap();
+        assert_eq!(valid.provides, vec![("KittiesModule", 2u32).encode()]);
+        assert_eq!(valid.requires, vec![("KittiesModule", 1u32).encode()]);
+    });
+}
+
+#[test]
+fn auto_breed_validation_rejects_failing_calls() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(KittiesModule::create(Origin::signed(100), CLASS_ID));
+        MockRandom::set(H256::from([2; 32]));
+        assert_ok!(KittiesModule::create(Origin::signed(100), CLASS_ID));
+        assert_ok!(KittiesModule::create(Origin::signed(100), CLASS_ID));
+
+        let validate = |kitty_id_1, kitty_id_2, nonce| {
+            let call = crate::Call::auto_breed(CLASS_ID, kitty_id_1, kitty_id_2, nonce, find_solution(kitty_id_1, kitty_id_2, nonce));
+            KittiesModule::validate_unsigned(TransactionSource::External, &call).map(|_| ())
+        };
+        let custom = |error: AutoBreedValidityError| -> Result<(), TransactionValidityError> {
+            Err(InvalidTransaction::from(error).into())
+        };
+
+        // the kitty with id 10 does not exist
+        assert_eq!(validate(0, 10, 0), custom(AutoBreedValidityError::InvalidKittyId));
+        // kitties 1 and 2 have the same gender
+        assert_eq!(validate(1, 2, 0), custom(AutoBreedValidityError::SameGender));
+        // the owner did not opt in
+        assert_eq!(validate(0, 1, 0), custom(AutoBreedValidityError::NoConsent));
+
+        assert_ok!(KittiesModule::set_auto_breed_consent(Origin::signed(100), CLASS_ID, 0, true));
+        assert_ok!(KittiesModule::set_auto_breed_consent(Origin::signed(100), CLASS_ID, 1, true));
+        assert_ok!(validate(0, 1, 0));
+
+        // the parents of a kitten are on a cooldown
+        assert_ok!(KittiesModule::breed(Origin::signed(100), CLASS_ID, 0, 1));
+        assert_eq!(validate(0, 1, 0), custom(AutoBreedValidityError::OnCooldown));
+
+        System::set_block_number(1 + BreedCooldown::get());
+        assert_ok!(validate(0, 1, 0));
+    });
+}
+
+#[test]
+fn auto_breed_pre_dispatch_drops_stale_solutions() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(KittiesModule::create(Origin::signed(100), CLASS_ID));
+        MockRandom::set(H256::from([2; 32]));
+        assert_ok!(KittiesModule::create(Origin::signed(100), CLASS_ID));
+
+        assert_ok!(KittiesModule::set_auto_breed_consent(Origin::signed(100), CLASS_ID, 0, true));
+        assert_ok!(KittiesModule::set_auto_breed_consent(Origin::signed(100), CLASS_ID, 1, true));
+
+        let call = crate::Call::auto_breed(CLASS_ID, 0, 1, 0, find_solution(0, 1, 0));
+        let future_call = crate::Call::auto_breed(CLASS_ID, 0, 1, 1, find_solution(0, 1, 1));
+
+        assert_ok!(KittiesModule::pre_dispatch(&call));
+        // a future solution can wait in the pool, but it can not be included yet
+        assert_eq!(KittiesModule::pre_dispatch(&future_call), Err(InvalidTransaction::Future.into()));
+
+        // the owner revoked the consent while the transaction was in the pool
+        assert_ok!(KittiesModule::set_auto_breed_consent(Origin::signed(100), CLASS_ID, 1, false));
+        assert_eq!(
+            KittiesModule::pre_dispatch(&call),
+            Err(InvalidTransaction::from(AutoBreedValidityError::NoConsent).into()),
+        );
+
+        // the owner bred the kitties while the transaction was in the pool
+        assert_ok!(KittiesModule::set_auto_breed_consent(Origin::signed(100), CLASS_ID, 1, true));
+        assert_ok!(KittiesModule::breed(Origin::signed(100), CLASS_ID, 0, 1));
+        assert_eq!(KittiesModule::last_breed_block(CLASS_ID, 0), Some(1));
+        assert_eq!(
+            KittiesModule::pre_dispatch(&call),
+            Err(InvalidTransaction::from(AutoBreedValidityError::OnCooldown).into()),
+        );
+        assert_noop!(KittiesModule::auto_breed(Origin::none(), CLASS_ID, 0, 1, 0, find_solution(0, 1, 0)), Error::<Test>::KittyOnCooldown);
+
+        // the cooldown is counted from the last breed, so a shorter cooldown applies right away
+        assert_ok!(KittiesModule::set_parameters(Origin::root(), KittyParameters { breed_cooldown: 0, ..KittiesModule::parameters() }));
+        assert_ok!(KittiesModule::pre_dispatch(&call));
+    });
+}
+
+#[test]
+fn miner_config_is_read_from_local_storage() {
+    let (offchain, _state) = TestOffchainExt::new();
+    let mut t = new_test_ext();
+    t.register_extension(OffchainDbExt::new(offchain.clone()));
+    t.register_extension(OffchainWorkerExt::new(offchain));
+
+    t.execute_with(|| {
+        // nothing was set by the node operator yet
+        assert_eq!(MinerConfig::load(), MinerConfig::default());
+
+        sp_io::offchain::local_storage_set(StorageKind::PERSISTENT, MINER_ENABLED_KEY, &false.encode());
+        sp_io::offchain::local_storage_set(StorageKind::PERSISTENT, MINER_MAX_ITERATIONS_KEY, &1000u32.encode());
+        // an interval of 0 blocks is ignored
+        sp_io::offchain::local_storage_set(StorageKind::PERSISTENT, MINER_INTERVAL_KEY, &0u32.encode());
+
+        assert_eq!(MinerConfig::load(), MinerConfig { enabled: false, max_iterations: 1000, interval: 1 });
+
+        sp_io::offchain::local_storage_set(StorageKind::PERSISTENT, MINER_INTERVAL_KEY, &10u32.encode());
+        assert_eq!(MinerConfig::load().interval, 10);
+    });
+}
+
+#[test]
+fn runtime_api_queries() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(KittiesModule::create(Origin::signed(100), CLASS_ID));
+        assert_ok!(KittiesModule::create(Origin::signed(101), CLASS_ID));
+        assert_ok!(KittiesModule::create(Origin::signed(100), CLASS_ID));
+
+        assert_eq!(KittiesModule::owner_of(CLASS_ID, 1), Some(101));
+        assert_eq!(KittiesModule::owner_of(CLASS_ID, 3), None);
+        assert_eq!(KittiesModule::kitty(CLASS_ID, 1), Nft::tokens(CLASS_ID, 1).map(|token| token.data));
+
+        // the owner enumeration only returns the kitties of the owner
+        let page = KittiesModule::kitties_of(&100, CLASS_ID, None, 10);
+        assert_eq!(page.kitties.iter().map(|(kitty_id, _)| *kitty_id).collect::<Vec<_>>(), vec![0, 2]);
+        assert_eq!(page.next, None);
+
+        assert_ok!(KittiesModule::set_price(Origin::signed(100), CLASS_ID, 2, Some(30)));
+        assert_eq!(KittiesModule::listings(CLASS_ID, 0, 10), vec![(2, 30)]);
+        assert_eq!(KittiesModule::listings(CLASS_ID, 1, 10), vec![]);
+    });
+}
+
+#[test]
+fn owner_enumeration_is_stable_while_minting() {
+    new_test_ext().execute_with(|| {
+        for _ in 0..5 {
+            assert_ok!(KittiesModule::create(Origin::signed(100), CLASS_ID));
+        }
+        // kitty 5 belongs to someone else
+        assert_ok!(KittiesModule::create(Origin::signed(101), CLASS_ID));
+
+        let page_ids = |page: &KittiesPage<u32>| page.kitties.iter().map(|(kitty_id, _)| *kitty_id).collect::<Vec<_>>();
+
+        let page = KittiesModule::kitties_of(&100, CLASS_ID, None, 2);
+        assert_eq!(page_ids(&page), vec![0, 1]);
+        assert_eq!(page.next, Some(1));
+
+        // new kitties minted between two requests are added after the existing ones
+        assert_ok!(KittiesModule::create(Origin::signed(100), CLASS_ID));
+
+        let page = KittiesModule::kitties_of(&100, CLASS_ID, page.next, 2);
+        assert_eq!(page_ids(&page), vec![2, 3]);
+        assert_eq!(page.next, Some(3));
+
+        let page = KittiesModule::kitties_of(&100, CLASS_ID, page.next, 2);
+        assert_eq!(page_ids(&page), vec![4, 6]);
+        assert_eq!(page.next, None);
+
+        // transferred kitties are no longer enumerated
+        assert_ok!(KittiesModule::transfer(Origin::signed(100), 101, CLASS_ID, 3));
+        let page = KittiesModule::kitties_of(&100, CLASS_ID, Some(1), 10);
+        assert_eq!(page_ids(&page), vec![2, 4, 6]);
+        assert_eq!(page.next, None);
+    });
+}
+
+#[test]
+fn listings_are_sorted_by_price() {
+    new_test_ext().execute_with(|| {
+        for _ in 0..4 {
+            assert_ok!(KittiesModule::create(Origin::signed(101), CLASS_ID));
+        }
+
+        assert_eq!(KittiesModule::floor_price(CLASS_ID), None);
+
+        assert_ok!(KittiesModule::set_price(Origin::signed(101), CLASS_ID, 0, Some(30)));
+        assert_ok!(KittiesModule::set_price(Origin::signed(101), CLASS_ID, 1, Some(10)));
+        assert_ok!(KittiesModule::set_price(Origin::signed(101), CLASS_ID, 2, Some(20)));
+
+        assert_eq!(KittiesModule::listings(CLASS_ID, 0, 10), vec![(1, 10), (2, 20), (0, 30)]);
+        assert_eq!(KittiesModule::listings(CLASS_ID, 1, 1), vec![(2, 20)]);
+        assert_eq!(KittiesModule::floor_price(CLASS_ID), Some(10));
+
+        // changing the price of a listed kitty moves it in the index, kitties with the same
+        // price are sorted by their id
+        assert_ok!(KittiesModule::set_price(Origin::signed(101), CLASS_ID, 3, Some(20)));
+        assert_ok!(KittiesModule::set_price(Origin::signed(101), CLASS_ID, 0, Some(5)));
+        assert_eq!(KittiesModule::listings(CLASS_ID, 0, 10), vec![(0, 5), (1, 10), (2, 20), (3, 20)]);
+        assert_eq!(KittiesModule::floor_price(CLASS_ID), Some(5));
+
+        // delisting, selling and transferring a kitty removes it from the index
+        assert_ok!(KittiesModule::set_price(Origin::signed(101), CLASS_ID, 0, None));
+        assert_ok!(KittiesModule::buy(Origin::signed(100), 101, CLASS_ID, 1, 10));
+        assert_ok!(KittiesModule::transfer(Origin::signed(101), 100, CLASS_ID, 2));
+        assert_ok!(KittiesModule::transfer(Origin::signed(101), 100, CLASS_ID, 3));
+
+        assert_eq!(KittiesModule::listings(CLASS_ID, 0, 10), vec![]);
+        assert_eq!(KittiesModule::floor_price(CLASS_ID), None);
+    });
+}
+
+#[test]
+fn can_set_name_and_metadata() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(KittiesModule::create(Origin::signed(100), CLASS_ID));
+
+        assert_noop!(KittiesModule::set_name(Origin::signed(101), CLASS_ID, 0, b"Tom".to_vec()), Error::<Test>::NotOwner);
+        assert_noop!(KittiesModule::set_name(Origin::signed(100), CLASS_ID, 0, b"Tom the cat".to_vec()), Error::<Test>::NameTooLong);
+        assert_noop!(KittiesModule::set_name(Origin::signed(100), CLASS_ID, 0, vec![0xff, 0xfe]), Error::<Test>::InvalidName);
+        assert_noop!(KittiesModule::set_metadata(Origin::signed(100), CLASS_ID, 0, vec![0; 17]), Error::<Test>::MetadataTooLong);
+
+        assert_ok!(KittiesModule::set_name(Origin::signed(100), CLASS_ID, 0, b"Tom".to_vec()));
+        System::assert_last_event(Event::KittiesModule(crate::Event::KittyNameUpdated(100, CLASS_ID, 0, b"Tom".to_vec())));
+        assert_ok!(KittiesModule::set_metadata(Origin::signed(100), CLASS_ID, 0, vec![1; 10]));
+        System::assert_last_event(Event::KittiesModule(crate::Event::KittyMetadataUpdated(100, CLASS_ID, 0)));
+
+        assert_eq!(KittiesModule::kitty_name(CLASS_ID, 0), Some(b"Tom".to_vec()));
+        assert_eq!(KittiesModule::kitty_metadata(CLASS_ID, 0), Some(vec![1; 10]));
+
+        // 1 unit is reserved for each byte
+        assert_eq!(Balances::reserved_balance(100), 13);
+        assert_eq!(KittiesModule::kitty_deposit(CLASS_ID, 0), Some((100, 13)));
+
+        // a shorter name refunds the difference
+        assert_ok!(KittiesModule::set_name(Origin::signed(100), CLASS_ID, 0, b"T".to_vec()));
+        assert_eq!(Balances::reserved_balance(100), 11);
+
+        // clearing the metadata refunds its deposit
+        assert_ok!(KittiesModule::set_metadata(Origin::signed(100), CLASS_ID, 0, Vec::new()));
+        assert_eq!(KittiesModule::kitty_metadata(CLASS_ID, 0), None);
+        assert_eq!(Balances::reserved_balance(100), 1);
+
+        // the name can not be set without enough free balance for the deposit
+        assert_ok!(KittiesModule::create(Origin::signed(101), CLASS_ID));
+        assert_noop!(KittiesModule::set_name(Origin::signed(101), CLASS_ID, 1, b"Jerry".to_vec()), pallet_balances::Error::<Test, _>::InsufficientBalance);
+    });
+}
+
+#[test]
+fn name_moves_with_the_kitty() {
+    new_test_ext().execute_with(|| {
+        let _ = Balances::deposit_creating(&101, 50);
+
+        assert_ok!(KittiesModule::create(Origin::signed(100), CLASS_ID));
+        assert_ok!(KittiesModule::set_name(Origin::signed(100), CLASS_ID, 0, b"Tom".to_vec()));
+
+        assert_ok!(KittiesModule::transfer(Origin::signed(100), 101, CLASS_ID, 0));
+
+        // the name stays with the kitty, and the deposit is reserved from the new owner
+        assert_eq!(KittiesModule::kitty_name(CLASS_ID, 0), Some(b"Tom".to_vec()));
+        assert_eq!(Balances::reserved_balance(100), 0);
+        assert_eq!(Balances::reserved_balance(101), 3);
+        assert_eq!(KittiesModule::kitty_deposit(CLASS_ID, 0), Some((101, 3)));
+
+        // the new owner only pays the difference when renaming the kitty
+        assert_ok!(KittiesModule::set_name(Origin::signed(101), CLASS_ID, 0, b"Jerry".to_vec()));
+        assert_eq!(Balances::reserved_balance(101), 5);
+        assert_eq!(KittiesModule::kitty_deposit(CLASS_ID, 0), Some((101, 5)));
+
+        // selling the kitty moves the deposit as well
+        assert_ok!(KittiesModule::set_price(Origin::signed(101), CLASS_ID, 0, Some(10)));
+        assert_ok!(KittiesModule::buy(Origin::signed(100), 101, CLASS_ID, 0, 10));
+        assert_eq!(Balances::reserved_balance(101), 0);
+        assert_eq!(Balances::reserved_balance(100), 5);
+        assert_eq!(KittiesModule::kitty_deposit(CLASS_ID, 0), Some((100, 5)));
+    });
+}
+
+#[test]
+fn name_is_cleared_if_the_new_owner_can_not_pay_for_it() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(KittiesModule::create(Origin::signed(100), CLASS_ID));
+        assert_ok!(KittiesModule::set_name(Origin::signed(100), CLASS_ID, 0, b"Tom".to_vec()));
+        assert_ok!(KittiesModule::set_metadata(Origin::signed(100), CLASS_ID, 0, vec![1; 10]));
+        assert_eq!(Balances::reserved_balance(100), 13);
+
+        // 101 has no funds, the transfer succeeds anyway
+        assert_ok!(KittiesModule::transfer(Origin::signed(100), 101, CLASS_ID, 0));
+        assert_eq!(KittiesModule::owner_of(CLASS_ID, 0), Some(101));
+
+        assert_eq!(KittiesModule::kitty_name(CLASS_ID, 0), None);
+        assert_eq!(KittiesModule::kitty_metadata(CLASS_ID, 0), None);
+        assert_eq!(KittiesModule::kitty_deposit(CLASS_ID, 0), None);
+        assert_eq!(Balances::reserved_balance(100), 0);
+        assert_eq!(Balances::reserved_balance(101), 0);
+    });
+}
+
+#[test]
+fn can_burn() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(KittiesModule::create(Origin::signed(100), CLASS_ID));
+        assert_ok!(KittiesModule::set_name(Origin::signed(100), CLASS_ID, 0, b"Tom".to_vec()));
+        assert_ok!(KittiesModule::set_price(Origin::signed(100), CLASS_ID, 0, Some(20)));
+        assert_ok!(KittiesModule::set_auto_breed_consent(Origin::signed(100), CLASS_ID, 0, true));
+
+        // only the owner can burn a kitty
+        assert_noop!(KittiesModule::burn(Origin::signed(101), CLASS_ID, 0), orml_nft::Error::<Test>::NoPermission);
+
+        assert_ok!(KittiesModule::burn(Origin::signed(100), CLASS_ID, 0));
+        System::assert_last_event(Event::KittiesModule(crate::Event::KittyBurned(100, CLASS_ID, 0)));
+
+        assert_eq!(Nft::tokens(CLASS_ID, 0), None);
+        assert_eq!(KittiesModule::kitties_of(&100, CLASS_ID, None, 10).kitties, vec![]);
+        assert_eq!(KittiesModule::kitty_prices(CLASS_ID, 0), None);
+        assert_eq!(KittiesModule::listings(CLASS_ID, 0, 10), vec![]);
+        assert_eq!(KittiesModule::auto_breed_consent(CLASS_ID, 0), None);
+        assert_eq!(KittiesModule::kitty_name(CLASS_ID, 0), None);
+
+        // the deposit is refunded
+        assert_eq!(Balances::reserved_balance(100), 0);
+        assert_eq!(Balances::free_balance(100), 80);
+    });
+}
+
+#[test]
+fn can_create_collection() {
+    new_test_ext().execute_with(|| {
+        // only the collection origin can create collections
+        assert_noop!(KittiesModule::create_collection(Origin::signed(100), b"Cats".to_vec(), None, true), DispatchError::BadOrigin);
+        assert_noop!(KittiesModule::create_collection(Origin::root(), b"Fancy cats".to_vec(), None, true), Error::<Test>::NameTooLong);
+
+        assert_ok!(KittiesModule::create_collection(Origin::root(), b"Cats".to_vec(), Some(2), false));
+
+        let data = KittyClassData { name: b"Cats".to_vec(), max_supply: Some(2), breeding_enabled: false };
+        System::assert_last_event(Event::KittiesModule(crate::Event::CollectionCreated(1, data.clone())));
+        assert_eq!(KittiesModule::collection(1), Some(data));
+        assert_eq!(KittiesModule::collection(CLASS_ID), Some(KittyClassData::default()));
+
+        // kitty ids are counted per collection
+        assert_ok!(KittiesModule::create(Origin::signed(100), CLASS_ID));
+        assert_ok!(KittiesModule::create(Origin::signed(100), 1));
+        assert_eq!(KittiesModule::owner_of(1, 0), Some(100));
+        System::assert_last_event(Event::KittiesModule(crate::Event::KittyCreated(100, 1, 0, KittiesModule::kitty(1, 0).unwrap())));
+
+        // the state of a kitty is kept apart from the kitty with the same id in another collection
+        assert_ok!(KittiesModule::set_price(Origin::signed(100), 1, 0, Some(10)));
+        assert_eq!(KittiesModule::kitty_prices(CLASS_ID, 0), None);
+        assert_eq!(KittiesModule::floor_price(1), Some(10));
+        assert_eq!(KittiesModule::floor_price(CLASS_ID), None);
+
+        assert_noop!(KittiesModule::create(Origin::signed(100), 2), Error::<Test>::InvalidCollection);
+    });
+}
+
+#[test]
+fn collection_supply_is_capped() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(KittiesModule::create_collection(Origin::root(), b"Rare".to_vec(), Some(2), true));
+
+        assert_ok!(KittiesModule::create(Origin::signed(100), 1));
+        MockRandom::set(H256::from([2; 32]));
+        assert_ok!(KittiesModule::create(Origin::signed(100), 1));
+
+        assert_noop!(KittiesModule::create(Origin::signed(100), 1), Error::<Test>::MaxSupplyReached);
+        // bred kittens count as well
+        assert_noop!(KittiesModule::breed(Origin::signed(100), 1, 0, 1), Error::<Test>::MaxSupplyReached);
+
+        // burning a kitty makes room for a new one
+        assert_ok!(KittiesModule::burn(Origin::signed(100), 1, 0));
+        assert_ok!(KittiesModule::create(Origin::signed(100), 1));
+    });
+}
+
+#[test]
+fn breeding_can_be_disabled() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(KittiesModule::create_collection(Origin::root(), b"Sterile".to_vec(), None, false));
+
+        assert_ok!(KittiesModule::create(Origin::signed(100), 1));
+        MockRandom::set(H256::from([2; 32]));
+        assert_ok!(KittiesModule::create(Origin::signed(100), 1));
+
+        assert_noop!(KittiesModule::breed(Origin::signed(100), 1, 0, 1), Error::<Test>::BreedingDisabled);
+
+        // the offchain worker can not breed them either
+        assert_ok!(KittiesModule::set_auto_breed_consent(Origin::signed(100), 1, 0, true));
+        assert_ok!(KittiesModule::set_auto_breed_consent(Origin::signed(100), 1, 1, true));
+
+        let solution = (0u128..).find(|solution| KittiesModule::validate_solution(1, 0, 1, 0, *solution)).unwrap();
+        assert_eq!(
+            KittiesModule::validate_unsigned(TransactionSource::External, &crate::Call::auto_breed(1, 0, 1, 0, solution)),
+            Err(InvalidTransaction::from(AutoBreedValidityError::BreedingDisabled).into()),
+        );
+        assert_noop!(KittiesModule::auto_breed(Origin::none(), 1, 0, 1, 0, solution), Error::<Test>::BreedingDisabled);
+    });
+}
+
+#[test]
+fn migrates_storage_from_v1() {
+    new_test_ext().execute_with(|| {
+        // a new chain starts with the latest layout
+        assert_eq!(KittiesModule::storage_version(), Releases::V3_0_0);
+        assert_ok!(migrations::pre_migrate::<Test, ()>());
+
+        // write the state of a V1 chain with two kitties
+        let pallet = <KittiesModule as PalletInfoAccess>::name().as_bytes();
+        let old_key = |kitty_id: u32| Blake2_128Concat::hash(&kitty_id.encode());
+
+        StorageVersion::<Test>::kill();
+        Collections::<Test>::remove(CLASS_ID);
+        put_storage_value(pallet, b"ClassId", &[], CLASS_ID);
+        unhashed::put(&orml_nft::Classes::<Test>::hashed_key_for(CLASS_ID), &orml_nft::ClassInfo {
+            metadata: Default::default(),
+            total_issuance: 2u32,
+            owner: 0u64,
+            data: (),
+        });
+        for (kitty_id, dna) in vec![(0u32, [0u8; 16]), (1, [1; 16])] {
+            unhashed::put(&orml_nft::Tokens::<Test>::hashed_key_for(CLASS_ID, kitty_id), &orml_nft::TokenInfo {
+                metadata: Default::default(),
+                owner: 100u64,
+                data: migrations::v2::OldKitty(dna),
+            });
+            orml_nft::TokensByOwner::<Test>::insert(100, (CLASS_ID, kitty_id), ());
+        }
+        orml_nft::NextTokenId::<Test>::insert(CLASS_ID, 2);
+
+        put_storage_value(pallet, b"KittyPrices", &old_key(1), 20u64);
+        put_storage_value(pallet, b"ListingsByPrice", &[], vec![(20u64, 1u32)]);
+        put_storage_value(pallet, b"KittyNames", &old_key(0), b"Tom".to_vec());
+        put_storage_value(pallet, b"KittyDeposits", &old_key(0), (100u64, 3u64));
+        put_storage_value(pallet, b"NextBreedBlock", &old_key(1), 6u64);
+        put_storage_value(pallet, b"AutoBreedConsent", &old_key(0), 0u32);
+        put_storage_value(pallet, b"AutoBreedCandidates", &Twox64Concat::hash(&0u32.encode()), 0u32);
+        put_storage_value(pallet, b"AutoBreedCandidateCount", &[], 1u32);
+
+        // the V1 kitties can not be read before the migration
+        assert_eq!(KittiesModule::kitty(CLASS_ID, 0), None);
+
+        assert_ok!(migrations::pre_migrate::<Test, ()>());
+        migrations::migrate::<Test, ()>();
+        assert_ok!(migrations::post_migrate::<Test, ()>());
+
+        assert_eq!(KittiesModule::storage_version(), Releases::V3_0_0);
+        assert_eq!(KittiesModule::collection(CLASS_ID), Some(KittyClassData::default()));
+        assert_eq!(KittiesModule::kitty(CLASS_ID, 0), Some(Kitty::new([0; 16])));
+        assert_eq!(KittiesModule::kitty(CLASS_ID, 1), Some(Kitty::new([1; 16])));
+
+        assert_eq!(kitty_ids(KittiesModule::kitties_of(&100, CLASS_ID, None, 10)), vec![0, 1]);
+        assert_eq!(KittiesModule::kitty_prices(CLASS_ID, 1), Some(20));
+        assert_eq!(KittiesModule::listings(CLASS_ID, 0, 10), vec![(1, 20)]);
+        assert_eq!(KittiesModule::kitty_name(CLASS_ID, 0), Some(b"Tom".to_vec()));
+        assert_eq!(KittiesModule::kitty_deposit(CLASS_ID, 0), Some((100, 3)));
+        assert_eq!(KittiesModule::last_breed_block(CLASS_ID, 1), Some(1));
+        assert_eq!(KittiesModule::auto_breed_consent(CLASS_ID, 0), Some(0));
+        assert_eq!(AutoBreedCandidates::<Test>::get(CLASS_ID, 0), Some(0));
+        assert_eq!(KittiesModule::auto_breed_candidate_count(CLASS_ID), 1);
+
+        // running the migrations again changes nothing
+        migrations::migrate::<Test, ()>();
+        assert_eq!(KittiesModule::kitty(CLASS_ID, 1), Some(Kitty::new([1; 16])));
+
+        // the migrated kitties can be used as usual
+        assert_ok!(KittiesModule::transfer(Origin::signed(100), 101, CLASS_ID, 1));
+        assert_eq!(KittiesModule::floor_price(CLASS_ID), None);
+        assert_ok!(KittiesModule::create(Origin::signed(100), CLASS_ID));
+        assert_eq!(KittiesModule::owner_of(CLASS_ID, 2), Some(100));
+    });
+}
+
+#[test]
+fn migration_checks_detect_a_broken_state() {
+    new_test_ext().execute_with(|| {
+        // a V1 chain without a class can not be migrated
+        StorageVersion::<Test>::kill();
+        put_storage_value(<KittiesModule as PalletInfoAccess>::name().as_bytes(), b"ClassId", &[], 5u32);
+        assert_eq!(migrations::pre_migrate::<Test, ()>(), Err("the class of the kitties does not exist"));
+
+        // a kitty that is still in the V1 layout is detected
+        assert_ok!(KittiesModule::create(Origin::signed(100), CLASS_ID));
+        unhashed::put(&orml_nft::Tokens::<Test>::hashed_key_for(CLASS_ID, 0), &orml_nft::TokenInfo {
+            metadata: Default::default(),
+            owner: 100u64,
+            data: migrations::v2::OldKitty([0; 16]),
+        });
+        StorageVersion::<Test>::put(Releases::V3_0_0);
+        assert_eq!(migrations::post_migrate::<Test, ()>(), Err("a kitty could not be translated"));
+    });
+}
+
+#[test]
+fn migrates_collections_to_v3() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(KittiesModule::create(Origin::signed(100), CLASS_ID));
+
+        // the collections of a V2 chain are not assigned to an instance yet
+        StorageVersion::<Test>::put(Releases::V2_0_0);
+        Collections::<Test>::remove(CLASS_ID);
+        assert_eq!(KittiesModule::kitty(CLASS_ID, 0), None);
+
+        assert_ok!(migrations::pre_migrate::<Test, ()>());
+        migrations::migrate::<Test, ()>();
+        assert_ok!(migrations::post_migrate::<Test, ()>());
+
+        assert_eq!(KittiesModule::storage_version(), Releases::V3_0_0);
+        assert_eq!(KittiesModule::collection(CLASS_ID), Some(KittyClassData::default()));
+        assert_eq!(KittiesModule::owner_of(CLASS_ID, 0), Some(100));
+
+        // an instance that is added later starts with the latest layout, without taking the
+        // collections of the other instances
+        StorageVersion::<Test, Instance1>::kill();
+
+        assert_ok!(migrations::pre_migrate::<Test, Instance1>());
+        migrations::migrate::<Test, Instance1>();
+        assert_ok!(migrations::post_migrate::<Test, Instance1>());
+
+        assert_eq!(OtherKitties::storage_version(), Releases::V3_0_0);
+        assert_eq!(OtherKitties::collection(CLASS_ID), None);
+    });
+}
+
+#[test]
+fn can_approve_and_transfer_from() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(KittiesModule::create(Origin::signed(100), CLASS_ID));
+
+        assert_noop!(KittiesModule::approve(Origin::signed(101), CLASS_ID, 0, Some(101)), Error::<Test>::NotOwner);
+        assert_noop!(KittiesModule::approve(Origin::signed(100), CLASS_ID, 1, Some(101)), Error::<Test>::InvalidKittyId);
+        assert_noop!(KittiesModule::approve(Origin::signed(100), CLASS_ID, 0, Some(100)), Error::<Test>::ApproveToSelf);
+
+        // without an approval, nobody else can transfer the kitty
+        assert_noop!(KittiesModule::transfer_from(Origin::signed(101), 100, 102, CLASS_ID, 0), Error::<Test>::NotApproved);
+
+        assert_ok!(KittiesModule::approve(Origin::signed(100), CLASS_ID, 0, Some(101)));
+        System::assert_last_event(Event::KittiesModule(crate::Event::Approval(100, CLASS_ID, 0, Some(101))));
+        assert_eq!(KittiesModule::approved(CLASS_ID, 0), Some(101));
+
+        // the approved account can only transfer the kitty from its owner
+        assert_noop!(KittiesModule::transfer_from(Origin::signed(101), 102, 101, CLASS_ID, 0), orml_nft::Error::<Test>::NoPermission);
+
+        assert_ok!(KittiesModule::transfer_from(Origin::signed(101), 100, 102, CLASS_ID, 0));
+        System::assert_last_event(Event::KittiesModule(crate::Event::KittyTransferred(100, 102, CLASS_ID, 0)));
+        assert_eq!(KittiesModule::owner_of(CLASS_ID, 0), Some(102));
+
+        // the approval does not move with the kitty
+        assert_eq!(KittiesModule::approved(CLASS_ID, 0), None);
+        assert_noop!(KittiesModule::transfer_from(Origin::signed(101), 102, 101, CLASS_ID, 0), Error::<Test>::NotApproved);
+
+        // an approval can be cleared
+        assert_ok!(KittiesModule::approve(Origin::signed(102), CLASS_ID, 0, Some(101)));
+        assert_ok!(KittiesModule::approve(Origin::signed(102), CLASS_ID, 0, None));
+        System::assert_last_event(Event::KittiesModule(crate::Event::Approval(102, CLASS_ID, 0, None)));
+        assert_eq!(KittiesModule::approved(CLASS_ID, 0), None);
+    });
+}
+
+#[test]
+fn operators_can_transfer_all_kitties() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(KittiesModule::create(Origin::signed(100), CLASS_ID));
+        assert_ok!(KittiesModule::create(Origin::signed(100), CLASS_ID));
+
+        assert_noop!(KittiesModule::set_approval_for_all(Origin::signed(100), 100, true), Error::<Test>::ApproveToSelf);
+
+        assert_ok!(KittiesModule::set_approval_for_all(Origin::signed(100), 101, true));
+        System::assert_last_event(Event::KittiesModule(crate::Event::ApprovalForAll(100, 101, true)));
+        assert!(KittiesModule::is_approved_for_all(&100, &101));
+
+        // an operator can approve other accounts for the kitties of the owner
+        assert_ok!(KittiesModule::approve(Origin::signed(101), CLASS_ID, 1, Some(102)));
+        System::assert_last_event(Event::KittiesModule(crate::Event::Approval(100, CLASS_ID, 1, Some(102))));
+
+        assert_ok!(KittiesModule::transfer_from(Origin::signed(101), 100, 103, CLASS_ID, 0));
+        assert_eq!(KittiesModule::owner_of(CLASS_ID, 0), Some(103));
+
+        // the operator can no longer transfer the kitties once the approval is revoked
+        assert_ok!(KittiesModule::set_approval_for_all(Origin::signed(100), 101, false));
+        System::assert_last_event(Event::KittiesModule(crate::Event::ApprovalForAll(100, 101, false)));
+        assert_noop!(KittiesModule::transfer_from(Origin::signed(101), 100, 103, CLASS_ID, 1), Error::<Test>::NotApproved);
+
+        // but the accounts it approved still can
+        assert_ok!(KittiesModule::transfer_from(Origin::signed(102), 100, 103, CLASS_ID, 1));
+    });
+}
+
+#[test]
+fn approvals_are_cleared_when_a_kitty_is_sold() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(KittiesModule::create(Origin::signed(101), CLASS_ID));
+        assert_ok!(KittiesModule::approve(Origin::signed(101), CLASS_ID, 0, Some(102)));
+        assert_ok!(KittiesModule::set_price(Origin::signed(101), CLASS_ID, 0, Some(10)));
+
+        assert_ok!(KittiesModule::buy(Origin::signed(100), 101, CLASS_ID, 0, 10));
+
+        assert_eq!(KittiesModule::approved(CLASS_ID, 0), None);
+        assert_noop!(KittiesModule::transfer_from(Origin::signed(102), 100, 102, CLASS_ID, 0), Error::<Test>::NotApproved);
+    });
+}
+
+#[test]
+fn locked_kitties_can_not_change_their_owner() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(KittiesModule::create(Origin::signed(100), CLASS_ID));
+        assert_ok!(KittiesModule::set_price(Origin::signed(100), CLASS_ID, 0, Some(10)));
+        assert_ok!(KittiesModule::set_approval_for_all(Origin::signed(100), 102, true));
+
+        assert_noop!(<KittiesModule as KittyLockManager<_, _>>::lock(CLASS_ID, 1, LockReason::Staked), Error::<Test>::InvalidKittyId);
+
+        assert_ok!(<KittiesModule as KittyLockManager<_, _>>::lock(CLASS_ID, 0, LockReason::Staked));
+        System::assert_last_event(Event::KittiesModule(crate::Event::KittyLocked(CLASS_ID, 0, LockReason::Staked)));
+        assert!(<KittiesModule as KittyLockManager<_, _>>::is_locked(CLASS_ID, 0));
+
+        // the kitty is no longer for sale
+        assert_eq!(KittiesModule::kitty_prices(CLASS_ID, 0), None);
+        assert_eq!(KittiesModule::floor_price(CLASS_ID), None);
+
+        // a kitty can only have one lock
+        assert_noop!(<KittiesModule as KittyLockManager<_, _>>::lock(CLASS_ID, 0, LockReason::Lent), Error::<Test>::KittyLocked);
+
+        assert_noop!(KittiesModule::transfer(Origin::signed(100), 101, CLASS_ID, 0), Error::<Test>::KittyLocked);
+        assert_noop!(KittiesModule::transfer_from(Origin::signed(102), 100, 101, CLASS_ID, 0), Error::<Test>::KittyLocked);
+        assert_noop!(KittiesModule::set_price(Origin::signed(100), CLASS_ID, 0, Some(10)), Error::<Test>::KittyLocked);
+        assert_noop!(KittiesModule::buy(Origin::signed(101), 100, CLASS_ID, 0, 10), Error::<Test>::NotForSale);
+        assert_noop!(KittiesModule::burn(Origin::signed(100), CLASS_ID, 0), Error::<Test>::KittyLocked);
+
+        // only the lock of the same reason can be removed
+        assert_noop!(<KittiesModule as KittyLockManager<_, _>>::unlock(CLASS_ID, 0, LockReason::Lent), Error::<Test>::NotLocked);
+
+        assert_ok!(<KittiesModule as KittyLockManager<_, _>>::unlock(CLASS_ID, 0, LockReason::Staked));
+        System::assert_last_event(Event::KittiesModule(crate::Event::KittyUnlocked(CLASS_ID, 0, LockReason::Staked)));
+        assert_eq!(KittiesModule::kitty_lock(CLASS_ID, 0), None);
+
+        assert_ok!(KittiesModule::transfer(Origin::signed(100), 101, CLASS_ID, 0));
+        assert_eq!(KittiesModule::owner_of(CLASS_ID, 0), Some(101));
+    });
+}
+
+#[test]
+fn instances_are_independent() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(OtherKitties::create_collection(Origin::root(), b"Others".to_vec(), None, true));
+        let other_class_id = CLASS_ID + 1;
+        System::assert_last_event(Event::OtherKitties(crate::Event::CollectionCreated(other_class_id, KittyClassData {
+            name: b"Others".to_vec(),
+            max_supply: None,
+            breeding_enabled: true,
+        })));
+
+        // every instance can only use its own collections
+        assert_noop!(KittiesModule::create(Origin::signed(100), other_class_id), Error::<Test>::InvalidCollection);
+        assert_noop!(OtherKitties::create(Origin::signed(100), CLASS_ID), Error::<Test, Instance1>::InvalidCollection);
+
+        assert_ok!(KittiesModule::create(Origin::signed(100), CLASS_ID));
+        assert_ok!(OtherKitties::create(Origin::signed(100), other_class_id));
+        assert_eq!(KittiesModule::kitty(other_class_id, 0), None);
+        assert_eq!(OtherKitties::kitty(CLASS_ID, 0), None);
+
+        assert_noop!(KittiesModule::transfer(Origin::signed(100), 101, other_class_id, 0), Error::<Test>::InvalidCollection);
+        assert_noop!(KittiesModule::set_price(Origin::signed(100), other_class_id, 0, Some(10)), Error::<Test>::NotOwner);
+        assert_noop!(KittiesModule::burn(Origin::signed(100), other_class_id, 0), Error::<Test>::InvalidCollection);
+
+        // the prices are kept per instance
+        assert_ok!(OtherKitties::set_price(Origin::signed(100), other_class_id, 0, Some(10)));
+        assert_eq!(OtherKitties::floor_price(other_class_id), Some(10));
+        assert_eq!(KittiesModule::floor_price(other_class_id), None);
+        assert_eq!(KittiesModule::kitties_of(&100, CLASS_ID, None, 10).kitties.len(), 1);
+        assert_eq!(OtherKitties::kitties_of(&100, other_class_id, None, 10).kitties.len(), 1);
+    });
+}
+
+// the same game, played with any NFT backend
+
+// the ids of a page of kitties
+fn kitty_ids(page: KittiesPage<u32>) -> Vec<u32> {
+    page.kitties.into_iter().map(|(kitty_id, _)| kitty_id).collect()
+}
+
+fn plays_with_backend<I: 'static>(class_id: u32)
+where
+    Test: Config<I, Currency = Balances>,
+    <Test as Config<I>>::Nft: NftBackend<u64, ClassId = u32, TokenId = u32>,
+{
+    assert_ok!(Pallet::<Test, I>::create(Origin::signed(100), class_id));
+    assert_ok!(Pallet::<Test, I>::create(Origin::signed(100), class_id));
+    assert_eq!(kitty_ids(Pallet::<Test, I>::kitties_of(&100, class_id, None, 10)), vec![0, 1]);
+    assert_eq!(Pallet::<Test, I>::owner_of(class_id, 0), Some(100));
+    assert!(Pallet::<Test, I>::kitty(class_id, 0).is_some());
+
+    assert_ok!(Pallet::<Test, I>::transfer(Origin::signed(100), 101, class_id, 0));
+    assert_eq!(Pallet::<Test, I>::owner_of(class_id, 0), Some(101));
+    assert_noop!(Pallet::<Test, I>::transfer(Origin::signed(100), 102, class_id, 0), Error::<Test, I>::NotOwner);
+    assert_eq!(kitty_ids(Pallet::<Test, I>::kitties_of(&100, class_id, None, 10)), vec![1]);
+
+    assert_ok!(Pallet::<Test, I>::set_price(Origin::signed(100), class_id, 1, Some(30)));
+    assert_ok!(Balances::transfer(Origin::signed(100), 101, 50));
+    assert_ok!(Pallet::<Test, I>::buy(Origin::signed(101), 100, class_id, 1, 30));
+    assert_eq!(Pallet::<Test, I>::owner_of(class_id, 1), Some(101));
+    assert_eq!(kitty_ids(Pallet::<Test, I>::kitties_of(&101, class_id, None, 10)), vec![0, 1]);
+
+    assert_ok!(Pallet::<Test, I>::burn(Origin::signed(101), class_id, 0));
+    assert_eq!(Pallet::<Test, I>::owner_of(class_id, 0), None);
+    assert_eq!(Pallet::<Test, I>::kitty(class_id, 0), None);
+
+    // the ids of burned kitties are not reused
+    assert_ok!(Pallet::<Test, I>::create(Origin::signed(100), class_id));
+    assert_eq!(Pallet::<Test, I>::owner_of(class_id, 2), Some(100));
+
+    // the name and its deposit move with the kitty
+    assert_ok!(Pallet::<Test, I>::set_name(Origin::signed(101), class_id, 1, b"Tom".to_vec()));
+    assert_ok!(Pallet::<Test, I>::transfer(Origin::signed(101), 100, class_id, 1));
+    assert_eq!(Pallet::<Test, I>::kitty_name(class_id, 1), Some(b"Tom".to_vec()));
+    assert_eq!(Pallet::<Test, I>::kitty_deposit(class_id, 1), Some((100, 3)));
+
+    // breeding mints a new kitty
+    MockRandom::set(H256::from([2; 32]));
+    assert_ok!(Pallet::<Test, I>::create(Origin::signed(100), class_id));
+    assert_ok!(Pallet::<Test, I>::breed(Origin::signed(100), class_id, 2, 3));
+    assert_eq!(Pallet::<Test, I>::owner_of(class_id, 4), Some(100));
+    assert_eq!(Pallet::<Test, I>::kitty(class_id, 4).map(|kitty| kitty.generation), Some(1));
+    assert_eq!(kitty_ids(Pallet::<Test, I>::kitties_of(&100, class_id, None, 10)), vec![1, 2, 3, 4]);
+
+    // a vault holds the kitty until it is redeemed
+    assert_ok!(Pallet::<Test, I>::fractionalise(Origin::signed(100), class_id, 4, 10, 60));
+    let asset_id = Pallet::<Test, I>::kitty_shares(class_id, 4).unwrap();
+    assert_eq!(Pallet::<Test, I>::owner_of(class_id, 4), Some(Pallet::<Test, I>::vault_account(asset_id)));
+    assert_ok!(Pallet::<Test, I>::redeem(Origin::signed(100), asset_id));
+    assert_eq!(Pallet::<Test, I>::owner_of(class_id, 4), Some(100));
+
+    // the collateral of a loan is escrowed until the loan is cancelled
+    assert_ok!(Pallet::<Test, I>::request_loan(Origin::signed(100), class_id, 4, 50, 5, 10));
+    assert_eq!(Pallet::<Test, I>::owner_of(class_id, 4), Some(Pallet::<Test, I>::loan_account(0)));
+    assert_ok!(Pallet::<Test, I>::cancel_loan(Origin::signed(100), class_id, 4));
+    assert_eq!(Pallet::<Test, I>::owner_of(class_id, 4), Some(100));
+}
+
+#[test]
+fn plays_with_orml_nft() {
+    new_test_ext().execute_with(|| plays_with_backend::<()>(CLASS_ID));
+}
+
+#[test]
+fn plays_with_uniques() {
+    new_test_ext().execute_with(|| {
+        plays_with_backend::<Instance2>(CLASS_ID);
+
+        // the owners of the kitties are stored in pallet_uniques, and the kitties in the kitties
+        // pallet
+        assert_eq!(<Uniques as Inspect<u64>>::owner(&CLASS_ID, &1), Some(100));
+        assert!(UniquesTokenData::<Test, Instance2>::get(CLASS_ID, 1).is_some());
+        assert_eq!(UniquesTotalIssuance::<Test, Instance2>::get(CLASS_ID), 4);
+        assert_eq!(Nft::tokens(CLASS_ID, 1), None);
+    });
+}
+
+#[test]
+fn can_set_parameters() {
+    new_test_ext().execute_with(|| {
+        // the parameters start with the constants of the runtime
+        let parameters = KittiesModule::parameters();
+        assert_eq!(parameters, KittyParameters {
+            difficulty: DefaultDifficulty::get(),
+            breed_cooldown: BreedCooldown::get(),
+            max_name_length: MaxNameLength::get(),
+            max_metadata_length: MaxMetadataLength::get(),
+            deposit_per_byte: DepositPerByte::get(),
+            breeding_enabled: true,
+            auto_breeding_enabled: true,
+            breed_fee: 0,
+            max_reason_length: MaxReasonLength::get(),
+            max_history_length: MaxHistoryLength::get(),
+            max_buy_orders: MaxBuyOrders::get(),
+            max_floor_bids: MaxFloorBids::get(),
+            min_order_price: MinOrderPrice::get(),
+            layaway_deposit: LayawayDeposit::get(),
+            layaway_period: LayawayPeriod::get(),
+            layaway_forfeit: LayawayForfeit::get(),
+        });
+
+        let new_parameters = KittyParameters { max_name_length: 2, breeding_enabled: false, max_reason_length: 2, ..parameters.clone() };
+        assert_noop!(KittiesModule::set_parameters(Origin::signed(100), new_parameters.clone()), DispatchError::BadOrigin);
+        assert_noop!(KittiesModule::set_parameters(Origin::root(), KittyParameters { difficulty: 0, ..parameters.clone() }), Error::<Test>::InvalidParameters);
+        // the vectors can not grow beyond the lengths the weights are benchmarked with
+        assert_noop!(KittiesModule::set_parameters(Origin::root(), KittyParameters { max_history_length: MAX_HISTORY_LENGTH + 1, ..parameters.clone() }), Error::<Test>::InvalidParameters);
+        assert_noop!(KittiesModule::set_parameters(Origin::root(), KittyParameters { max_buy_orders: MAX_BUY_ORDERS + 1, ..parameters.clone() }), Error::<Test>::InvalidParameters);
+        assert_noop!(KittiesModule::set_parameters(Origin::root(), KittyParameters { max_floor_bids: MAX_FLOOR_BIDS + 1, ..parameters.clone() }), Error::<Test>::InvalidParameters);
+        assert_ok!(KittiesModule::set_parameters(Origin::root(), KittyParameters { max_buy_orders: MAX_BUY_ORDERS, ..parameters.clone() }));
+        assert_ok!(KittiesModule::set_parameters(Origin::root(), parameters.clone()));
+
+        assert_ok!(KittiesModule::set_parameters(Origin::root(), new_parameters.clone()));
+        System::assert_last_event(Event::KittiesModule(crate::Event::ParametersUpdated(vec![
+            KittyParameter::MaxNameLength(2),
+            KittyParameter::BreedingEnabled(false),
+            KittyParameter::MaxReasonLength(2),
+        ])));
+        assert_eq!(KittiesModule::parameters(), new_parameters);
+
+        // the new parameters are used right away
+        assert_ok!(KittiesModule::create(Origin::signed(100), CLASS_ID));
+        MockRandom::set(H256::from([2; 32]));
+        assert_ok!(KittiesModule::create(Origin::signed(100), CLASS_ID));
+        assert_noop!(KittiesModule::set_name(Origin::signed(100), CLASS_ID, 0, b"Tom".to_vec()), Error::<Test>::NameTooLong);
+        assert_noop!(KittiesModule::breed(Origin::signed(100), CLASS_ID, 0, 1), Error::<Test>::BreedingDisabled);
+        assert_noop!(KittiesModule::block_account(Origin::root(), 101, b"abc".to_vec(), None), Error::<Test>::ReasonTooLong);
+
+        // the breed fee is burned
+        assert_ok!(KittiesModule::set_parameters(Origin::root(), KittyParameters { breed_fee: 5, ..parameters }));
+        let issuance = Balances::total_issuance();
+        assert_ok!(KittiesModule::breed(Origin::signed(100), CLASS_ID, 0, 1));
+        assert_eq!(Balances::free_balance(100), 75);
+        assert_eq!(Balances::total_issuance(), issuance - 5);
+
+        // the other instances keep their parameters
+        assert_eq!(OtherKitties::parameters().difficulty, OtherDifficulty::get());
+        assert_eq!(OtherKitties::parameters().max_name_length, MaxNameLength::get());
+    });
+}
+
+#[test]
+fn can_pause_calls() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(KittiesModule::create(Origin::signed(100), CLASS_ID));
+        assert_ok!(KittiesModule::transfer(Origin::signed(100), 101, CLASS_ID, 0));
+        assert_ok!(KittiesModule::set_price(Origin::signed(101), CLASS_ID, 0, Some(20)));
+
+        assert_noop!(KittiesModule::pause(Origin::signed(100), CallKind::Buy), DispatchError::BadOrigin);
+        assert_ok!(KittiesModule::pause(Origin::root(), CallKind::Buy));
+        System::assert_last_event(Event::KittiesModule(crate::Event::CallPaused(CallKind::Buy)));
+        assert_noop!(KittiesModule::pause(Origin::root(), CallKind::Buy), Error::<Test>::AlreadyPaused);
+
+        // the paused calls are rejected by the call filter, the others are not
+        let buy = Call::KittiesModule(crate::Call::buy(101, CLASS_ID, 0, 20));
+        assert_noop!(buy.clone().dispatch(Origin::signed(100)), DispatchError::BadOrigin);
+        assert_ok!(Call::KittiesModule(crate::Call::set_price(CLASS_ID, 0, Some(30))).dispatch(Origin::signed(101)));
+        assert_ok!(Call::KittiesModule(crate::Call::set_price(CLASS_ID, 0, Some(20))).dispatch(Origin::signed(101)));
+        // every instance is paused on its own
+        assert!(!OtherKitties::is_paused(CallKind::Buy));
+
+        assert_noop!(KittiesModule::unpause(Origin::signed(100), CallKind::Buy), DispatchError::BadOrigin);
+        assert_ok!(KittiesModule::unpause(Origin::root(), CallKind::Buy));
+        System::assert_last_event(Event::KittiesModule(crate::Event::CallUnpaused(CallKind::Buy)));
+        assert_noop!(KittiesModule::unpause(Origin::root(), CallKind::Buy), Error::<Test>::NotPaused);
+
+        assert_ok!(buy.dispatch(Origin::signed(100)));
+        assert_eq!(KittiesModule::owner_of(CLASS_ID, 0), Some(100));
+    });
+}
+
+#[test]
+fn paused_calls_do_not_pay_fees() {
+    new_test_ext().execute_with(|| {
+        let _ = Balances::deposit_creating(&7, 1_000_000_000_000);
+        let extensions = (CheckKittiesPaused::<Test>::new(), pallet_transaction_payment::ChargeTransactionPayment::<Test>::from(0));
+        let create = Call::KittiesModule(crate::Call::create(CLASS_ID));
+        let info = create.get_dispatch_info();
+
+        assert_ok!(KittiesModule::pause(Origin::root(), CallKind::Create));
+
+        // the paused call is rejected by the pool and before it is dispatched, and pays nothing
+        assert_eq!(extensions.validate(&7, &create, &info, 10), Err(InvalidTransaction::Call.into()));
+        assert_eq!(extensions.clone().pre_dispatch(&7, &create, &info, 10).map(|_| ()), Err(InvalidTransaction::Call.into()));
+        assert_eq!(Balances::free_balance(7), 1_000_000_000_000);
+
+        // the other calls, and the same call of another instance, are not paused
+        let set_price = Call::KittiesModule(crate::Call::set_price(CLASS_ID, 0, None));
+        assert_ok!(extensions.validate(&7, &set_price, &set_price.get_dispatch_info(), 10));
+        let other_create = Call::OtherKitties(crate::Call::create(CLASS_ID));
+        assert_ok!(CheckKittiesPaused::<Test>::new().validate(&7, &other_create, &other_create.get_dispatch_info(), 10));
+
+        // an unpaused call pays its fee
+        let balance = Balances::free_balance(7);
+        assert_ok!(KittiesModule::unpause(Origin::root(), CallKind::Create));
+        assert_ok!(extensions.pre_dispatch(&7, &create, &info, 10));
+        assert!(Balances::free_balance(7) < balance);
+    });
+}
+
+#[test]
+fn paused_auto_breed_is_not_valid() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(KittiesModule::pause(Origin::root(), CallKind::AutoBreed));
+
+        // the transaction pool does not run the call filter
+        let call = crate::Call::auto_breed(CLASS_ID, 0, 1, 0, find_solution(0, 1, 0));
+        assert_eq!(KittiesModule::validate_unsigned(TransactionSource::External, &call), Err(InvalidTransaction::Custom(AutoBreedValidityError::Paused as u8).into()));
+    });
+}
+
+#[test]
+fn blocked_accounts_can_not_trade() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(KittiesModule::create(Origin::signed(100), CLASS_ID));
+        assert_ok!(KittiesModule::set_price(Origin::signed(100), CLASS_ID, 0, Some(20)));
+
+        assert_noop!(KittiesModule::block_account(Origin::signed(100), 101, b"phishing".to_vec(), None), DispatchError::BadOrigin);
+        assert_noop!(KittiesModule::block_account(Origin::root(), 101, vec![b'a'; 17], None), Error::<Test>::ReasonTooLong);
+        assert_noop!(KittiesModule::block_account(Origin::root(), 101, b"phishing".to_vec(), Some(1)), Error::<Test>::InvalidExpiry);
+
+        assert_ok!(KittiesModule::block_account(Origin::root(), 100, b"phishing".to_vec(), None));
+        System::assert_last_event(Event::KittiesModule(crate::Event::AccountBlocked(100, b"phishing".to_vec(), None)));
+        assert_eq!(KittiesModule::blocked_account(100), Some(BlockEntry { reason: b"phishing".to_vec(), expiry: None }));
+
+        assert_noop!(KittiesModule::create(Origin::signed(100), CLASS_ID), Error::<Test>::AccountBlocked);
+        assert_noop!(KittiesModule::set_price(Origin::signed(100), CLASS_ID, 0, Some(10)), Error::<Test>::AccountBlocked);
+        // the kitties of a blocked account can not be bought
+        assert_noop!(KittiesModule::buy(Origin::signed(101), 100, CLASS_ID, 0, 20), Error::<Test>::AccountBlocked);
+        // but they can be delisted
+        assert_ok!(KittiesModule::set_price(Origin::signed(100), CLASS_ID, 0, None));
+
+        // a blocked account can not receive kitties
+        assert_ok!(KittiesModule::transfer(Origin::signed(100), 101, CLASS_ID, 0));
+        assert_noop!(KittiesModule::transfer(Origin::signed(101), 100, CLASS_ID, 0), Error::<Test>::AccountBlocked);
+        assert_ok!(KittiesModule::set_price(Origin::signed(101), CLASS_ID, 0, Some(20)));
+        assert_noop!(KittiesModule::buy(Origin::signed(100), 101, CLASS_ID, 0, 20), Error::<Test>::AccountBlocked);
+
+        // every instance has its own blocklist
+        assert_ok!(OtherKitties::create_collection(Origin::root(), b"Others".to_vec(), None, true));
+        assert_ok!(OtherKitties::create(Origin::signed(100), CLASS_ID + 1));
+
+        assert_ok!(KittiesModule::unblock_account(Origin::root(), 100));
+        System::assert_last_event(Event::KittiesModule(crate::Event::AccountUnblocked(100)));
+        assert_noop!(KittiesModule::unblock_account(Origin::root(), 100), Error::<Test>::NotBlocked);
+        assert_ok!(KittiesModule::create(Origin::signed(100), CLASS_ID));
+    });
+}
+
+#[test]
+fn blocks_expire() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(KittiesModule::block_account(Origin::root(), 100, b"spam".to_vec(), Some(5)));
+        // blocking an account again replaces its expiry
+        assert_ok!(KittiesModule::block_account(Origin::root(), 100, b"phishing".to_vec(), Some(3)));
+        assert_eq!(BlockExpiries::<Test>::get(5), Vec::<u64>::new());
+        assert_eq!(BlockExpiries::<Test>::get(3), vec![100]);
+
+        KittiesModule::on_initialize(2);
+        assert_noop!(KittiesModule::create(Origin::signed(100), CLASS_ID), Error::<Test>::AccountBlocked);
+
+        KittiesModule::on_initialize(3);
+        System::assert_last_event(Event::KittiesModule(crate::Event::AccountUnblocked(100)));
+        assert_eq!(KittiesModule::blocked_account(100), None);
+        assert_eq!(BlockExpiries::<Test>::get(3), Vec::<u64>::new());
+        assert_ok!(KittiesModule::create(Origin::signed(100), CLASS_ID));
+    });
+}
+
+#[test]
+fn blocked_accounts_can_not_claim_kitties() {
+    new_test_ext().execute_with(|| {
+        // the collateral of a loan
+        assert_ok!(KittiesModule::create(Origin::signed(1000), CLASS_ID));
+        assert_ok!(KittiesModule::request_loan(Origin::signed(1000), CLASS_ID, 0, 50, 5, 10));
+        assert_ok!(KittiesModule::fund_loan(Origin::signed(100), CLASS_ID, 0));
+        System::set_block_number(12);
+        assert_ok!(KittiesModule::block_account(Origin::root(), 100, b"phishing".to_vec(), None));
+        assert_noop!(KittiesModule::claim_collateral(Origin::signed(100), CLASS_ID, 0), Error::<Test>::AccountBlocked);
+        assert_ok!(KittiesModule::unblock_account(Origin::root(), 100));
+        assert_ok!(KittiesModule::claim_collateral(Origin::signed(100), CLASS_ID, 0));
+
+        // a kitty in a vault
+        assert_ok!(KittiesModule::create(Origin::signed(1000), CLASS_ID));
+        assert_ok!(KittiesModule::fractionalise(Origin::signed(1000), CLASS_ID, 1, 10, 60));
+        assert_ok!(KittiesModule::block_account(Origin::root(), 1000, b"phishing".to_vec(), None));
+        assert_noop!(KittiesModule::redeem(Origin::signed(1000), 0), Error::<Test>::AccountBlocked);
+        assert_ok!(KittiesModule::unblock_account(Origin::root(), 1000));
+        assert_ok!(KittiesModule::redeem(Origin::signed(1000), 0));
+        assert_eq!(KittiesModule::owner_of(CLASS_ID, 1), Some(1000));
+    });
+}
+
+#[test]
+fn keeps_the_ownership_history() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(KittiesModule::create(Origin::signed(100), CLASS_ID));
+
+        System::set_block_number(2);
+        assert_ok!(KittiesModule::transfer(Origin::signed(100), 101, CLASS_ID, 0));
+        // transferring a kitty to its owner does not change its owner
+        assert_ok!(KittiesModule::transfer(Origin::signed(101), 101, CLASS_ID, 0));
+
+        System::set_block_number(3);
+        assert_ok!(KittiesModule::set_price(Origin::signed(101), CLASS_ID, 0, Some(20)));
+        assert_ok!(KittiesModule::buy(Origin::signed(100), 101, CLASS_ID, 0, 20));
+
+        assert_eq!(KittiesModule::ownership_history(CLASS_ID, 0), vec![
+            OwnershipRecord { from: None, to: 100, block: 1, kind: OwnershipChange::Mint },
+            OwnershipRecord { from: Some(100), to: 101, block: 2, kind: OwnershipChange::Transfer },
+            OwnershipRecord { from: Some(101), to: 100, block: 3, kind: OwnershipChange::Sale(20) },
+        ]);
+
+        // the oldest change is dropped when the history is full
+        System::set_block_number(4);
+        assert_ok!(KittiesModule::transfer(Origin::signed(100), 102, CLASS_ID, 0));
+        assert_eq!(KittiesModule::ownership_history(CLASS_ID, 0), vec![
+            OwnershipRecord { from: Some(100), to: 101, block: 2, kind: OwnershipChange::Transfer },
+            OwnershipRecord { from: Some(101), to: 100, block: 3, kind: OwnershipChange::Sale(20) },
+            OwnershipRecord { from: Some(100), to: 102, block: 4, kind: OwnershipChange::Transfer },
+        ]);
+
+        // bred kittens start their history as well
+        MockRandom::set(H256::from([2; 32]));
+        assert_ok!(KittiesModule::create(Origin::signed(102), CLASS_ID));
+        assert_ok!(KittiesModule::breed(Origin::signed(102), CLASS_ID, 0, 1));
+        assert_eq!(KittiesModule::ownership_history(CLASS_ID, 2), vec![
+            OwnershipRecord { from: None, to: 102, block: 4, kind: OwnershipChange::Breed },
+        ]);
+
+        // the history of a burned kitty is removed
+        assert_ok!(KittiesModule::burn(Origin::signed(102), CLASS_ID, 0));
+        assert_eq!(KittiesModule::ownership_history(CLASS_ID, 0), vec![]);
+    });
+}
+
+#[test]
+fn can_fill_buy_orders() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(KittiesModule::create(Origin::signed(1000), CLASS_ID));
+        let dna = KittiesModule::kitty(CLASS_ID, 0).unwrap().dna;
+
+        // an order for kitties with the first bit of the dna flipped does not match
+        let mut other_dna = dna;
+        other_dna[0] ^= 1;
+        assert_ok!(KittiesModule::place_buy_order(Origin::signed(100), CLASS_ID, [0xff; 16], other_dna, 30));
+        assert_eq!(Balances::reserved_balance(100), 30);
+        assert_noop!(KittiesModule::fill_order(Origin::signed(1000), CLASS_ID, 0, 0), Error::<Test>::OrderMismatch);
+
+        // but it matches if the bit is not part of the mask
+        let mut mask = [0xff; 16];
+        mask[0] = 0xfe;
+        assert_ok!(KittiesModule::place_buy_order(Origin::signed(100), CLASS_ID, mask, other_dna, 40));
+        System::assert_last_event(Event::KittiesModule(crate::Event::BuyOrderPlaced(100, CLASS_ID, 1, 40)));
+
+        assert_noop!(KittiesModule::fill_order(Origin::signed(1000), CLASS_ID, 2, 0), Error::<Test>::UnknownOrder);
+        assert_noop!(KittiesModule::fill_order(Origin::signed(100), CLASS_ID, 1, 0), Error::<Test>::NotOwner);
+
+        // the kitty is sold for the highest price of the order
+        assert_ok!(KittiesModule::fill_order(Origin::signed(1000), CLASS_ID, 1, 0));
+        assert_eq!(KittiesModule::owner_of(CLASS_ID, 0), Some(100));
+        assert_eq!(Balances::free_balance(1000), 40);
+        assert_eq!(Balances::free_balance(100), 80 - 30 - 40);
+        assert_eq!(Balances::reserved_balance(100), 30);
+        System::assert_last_event(Event::KittiesModule(crate::Event::BuyOrderFilled(CLASS_ID, 1, 0)));
+        System::assert_has_event(Event::KittiesModule(crate::Event::KittySold(1000, 100, CLASS_ID, 0, 40)));
+
+        // a filled order is gone
+        assert_eq!(KittiesModule::buy_orders(CLASS_ID).into_iter().map(|(id, _)| id).collect::<Vec<_>>(), vec![0]);
+    });
+}
+
+#[test]
+fn listing_a_kitty_fills_the_best_buy_order() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(KittiesModule::create(Origin::signed(1000), CLASS_ID));
+        let _ = Balances::deposit_creating(&101, 100);
+
+        // orders with the same price are filled oldest first
+        assert_ok!(KittiesModule::place_buy_order(Origin::signed(100), CLASS_ID, [0; 16], [0; 16], 30));
+        assert_ok!(KittiesModule::place_buy_order(Origin::signed(101), CLASS_ID, [0; 16], [0; 16], 30));
+        assert_eq!(KittiesModule::buy_orders(CLASS_ID).into_iter().map(|(id, _)| id).collect::<Vec<_>>(), vec![0, 1]);
+
+        // a price above all orders just lists the kitty
+        assert_ok!(KittiesModule::set_price(Origin::signed(1000), CLASS_ID, 0, Some(50)));
+        assert_eq!(KittiesModule::owner_of(CLASS_ID, 0), Some(1000));
+
+        // the buyer only pays the asked price
+        assert_ok!(KittiesModule::set_price(Origin::signed(1000), CLASS_ID, 0, Some(25)));
+        assert_eq!(KittiesModule::owner_of(CLASS_ID, 0), Some(100));
+        assert_eq!(KittyPrices::<Test>::contains_key(CLASS_ID, 0), false);
+        assert_eq!(Balances::free_balance(100), 80 - 25);
+        assert_eq!(Balances::reserved_balance(100), 0);
+        assert_eq!(Balances::free_balance(1000), 25);
+        System::assert_last_event(Event::KittiesModule(crate::Event::BuyOrderFilled(CLASS_ID, 0, 0)));
+
+        // the own order of the seller is skipped
+        assert_ok!(KittiesModule::place_buy_order(Origin::signed(100), CLASS_ID, [0; 16], [0; 16], 40));
+        assert_ok!(KittiesModule::set_price(Origin::signed(100), CLASS_ID, 0, Some(30)));
+        assert_eq!(KittiesModule::owner_of(CLASS_ID, 0), Some(101));
+        System::assert_last_event(Event::KittiesModule(crate::Event::BuyOrderFilled(CLASS_ID, 1, 0)));
+    });
+}
+
+#[test]
+fn can_cancel_buy_orders() {
+    new_test_ext().execute_with(|| {
+        assert_noop!(KittiesModule::place_buy_order(Origin::signed(100), 1, [0; 16], [0; 16], 30), Error::<Test>::InvalidCollection);
+        assert_noop!(KittiesModule::place_buy_order(Origin::signed(100), CLASS_ID, [0; 16], [0; 16], 100), pallet_balances::Error::<Test, _>::InsufficientBalance);
+
+        assert_ok!(KittiesModule::place_buy_order(Origin::signed(100), CLASS_ID, [0; 16], [0; 16], 30));
+        assert_ok!(KittiesModule::place_buy_order(Origin::signed(100), CLASS_ID, [0; 16], [0; 16], 40));
+        // the order book of a collection is bounded
+        assert_noop!(KittiesModule::place_buy_order(Origin::signed(100), CLASS_ID, [0; 16], [0; 16], 30), Error::<Test>::TooManyBuyOrders);
+        assert_eq!(Balances::reserved_balance(100), 70);
+
+        // only the buyer can cancel an order
+        assert_noop!(KittiesModule::cancel_buy_order(Origin::signed(101), CLASS_ID, 0), Error::<Test>::NotOwner);
+        assert_ok!(KittiesModule::cancel_buy_order(Origin::signed(100), CLASS_ID, 0));
+        assert_eq!(Balances::reserved_balance(100), 40);
+        System::assert_last_event(Event::KittiesModule(crate::Event::BuyOrderCancelled(CLASS_ID, 0)));
+        assert_noop!(KittiesModule::cancel_buy_order(Origin::signed(100), CLASS_ID, 0), Error::<Test>::UnknownOrder);
+
+        assert_ok!(KittiesModule::cancel_buy_order(Origin::signed(100), CLASS_ID, 1));
+        assert_eq!(Balances::reserved_balance(100), 0);
+        assert_eq!(BuyOrders::<Test>::contains_key(CLASS_ID), false);
+    });
+}
+
+#[test]
+fn higher_buy_orders_evict_the_lowest_order() {
+    new_test_ext().execute_with(|| {
+        let _ = Balances::deposit_creating(&101, 100);
+
+        // orders have a minimum price
+        assert_noop!(KittiesModule::place_buy_order(Origin::signed(100), CLASS_ID, [0; 16], [0; 16], 9), Error::<Test>::PriceTooLow);
+
+        assert_ok!(KittiesModule::place_buy_order(Origin::signed(100), CLASS_ID, [0; 16], [0; 16], 30));
+        assert_ok!(KittiesModule::place_buy_order(Origin::signed(100), CLASS_ID, [0; 16], [0; 16], 20));
+
+        // a full order book only takes orders that pay more than its lowest order
+        assert_noop!(KittiesModule::place_buy_order(Origin::signed(101), CLASS_ID, [0; 16], [0; 16], 20), Error::<Test>::TooManyBuyOrders);
+
+        // the lowest order is cancelled and its funds are unreserved
+        assert_ok!(KittiesModule::place_buy_order(Origin::signed(101), CLASS_ID, [0; 16], [0; 16], 25));
+        System::assert_has_event(Event::KittiesModule(crate::Event::BuyOrderCancelled(CLASS_ID, 1)));
+        assert_eq!(KittiesModule::buy_orders(CLASS_ID).into_iter().map(|(id, _)| id).collect::<Vec<_>>(), vec![0, 2]);
+        assert_eq!(Balances::reserved_balance(100), 30);
+        assert_eq!(Balances::reserved_balance(101), 25);
+    });
+}
+
+#[test]
+fn can_sell_into_floor_bids() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(KittiesModule::create(Origin::signed(1000), CLASS_ID));
+        assert_ok!(KittiesModule::create(Origin::signed(1000), CLASS_ID));
+        assert_ok!(KittiesModule::create(Origin::signed(101), CLASS_ID));
+
+        assert_noop!(KittiesModule::place_floor_bid(Origin::signed(100), CLASS_ID, 10, 0), Error::<Test>::InvalidQuantity);
+        assert_noop!(KittiesModule::place_floor_bid(Origin::signed(100), CLASS_ID, 10, 9), pallet_balances::Error::<Test, _>::InsufficientBalance);
+
+        assert_ok!(KittiesModule::place_floor_bid(Origin::signed(100), CLASS_ID, 10, 2));
+        assert_ok!(KittiesModule::place_floor_bid(Origin::signed(100), CLASS_ID, 15, 1));
+        System::assert_last_event(Event::KittiesModule(crate::Event::FloorBidPlaced(100, CLASS_ID, 1, 15, 1)));
+        assert_eq!(Balances::reserved_balance(100), 35);
+        // the bids are sorted by their amount
+        assert_eq!(KittiesModule::floor_bids(CLASS_ID).into_iter().map(|(id, _)| id).collect::<Vec<_>>(), vec![1, 0]);
+        // and bounded
+        assert_noop!(KittiesModule::place_floor_bid(Origin::signed(100), CLASS_ID, 10, 1), Error::<Test>::TooManyFloorBids);
+
+        assert_noop!(KittiesModule::sell_into_bid(Origin::signed(100), CLASS_ID, 0, 0), Error::<Test>::NotOwner);
+        assert_noop!(KittiesModule::sell_into_bid(Origin::signed(1000), CLASS_ID, 2, 0), Error::<Test>::UnknownBid);
+
+        // any kitty can be sold into a bid, which is filled partially
+        assert_ok!(KittiesModule::sell_into_bid(Origin::signed(1000), CLASS_ID, 0, 1));
+        assert_eq!(KittiesModule::owner_of(CLASS_ID, 1), Some(100));
+        assert_eq!(Balances::free_balance(1000), 10);
+        assert_eq!(Balances::reserved_balance(100), 25);
+        System::assert_last_event(Event::KittiesModule(crate::Event::FloorBidFilled(CLASS_ID, 0, 1, 1)));
+        System::assert_has_event(Event::KittiesModule(crate::Event::KittySold(1000, 100, CLASS_ID, 1, 10)));
+        // the bidder can not sell into its own bid
+        assert_noop!(KittiesModule::sell_into_bid(Origin::signed(100), CLASS_ID, 0, 1), Error::<Test>::BuyFromSelf);
+
+        // a bid is removed when it is filled
+        assert_ok!(KittiesModule::sell_into_bid(Origin::signed(101), CLASS_ID, 0, 2));
+        assert_eq!(KittiesModule::owner_of(CLASS_ID, 2), Some(100));
+        assert_eq!(Balances::reserved_balance(100), 15);
+        assert_eq!(KittiesModule::floor_bids(CLASS_ID).into_iter().map(|(id, _)| id).collect::<Vec<_>>(), vec![1]);
+        assert_noop!(KittiesModule::sell_into_bid(Origin::signed(1000), CLASS_ID, 0, 0), Error::<Test>::UnknownBid);
+    });
+}
+
+#[test]
+fn can_cancel_floor_bids() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(KittiesModule::create(Origin::signed(1000), CLASS_ID));
+        assert_ok!(KittiesModule::place_floor_bid(Origin::signed(100), CLASS_ID, 10, 3));
+        assert_ok!(KittiesModule::sell_into_bid(Origin::signed(1000), CLASS_ID, 0, 0));
+        assert_eq!(Balances::reserved_balance(100), 20);
+
+        // only the bidder can cancel a bid, which refunds the remaining quantity
+        assert_noop!(KittiesModule::cancel_floor_bid(Origin::signed(1000), CLASS_ID, 0), Error::<Test>::NotOwner);
+        assert_ok!(KittiesModule::cancel_floor_bid(Origin::signed(100), CLASS_ID, 0));
+        assert_eq!(Balances::reserved_balance(100), 0);
+        assert_eq!(Balances::free_balance(100), 70);
+        System::assert_last_event(Event::KittiesModule(crate::Event::FloorBidCancelled(CLASS_ID, 0, 20)));
+
+        assert_eq!(FloorBids::<Test>::contains_key(CLASS_ID), false);
+        assert_noop!(KittiesModule::cancel_floor_bid(Origin::signed(100), CLASS_ID, 0), Error::<Test>::UnknownBid);
+    });
+}
+
+#[test]
+fn higher_floor_bids_evict_the_lowest_bid() {
+    new_test_ext().execute_with(|| {
+        let _ = Balances::deposit_creating(&101, 100);
+
+        // bids have a minimum amount
+        assert_noop!(KittiesModule::place_floor_bid(Origin::signed(100), CLASS_ID, 9, 1), Error::<Test>::PriceTooLow);
+
+        assert_ok!(KittiesModule::place_floor_bid(Origin::signed(100), CLASS_ID, 20, 1));
+        assert_ok!(KittiesModule::place_floor_bid(Origin::signed(100), CLASS_ID, 10, 3));
+
+        // a full order book only takes bids that are higher than its lowest bid
+        assert_noop!(KittiesModule::place_floor_bid(Origin::signed(101), CLASS_ID, 10, 1), Error::<Test>::TooManyFloorBids);
+
+        // the lowest bid is cancelled and its funds are unreserved
+        assert_ok!(KittiesModule::place_floor_bid(Origin::signed(101), CLASS_ID, 11, 1));
+        System::assert_has_event(Event::KittiesModule(crate::Event::FloorBidCancelled(CLASS_ID, 1, 30)));
+        assert_eq!(KittiesModule::floor_bids(CLASS_ID).into_iter().map(|(id, _)| id).collect::<Vec<_>>(), vec![0, 2]);
+        assert_eq!(Balances::reserved_balance(100), 20);
+        assert_eq!(Balances::reserved_balance(101), 11);
+    });
+}
+
+#[test]
+fn can_buy_in_instalments() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(KittiesModule::create(Origin::signed(1000), CLASS_ID));
+        assert_noop!(KittiesModule::start_layaway(Origin::signed(100), 1000, CLASS_ID, 0, 50), Error::<Test>::NotForSale);
+
+        assert_ok!(KittiesModule::set_price(Origin::signed(1000), CLASS_ID, 0, Some(50)));
+        assert_noop!(KittiesModule::start_layaway(Origin::signed(100), 1000, CLASS_ID, 0, 49), Error::<Test>::PriceTooLow);
+        assert_noop!(KittiesModule::start_layaway(Origin::signed(100), 101, CLASS_ID, 0, 50), Error::<Test>::NotOwner);
+
+        // a fifth of the price is paid as deposit
+        assert_ok!(KittiesModule::start_layaway(Origin::signed(100), 1000, CLASS_ID, 0, 50));
+        System::assert_last_event(Event::KittiesModule(crate::Event::LayawayStarted(100, CLASS_ID, 0, 50, 10, 11)));
+        assert_eq!(Balances::reserved_balance(100), 10);
+
+        // the kitty is locked and no longer for sale
+        assert_eq!(KittiesModule::kitty_lock(CLASS_ID, 0), Some(LockReason::Layaway));
+        assert_eq!(KittyPrices::<Test>::contains_key(CLASS_ID, 0), false);
+        assert_noop!(KittiesModule::transfer(Origin::signed(1000), 101, CLASS_ID, 0), Error::<Test>::KittyLocked);
+        assert_noop!(KittiesModule::start_layaway(Origin::signed(101), 1000, CLASS_ID, 0, 50), Error::<Test>::NotForSale);
+
+        // only the buyer pays the instalments
+        assert_noop!(KittiesModule::pay_instalment(Origin::signed(101), CLASS_ID, 0, 10), Error::<Test>::NotOwner);
+        assert_ok!(KittiesModule::pay_instalment(Origin::signed(100), CLASS_ID, 0, 30));
+        System::assert_last_event(Event::KittiesModule(crate::Event::InstalmentPaid(CLASS_ID, 0, 30, 10)));
+        assert_eq!(KittiesModule::owner_of(CLASS_ID, 0), Some(1000));
+
+        // the blocklist is only checked when the layaway starts, so a buyer that is blocked
+        // afterwards can still finish paying
+        assert_ok!(KittiesModule::block_account(Origin::root(), 100, b"phishing".to_vec(), None));
+
+        // the kitty is sold with the final payment, without charging more than the price
+        System::set_block_number(11);
+        assert_ok!(KittiesModule::pay_instalment(Origin::signed(100), CLASS_ID, 0, 20));
+        System::assert_has_event(Event::KittiesModule(crate::Event::InstalmentPaid(CLASS_ID, 0, 10, 0)));
+        System::assert_last_event(Event::KittiesModule(crate::Event::KittySold(1000, 100, CLASS_ID, 0, 50)));
+        assert_eq!(KittiesModule::owner_of(CLASS_ID, 0), Some(100));
+        assert_eq!(KittiesModule::kitty_lock(CLASS_ID, 0), None);
+        assert_eq!(KittiesModule::layaway(CLASS_ID, 0), None);
+        assert_eq!(Balances::reserved_balance(100), 0);
+        assert_eq!(Balances::free_balance(100), 30);
+        assert_eq!(Balances::free_balance(1000), 50);
+    });
+}
+
+#[test]
+fn defaulted_layaways_restore_the_listing() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(KittiesModule::create(Origin::signed(1000), CLASS_ID));
+        assert_ok!(KittiesModule::set_price(Origin::signed(1000), CLASS_ID, 0, Some(50)));
+        assert_ok!(KittiesModule::start_layaway(Origin::signed(100), 1000, CLASS_ID, 0, 50));
+        assert_ok!(KittiesModule::pay_instalment(Origin::signed(100), CLASS_ID, 0, 20));
+
+        assert_noop!(KittiesModule::claim_layaway_default(Origin::signed(101), CLASS_ID, 0), Error::<Test>::LayawayNotExpired);
+
+        // the buyer can not pay after the deadline
+        System::set_block_number(12);
+        assert_noop!(KittiesModule::pay_instalment(Origin::signed(100), CLASS_ID, 0, 20), Error::<Test>::LayawayExpired);
+
+        // the seller keeps half of the deposit, the rest is refunded
+        assert_ok!(KittiesModule::claim_layaway_default(Origin::signed(101), CLASS_ID, 0));
+        System::assert_last_event(Event::KittiesModule(crate::Event::LayawayDefaulted(CLASS_ID, 0, 5)));
+        assert_eq!(Balances::reserved_balance(100), 0);
+        assert_eq!(Balances::free_balance(100), 75);
+        assert_eq!(Balances::free_balance(1000), 5);
+
+        // the kitty is unlocked and listed for its old price again
+        assert_eq!(KittiesModule::owner_of(CLASS_ID, 0), Some(1000));
+        assert_eq!(KittiesModule::kitty_lock(CLASS_ID, 0), None);
+        assert_eq!(KittiesModule::kitty_prices(CLASS_ID, 0), Some(50));
+        assert_eq!(KittiesModule::listings(CLASS_ID, 0, 10), vec![(0, 50)]);
+        assert_noop!(KittiesModule::claim_layaway_default(Origin::signed(101), CLASS_ID, 0), Error::<Test>::NoLayaway);
+    });
+}
+
+#[test]
+fn can_borrow_against_a_kitty() {
+    new_test_ext().execute_with(|| {
+        let escrow = KittiesModule::loan_account(0);
+        assert_ok!(KittiesModule::create(Origin::signed(1000), CLASS_ID));
+
+        assert_noop!(KittiesModule::request_loan(Origin::signed(1000), CLASS_ID, 0, 0, 5, 10), Error::<Test>::InvalidLoanTerms);
+        assert_noop!(KittiesModule::request_loan(Origin::signed(100), CLASS_ID, 0, 50, 5, 10), Error::<Test>::NotOwner);
+
+        // the kitty is escrowed in the account of the loan
+        assert_ok!(KittiesModule::request_loan(Origin::signed(1000), CLASS_ID, 0, 50, 5, 10));
+        System::assert_last_event(Event::KittiesModule(crate::Event::LoanRequested(1000, CLASS_ID, 0, 50, 5, 10)));
+        assert_eq!(KittiesModule::owner_of(CLASS_ID, 0), Some(escrow));
+        assert_eq!(KittiesModule::loan(CLASS_ID, 0).map(|loan| loan.id), Some(0));
+        assert_noop!(KittiesModule::transfer(Origin::signed(1000), 100, CLASS_ID, 0), Error::<Test>::NotOwner);
+
+        // the borrower gets the principal right away
+        assert_noop!(KittiesModule::fund_loan(Origin::signed(1000), CLASS_ID, 0), Error::<Test>::BuyFromSelf);
+        assert_ok!(KittiesModule::fund_loan(Origin::signed(100), CLASS_ID, 0));
+        System::assert_last_event(Event::KittiesModule(crate::Event::LoanFunded(100, CLASS_ID, 0, 11)));
+        assert_eq!(Balances::free_balance(1000), 50);
+        assert_noop!(KittiesModule::fund_loan(Origin::signed(101), CLASS_ID, 0), Error::<Test>::LoanFunded);
+        assert_noop!(KittiesModule::cancel_loan(Origin::signed(1000), CLASS_ID, 0), Error::<Test>::LoanFunded);
+        assert_noop!(KittiesModule::claim_collateral(Origin::signed(100), CLASS_ID, 0), Error::<Test>::LoanNotExpired);
+
+        // repaying the loan with its interest returns the kitty
+        let _ = Balances::deposit_creating(&1000, 10);
+        assert_noop!(KittiesModule::repay_loan(Origin::signed(100), CLASS_ID, 0), Error::<Test>::NotOwner);
+        assert_ok!(KittiesModule::repay_loan(Origin::signed(1000), CLASS_ID, 0));
+        System::assert_last_event(Event::KittiesModule(crate::Event::LoanRepaid(CLASS_ID, 0, 55)));
+        assert_eq!(KittiesModule::owner_of(CLASS_ID, 0), Some(1000));
+        assert_eq!(KittiesModule::ownership_history(CLASS_ID, 0).last(), Some(&OwnershipRecord {
+            from: Some(escrow), to: 1000, block: 1, kind: OwnershipChange::Transfer,
+        }));
+        assert_eq!(Balances::free_balance(1000), 5);
+        assert_eq!(Balances::free_balance(100), 85);
+        assert_eq!(KittiesModule::loan(CLASS_ID, 0), None);
+
+        // a loan request that is not funded can be cancelled, every loan has its own escrow
+        assert_ok!(KittiesModule::request_loan(Origin::signed(1000), CLASS_ID, 0, 50, 5, 10));
+        assert_eq!(KittiesModule::owner_of(CLASS_ID, 0), Some(KittiesModule::loan_account(1)));
+        assert_noop!(KittiesModule::cancel_loan(Origin::signed(100), CLASS_ID, 0), Error::<Test>::NotOwner);
+        assert_ok!(KittiesModule::cancel_loan(Origin::signed(1000), CLASS_ID, 0));
+        System::assert_last_event(Event::KittiesModule(crate::Event::LoanCancelled(CLASS_ID, 0)));
+        assert_eq!(KittiesModule::owner_of(CLASS_ID, 0), Some(1000));
+        assert_noop!(KittiesModule::cancel_loan(Origin::signed(1000), CLASS_ID, 0), Error::<Test>::NoLoan);
+    });
+}
+
+#[test]
+fn lenders_claim_the_kitty_of_unpaid_loans() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(KittiesModule::create(Origin::signed(1000), CLASS_ID));
+        assert_ok!(KittiesModule::request_loan(Origin::signed(1000), CLASS_ID, 0, 50, 5, 10));
+        assert_noop!(KittiesModule::claim_collateral(Origin::signed(100), CLASS_ID, 0), Error::<Test>::LoanNotFunded);
+        assert_ok!(KittiesModule::fund_loan(Origin::signed(100), CLASS_ID, 0));
+
+        System::set_block_number(12);
+        assert_noop!(KittiesModule::repay_loan(Origin::signed(1000), CLASS_ID, 0), Error::<Test>::LoanExpired);
+        // only the lender can claim the kitty
+        assert_noop!(KittiesModule::claim_collateral(Origin::signed(101), CLASS_ID, 0), Error::<Test>::NotOwner);
+
+        assert_ok!(KittiesModule::claim_collateral(Origin::signed(100), CLASS_ID, 0));
+        System::assert_last_event(Event::KittiesModule(crate::Event::CollateralClaimed(100, CLASS_ID, 0)));
+        assert_eq!(KittiesModule::owner_of(CLASS_ID, 0), Some(100));
+        assert_eq!(KittiesModule::ownership_history(CLASS_ID, 0).last(), Some(&OwnershipRecord {
+            from: Some(KittiesModule::loan_account(0)), to: 100, block: 12, kind: OwnershipChange::Transfer,
+        }));
+        assert_eq!(KittiesModule::loan(CLASS_ID, 0), None);
+    });
+}
+
+#[test]
+fn can_fractionalise_and_redeem_a_kitty() {
+    new_test_ext().execute_with(|| {
+        let vault = KittiesModule::vault_account(0);
+        assert_ok!(KittiesModule::create(Origin::signed(1000), CLASS_ID));
+
+        assert_noop!(KittiesModule::fractionalise(Origin::signed(1000), CLASS_ID, 0, 0, 60), Error::<Test>::InvalidShares);
+        assert_noop!(KittiesModule::fractionalise(Origin::signed(1000), CLASS_ID, 0, 10, 0), Error::<Test>::PriceTooLow);
+        assert_noop!(KittiesModule::fractionalise(Origin::signed(100), CLASS_ID, 0, 10, 60), Error::<Test>::NotOwner);
+
+        // the kitty is escrowed in the account of the vault and its owner gets all shares
+        assert_ok!(KittiesModule::fractionalise(Origin::signed(1000), CLASS_ID, 0, 10, 60));
+        System::assert_last_event(Event::KittiesModule(crate::Event::KittyFractionalised(1000, CLASS_ID, 0, 0, 10, 60)));
+        assert_eq!(KittiesModule::owner_of(CLASS_ID, 0), Some(vault));
+        assert_eq!(KittiesModule::ownership_history(CLASS_ID, 0).last().map(|record| (record.from, record.to)), Some((Some(1000), vault)));
+        assert_eq!(KittiesModule::kitty_shares(CLASS_ID, 0), Some(0));
+        assert_eq!(Assets::balance(0, 1000), 10);
+
+        // the shares can be traded with pallet_assets
+        assert_ok!(Assets::transfer(Origin::signed(1000), 0, 101, 4));
+
+        // the kitty can only be redeemed with all shares
+        assert_noop!(KittiesModule::redeem(Origin::signed(1000), 0), Error::<Test>::NotAllShares);
+        assert_noop!(KittiesModule::redeem(Origin::signed(1000), 1), Error::<Test>::NoVault);
+        assert_ok!(Assets::transfer(Origin::signed(101), 0, 1000, 4));
+        assert_ok!(KittiesModule::redeem(Origin::signed(1000), 0));
+        System::assert_last_event(Event::KittiesModule(crate::Event::KittyRedeemed(1000, CLASS_ID, 0)));
+        assert_eq!(KittiesModule::owner_of(CLASS_ID, 0), Some(1000));
+        assert_eq!(KittiesModule::ownership_history(CLASS_ID, 0).last().map(|record| (record.from, record.to)), Some((Some(vault), 1000)));
+        assert!(!<Assets as SharesBackend<u64, u64>>::exists(0));
+        assert_eq!(KittiesModule::vault(0), None);
+        assert_eq!(KittiesModule::kitty_shares(CLASS_ID, 0), None);
+
+        // a new vault gets a new asset class, and the ids that were taken with the assets pallet
+        // are skipped
+        assert_ok!(Assets::force_create(Origin::root(), 1, 1000, true, 1));
+        assert_ok!(KittiesModule::fractionalise(Origin::signed(1000), CLASS_ID, 0, 10, 60));
+        assert_eq!(KittiesModule::kitty_shares(CLASS_ID, 0), Some(2));
+        assert_eq!(KittiesModule::owner_of(CLASS_ID, 0), Some(KittiesModule::vault_account(2)));
+    });
+}
+
+#[test]
+fn holders_share_the_buyout_price() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(KittiesModule::create(Origin::signed(1000), CLASS_ID));
+        assert_ok!(KittiesModule::fractionalise(Origin::signed(1000), CLASS_ID, 0, 3, 10));
+        assert_ok!(Assets::transfer(Origin::signed(1000), 0, 101, 2));
+
+        assert_noop!(KittiesModule::claim_proceeds(Origin::signed(1000), 0), Error::<Test>::NotBoughtOut);
+
+        // the buyer gets the kitty right away
+        assert_ok!(KittiesModule::buyout(Origin::signed(100), 0));
+        System::assert_last_event(Event::KittiesModule(crate::Event::KittyBoughtOut(100, CLASS_ID, 0, 10)));
+        assert_eq!(KittiesModule::owner_of(CLASS_ID, 0), Some(100));
+        assert_eq!(Balances::free_balance(100), 70);
+        assert_eq!(KittiesModule::vault(0).and_then(|vault| vault.proceeds), Some(10));
+        assert_eq!(KittiesModule::kitty_shares(CLASS_ID, 0), None);
+        assert_noop!(KittiesModule::buyout(Origin::signed(100), 0), Error::<Test>::BoughtOut);
+        assert_noop!(KittiesModule::redeem(Origin::signed(1000), 0), Error::<Test>::BoughtOut);
+
+        // the holders claim their part of the price, the last one gets what is left after
+        // rounding
+        assert_noop!(KittiesModule::claim_proceeds(Origin::signed(100), 0), Error::<Test>::NoShares);
+        assert_ok!(KittiesModule::claim_proceeds(Origin::signed(1000), 0));
+        System::assert_last_event(Event::KittiesModule(crate::Event::ProceedsClaimed(1000, 0, 1, 3)));
+        assert_eq!(Balances::free_balance(1000), 3);
+        assert_eq!(Assets::balance(0, 1000), 0);
+
+        assert_ok!(KittiesModule::claim_proceeds(Origin::signed(101), 0));
+        System::assert_last_event(Event::KittiesModule(crate::Event::ProceedsClaimed(101, 0, 2, 7)));
+        assert_eq!(Balances::free_balance(101), 7);
+        assert_eq!(KittiesModule::vault(0), None);
+        assert!(!<Assets as SharesBackend<u64, u64>>::exists(0));
+    });
+}
+
+#[test]
+fn no_proceeds_are_lost_to_the_existential_deposit() {
+    new_test_ext().execute_with(|| {
+        ExistentialDeposit::set(5);
+        let _ = Balances::deposit_creating(&1000, 5);
+        let _ = Balances::deposit_creating(&101, 5);
+
+        assert_ok!(KittiesModule::create(Origin::signed(1000), CLASS_ID));
+        assert_ok!(KittiesModule::fractionalise(Origin::signed(1000), CLASS_ID, 0, 3, 10));
+        assert_ok!(Assets::transfer(Origin::signed(1000), 0, 101, 1));
+        assert_ok!(Assets::transfer(Origin::signed(1000), 0, 102, 1));
+        assert_ok!(KittiesModule::buyout(Origin::signed(100), 0));
+
+        // the proceeds that are left drop below the existential deposit
+        assert_ok!(KittiesModule::claim_proceeds(Origin::signed(1000), 0));
+        assert_ok!(KittiesModule::claim_proceeds(Origin::signed(101), 0));
+        assert_eq!(Balances::free_balance(1000), 8);
+        assert_eq!(Balances::free_balance(101), 8);
+        assert_eq!(KittiesModule::vault(0).and_then(|vault| vault.proceeds), Some(4));
+
+        // an account without funds can not take less than the existential deposit, but it keeps
+        // its claim
+        assert_noop!(KittiesModule::claim_proceeds(Origin::signed(102), 0), Error::<Test>::ProceedsBelowExistentialDeposit);
+        let _ = Balances::deposit_creating(&102, 5);
+        assert_ok!(KittiesModule::claim_proceeds(Origin::signed(102), 0));
+        System::assert_last_event(Event::KittiesModule(crate::Event::ProceedsClaimed(102, 0, 1, 4)));
+        assert_eq!(Balances::free_balance(102), 9);
+        assert_eq!(KittiesModule::vault(0), None);
+        assert!(!<Assets as SharesBackend<u64, u64>>::exists(0));
     });
 }
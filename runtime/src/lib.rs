@@ -7,7 +7,7 @@
 include!(concat!(env!("OUT_DIR"), "/wasm_binary.rs"));
 
 use sp_std::prelude::*;
-use sp_core::{crypto::KeyTypeId, OpaqueMetadata};
+use sp_core::{crypto::KeyTypeId, OpaqueMetadata, u32_trait::{_1, _2}};
 use sp_runtime::{
 	ApplyExtrinsicResult, generic, create_runtime_str, impl_opaque_keys, MultiSignature,
 	transaction_validity::{TransactionValidity, TransactionSource},
@@ -30,14 +30,15 @@ pub use pallet_timestamp::Call as TimestampCall;
 pub use pallet_balances::Call as BalancesCall;
 pub use sp_runtime::{Permill, Perbill};
 pub use frame_support::{
-	construct_runtime, parameter_types, StorageValue,
-	traits::{KeyOwnerProofSystem, Randomness},
+	construct_runtime, parameter_types, StorageValue, PalletId,
+	traits::{Filter, KeyOwnerProofSystem, Randomness},
 	weights::{
 		Weight, IdentityFee,
 		constants::{BlockExecutionWeight, ExtrinsicBaseWeight, RocksDbWeight, WEIGHT_PER_SECOND},
 	},
 };
 use pallet_transaction_payment::CurrencyAdapter;
+use frame_system::{EnsureOneOf, EnsureRoot};
 
 mod weights;
 
@@ -60,6 +61,12 @@ pub type Balance = u128;
 /// Index of a transaction in the chain.
 pub type Index = u32;
 
+/// Id of a collection of kitties (the class id of the collection in orml_nft).
+pub type CollectionId = u32;
+
+/// Index of a kitty (the token id of the kitty in orml_nft).
+pub type KittyIndex = u32;
+
 /// A hash of some data used by the chain.
 pub type Hash = sp_core::H256;
 
@@ -144,11 +151,25 @@ parameter_types! {
 	pub const SS58Prefix: u8 = 42;
 }
 
+/// Rejects the calls that are paused by the kitties pallet. The signed ones are rejected before
+/// they pay a fee by `CheckKittiesPaused`, this catches the ones dispatched in other ways, like
+/// from a council motion.
+pub struct BaseFilter;
+
+impl Filter<Call> for BaseFilter {
+	fn filter(call: &Call) -> bool {
+		match call {
+			Call::Kitties(call) => !Kitties::is_call_paused(call),
+			_ => true,
+		}
+	}
+}
+
 // Configure FRAME pallets to include in runtime.
 
 impl frame_system::Config for Runtime {
 	/// The basic call filter to use in dispatchable.
-	type BaseCallFilter = ();
+	type BaseCallFilter = BaseFilter;
 	/// Block & extrinsics weights: base values and limits.
 	type BlockWeights = BlockWeights;
 	/// The maximum length of a block (in bytes).
@@ -264,9 +285,106 @@ impl pallet_transaction_payment::Config for Runtime {
 	type FeeMultiplierUpdate = ();
 }
 
-impl pallet_sudo::Config for Runtime {
+parameter_types! {
+	pub const CouncilMotionDuration: BlockNumber = 3 * DAYS;
+	pub const CouncilMaxProposals: u32 = 100;
+	pub const CouncilMaxMembers: u32 = 100;
+}
+
+type CouncilCollective = pallet_collective::Instance1;
+
+type CouncilMember = pallet_collective::EnsureMember<AccountId, CouncilCollective>;
+type HalfCouncil = pallet_collective::EnsureProportionMoreThan<_1, _2, AccountId, CouncilCollective>;
+type WholeCouncil = pallet_collective::EnsureProportionAtLeast<_1, _1, AccountId, CouncilCollective>;
+
+/// Root (a democracy referendum) or more than half of the council
+type EnsureRootOrHalfCouncil = EnsureOneOf<AccountId, EnsureRoot<AccountId>, HalfCouncil>;
+
+impl pallet_collective::Config<CouncilCollective> for Runtime {
+	type Origin = Origin;
+	type Proposal = Call;
 	type Event = Event;
+	type MotionDuration = CouncilMotionDuration;
+	type MaxProposals = CouncilMaxProposals;
+	type MaxMembers = CouncilMaxMembers;
+	type DefaultVote = pallet_collective::PrimeDefaultVote;
+	type WeightInfo = pallet_collective::weights::SubstrateWeight<Runtime>;
+}
+
+/// The members of the council are managed by the council itself.
+impl pallet_membership::Config<pallet_membership::Instance1> for Runtime {
+	type Event = Event;
+	type AddOrigin = EnsureRootOrHalfCouncil;
+	type RemoveOrigin = EnsureRootOrHalfCouncil;
+	type SwapOrigin = EnsureRootOrHalfCouncil;
+	type ResetOrigin = EnsureRootOrHalfCouncil;
+	type PrimeOrigin = EnsureRootOrHalfCouncil;
+	type MembershipInitialized = Council;
+	type MembershipChanged = Council;
+	type MaxMembers = CouncilMaxMembers;
+	type WeightInfo = pallet_membership::weights::SubstrateWeight<Runtime>;
+}
+
+parameter_types! {
+	pub MaximumSchedulerWeight: Weight = Perbill::from_percent(80) * BlockWeights::get().max_block;
+	pub const MaxScheduledPerBlock: u32 = 50;
+}
+
+/// Lets the council schedule calls, for example to unpause the kitties at a given block.
+impl pallet_scheduler::Config for Runtime {
+	type Event = Event;
+	type Origin = Origin;
+	type PalletsOrigin = OriginCaller;
 	type Call = Call;
+	type MaximumWeight = MaximumSchedulerWeight;
+	type ScheduleOrigin = EnsureRootOrHalfCouncil;
+	type MaxScheduledPerBlock = MaxScheduledPerBlock;
+	type WeightInfo = pallet_scheduler::weights::SubstrateWeight<Runtime>;
+}
+
+parameter_types! {
+	pub const LaunchPeriod: BlockNumber = 7 * DAYS;
+	pub const VotingPeriod: BlockNumber = 7 * DAYS;
+	pub const FastTrackVotingPeriod: BlockNumber = 3 * HOURS;
+	pub const InstantAllowed: bool = true;
+	pub const MinimumDeposit: Balance = 100_000;
+	pub const EnactmentPeriod: BlockNumber = 1 * DAYS;
+	pub const CooloffPeriod: BlockNumber = 7 * DAYS;
+	pub const PreimageByteDeposit: Balance = 1_000;
+	pub const MaxVotes: u32 = 100;
+	pub const MaxProposals: u32 = 100;
+}
+
+/// Referenda are the only way to dispatch as Root, for example to upgrade the runtime. The
+/// council tables them, and a unanimous council can fast track or enact them instantly.
+impl pallet_democracy::Config for Runtime {
+	type Proposal = Call;
+	type Event = Event;
+	type Currency = Balances;
+	type EnactmentPeriod = EnactmentPeriod;
+	type LaunchPeriod = LaunchPeriod;
+	type VotingPeriod = VotingPeriod;
+	type MinimumDeposit = MinimumDeposit;
+	type ExternalOrigin = HalfCouncil;
+	type ExternalMajorityOrigin = HalfCouncil;
+	type ExternalDefaultOrigin = WholeCouncil;
+	type FastTrackOrigin = WholeCouncil;
+	type InstantOrigin = WholeCouncil;
+	type InstantAllowed = InstantAllowed;
+	type FastTrackVotingPeriod = FastTrackVotingPeriod;
+	type CancellationOrigin = EnsureRootOrHalfCouncil;
+	type CancelProposalOrigin = EnsureRootOrHalfCouncil;
+	type BlacklistOrigin = EnsureRootOrHalfCouncil;
+	type VetoOrigin = CouncilMember;
+	type CooloffPeriod = CooloffPeriod;
+	type PreimageByteDeposit = PreimageByteDeposit;
+	type OperationalPreimageOrigin = CouncilMember;
+	type Slash = ();
+	type Scheduler = Scheduler;
+	type PalletsOrigin = OriginCaller;
+	type MaxVotes = MaxVotes;
+	type WeightInfo = pallet_democracy::weights::SubstrateWeight<Runtime>;
+	type MaxProposals = MaxProposals;
 }
 
 /// Configure the pallet-template in pallets/template.
@@ -276,32 +394,90 @@ impl pallet_template::Config for Runtime {
 
 parameter_types! {
     pub const DefaultDifficulty: u32 = 10;
+    pub const BreedCooldown: BlockNumber = 5 * MINUTES;
+    pub const MaxNameLength: u32 = 32;
+    pub const MaxMetadataLength: u32 = 256;
+    pub const DepositPerByte: Balance = 1_000;
+    pub const MaxReasonLength: u32 = 256;
+    pub const MaxHistoryLength: u32 = 20;
+    pub const MaxBuyOrders: u32 = 50;
+    pub const MinOrderPrice: Balance = 100_000;
+    pub const MaxFloorBids: u32 = 50;
+    pub const LayawayDeposit: Perbill = Perbill::from_percent(20);
+    pub const LayawayPeriod: BlockNumber = 7 * DAYS;
+    pub const LayawayForfeit: Perbill = Perbill::from_percent(50);
+    pub const KittiesPalletId: PalletId = PalletId(*b"py/kitty");
 }
 impl pallet_kitties::Config for Runtime {
     type Event = Event;
+    type Nft = Nft;
     type Randomness = RandomnessCollectiveFlip;
     type Currency = Balances;
+    // the kitties are administered by council motions
+    type CollectionOrigin = EnsureRootOrHalfCouncil;
+    type AdminOrigin = EnsureRootOrHalfCouncil;
     type WeightInfo = weights::pallet_kitties::WeightInfo<Runtime>;
+    type PalletId = KittiesPalletId;
+    type Shares = Assets;
     type DefaultDifficulty = DefaultDifficulty;
+    type BreedCooldown = BreedCooldown;
+    type MaxNameLength = MaxNameLength;
+    type MaxMetadataLength = MaxMetadataLength;
+    type DepositPerByte = DepositPerByte;
+    type MaxReasonLength = MaxReasonLength;
+    type MaxHistoryLength = MaxHistoryLength;
+    type MaxBuyOrders = MaxBuyOrders;
+    type MaxFloorBids = MaxFloorBids;
+    type MinOrderPrice = MinOrderPrice;
+    type LayawayDeposit = LayawayDeposit;
+    type LayawayPeriod = LayawayPeriod;
+    type LayawayForfeit = LayawayForfeit;
 }
 
 parameter_types! {
     // since class and token metadata is not used, set their max size to 0
+    // (the names and metadata of the kitties are stored by pallet_kitties, which also takes
+    // a deposit for them)
     pub const MaxClassMetadata: u32 = 0;
     pub const MaxTokenMetadata: u32 = 0;
 }
 
 impl orml_nft::Config for Runtime {
-    type ClassId = u32;
-    type TokenId = u32;
-    // since class data is not used, set it equal to ()  (the unit type)
-    type ClassData = ();
+    type ClassId = CollectionId;
+    type TokenId = KittyIndex;
+    // the class data holds the settings of a collection of kitties
+    type ClassData = pallet_kitties::KittyClassData;
     // token data is the kitty
     type TokenData = pallet_kitties::Kitty;
     type MaxClassMetadata = MaxClassMetadata;
     type MaxTokenMetadata = MaxTokenMetadata;
 }
 
+parameter_types! {
+    pub const AssetDeposit: Balance = 100_000;
+    pub const ApprovalDeposit: Balance = 1_000;
+    pub const StringLimit: u32 = 50;
+    pub const MetadataDepositBase: Balance = 10_000;
+    pub const MetadataDepositPerByte: Balance = 1_000;
+}
+
+// holds the shares of fractionalised kitties
+impl pallet_assets::Config for Runtime {
+    type Event = Event;
+    type Balance = Balance;
+    type AssetId = u32;
+    type Currency = Balances;
+    type ForceOrigin = EnsureRootOrHalfCouncil;
+    type AssetDeposit = AssetDeposit;
+    type MetadataDepositBase = MetadataDepositBase;
+    type MetadataDepositPerByte = MetadataDepositPerByte;
+    type ApprovalDeposit = ApprovalDeposit;
+    type StringLimit = StringLimit;
+    type Freezer = ();
+    type Extra = ();
+    type WeightInfo = pallet_assets::weights::SubstrateWeight<Runtime>;
+}
+
 // define the types for SendTransaction
 impl<C> frame_system::offchain::SendTransactionTypes<C> for Runtime
     where Call: From<C>, {
@@ -324,12 +500,16 @@ construct_runtime!(
 		Grandpa: pallet_grandpa::{Pallet, Call, Storage, Config, Event},
 		Balances: pallet_balances::{Pallet, Call, Storage, Config<T>, Event<T>},
 		TransactionPayment: pallet_transaction_payment::{Pallet, Storage},
-		Sudo: pallet_sudo::{Pallet, Call, Config<T>, Storage, Event<T>},
+		Council: pallet_collective::<Instance1>::{Pallet, Call, Storage, Origin<T>, Event<T>, Config<T>},
+		CouncilMembership: pallet_membership::<Instance1>::{Pallet, Call, Storage, Event<T>, Config<T>},
+		Scheduler: pallet_scheduler::{Pallet, Call, Storage, Event<T>},
+		Democracy: pallet_democracy::{Pallet, Call, Storage, Config<T>, Event<T>},
 		// Include the custom logic from the pallet-template in the runtime.
 		TemplateModule: pallet_template::{Pallet, Call, Storage, Event<T>},
         // Substrate Kitties pallet
-        Kitties: pallet_kitties::{Pallet, Storage, Call, Event<T>, Config, ValidateUnsigned},   // ValidateUnsigned to let the runtime know that the pallet accepts unsigned transactions
+        Kitties: pallet_kitties::{Pallet, Storage, Call, Event<T>, Config<T>, ValidateUnsigned},   // ValidateUnsigned to let the runtime know that the pallet accepts unsigned transactions
         Nft: orml_nft::{Pallet, Storage, Config<T>},
+        Assets: pallet_assets::{Pallet, Call, Storage, Event<T>},
 	}
 );
 
@@ -347,6 +527,8 @@ pub type SignedExtra = (
 	frame_system::CheckEra<Runtime>,
 	frame_system::CheckNonce<Runtime>,
 	frame_system::CheckWeight<Runtime>,
+	// rejects the paused kitty calls before the fee is charged
+	pallet_kitties::CheckKittiesPaused<Runtime>,
 	pallet_transaction_payment::ChargeTransactionPayment<Runtime>
 );
 /// Unchecked extrinsic type as expected by this runtime.
@@ -358,6 +540,7 @@ pub type Executive = frame_executive::Executive<
 	frame_system::ChainContext<Runtime>,
 	Runtime,
 	AllPallets,
+	pallet_kitties::migrations::MigrateFromOrml<Runtime>,
 >;
 
 impl_runtime_apis! {
@@ -487,6 +670,40 @@ impl_runtime_apis! {
 		}
 	}
 
+	impl pallet_kitties_runtime_api::KittiesApi<Block, AccountId, CollectionId, KittyIndex, Balance, BlockNumber> for Runtime {
+		fn collection(class_id: CollectionId) -> Option<pallet_kitties::KittyClassData> {
+			Kitties::collection(class_id)
+		}
+
+		fn kitty(class_id: CollectionId, kitty_id: KittyIndex) -> Option<pallet_kitties::Kitty> {
+			Kitties::kitty(class_id, kitty_id)
+		}
+
+		fn owner_of(class_id: CollectionId, kitty_id: KittyIndex) -> Option<AccountId> {
+			Kitties::owner_of(class_id, kitty_id)
+		}
+
+		fn price_of(class_id: CollectionId, kitty_id: KittyIndex) -> Option<Balance> {
+			Kitties::kitty_prices(class_id, kitty_id)
+		}
+
+		fn kitties_of(owner: AccountId, class_id: CollectionId, after: Option<KittyIndex>, limit: u32) -> pallet_kitties::KittiesPage<KittyIndex> {
+			Kitties::kitties_of(&owner, class_id, after, limit)
+		}
+
+		fn listings(class_id: CollectionId, start: u32, limit: u32) -> Vec<(KittyIndex, Balance)> {
+			Kitties::listings(class_id, start, limit)
+		}
+
+		fn floor_price(class_id: CollectionId) -> Option<Balance> {
+			Kitties::floor_price(class_id)
+		}
+
+		fn ownership_history(class_id: CollectionId, kitty_id: KittyIndex) -> Vec<pallet_kitties::OwnershipRecord<AccountId, Balance, BlockNumber>> {
+			Kitties::ownership_history(class_id, kitty_id)
+		}
+	}
+
 	#[cfg(feature = "runtime-benchmarks")]
 	impl frame_benchmarking::Benchmark<Block> for Runtime {
 		fn dispatch_benchmark(